@@ -1,3 +1,58 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
 fn main() {
+    // 便携版：按目标平台选择 UPX，压缩后内嵌
+    if env::var_os("CARGO_FEATURE_PORTABLE").is_some() {
+        embed_upx();
+    }
+
+    #[cfg(feature = "gui")]
     tauri_build::build()
 }
+
+/// 依次查找 `../upx/<target>/upx[.exe]`，Windows 目标再回退到 `../upx/upx.exe`
+fn find_upx_payload(target: &str) -> Option<PathBuf> {
+    let windows = target.contains("windows");
+    let file_name = if windows { "upx.exe" } else { "upx" };
+
+    let mut candidates = vec![Path::new("../upx").join(target).join(file_name)];
+    if windows {
+        candidates.push(PathBuf::from("../upx/upx.exe"));
+    }
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+/// 写入 `$OUT_DIR/upx.gz`，并导出解压后数据的 SHA-256 供运行时校验
+fn embed_upx() {
+    let target = env::var("TARGET").expect("TARGET 未设置");
+    println!("cargo:rerun-if-changed=../upx");
+
+    let payload = find_upx_payload(&target).unwrap_or_else(|| {
+        panic!(
+            "portable 特性需要目标平台的 UPX，请放到 upx/{}/ 目录下",
+            target
+        )
+    });
+    println!("cargo:rerun-if-changed={}", payload.display());
+
+    let data =
+        fs::read(&payload).unwrap_or_else(|e| panic!("无法读取 {}: {}", payload.display(), e));
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&data).expect("压缩 UPX 失败");
+    let compressed = encoder.finish().expect("压缩 UPX 失败");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR 未设置"));
+    fs::write(out_dir.join("upx.gz"), compressed).expect("写入 upx.gz 失败");
+
+    println!(
+        "cargo:rustc-env=EMBEDDED_UPX_SHA256={:x}",
+        Sha256::digest(&data)
+    );
+}
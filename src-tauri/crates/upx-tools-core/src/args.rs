@@ -0,0 +1,87 @@
+//! UPX 命令行参数构建
+
+use crate::naming::ConflictPolicy;
+use crate::options::{UpxMode, UpxOptions};
+
+/// `-o 输出文件`；只有冲突处理方式为覆盖时才允许 UPX 覆盖已有文件
+fn push_output_args(args: &mut Vec<String>, options: &UpxOptions, is_overwrite: bool) {
+    if is_overwrite {
+        return;
+    }
+    args.push("-o".to_string());
+    args.push(options.output_file.clone());
+    if options.on_conflict == ConflictPolicy::Overwrite {
+        args.push("--force-overwrite".to_string());
+    }
+}
+
+/// 加壳参数；`is_overwrite` 为 `false` 时追加 `-o 输出文件`
+pub fn build_compress_args(
+    options: &UpxOptions,
+    is_overwrite: bool,
+) -> impl Iterator<Item = String> + '_ {
+    let mut args = Vec::new();
+
+    // 压缩级别
+    if options.ultra_brute {
+        args.push("--ultra-brute".to_string());
+    } else if let "best" | "brute" = options.compression_level.as_str() {
+        args.push(format!("--{}", options.compression_level));
+    } else {
+        args.push(format!("-{}", options.compression_level));
+    }
+
+    // 压缩算法：LZMA 优先于 NRV 变体
+    if options.lzma {
+        args.push("--lzma".to_string());
+    } else if let Some(nrv) = options.nrv {
+        args.push(nrv.as_arg().to_string());
+    }
+
+    // 强制压缩
+    if options.force {
+        args.push("--force".to_string());
+    }
+
+    // 输入输出
+    args.push(options.input_file.clone());
+    push_output_args(&mut args, options, is_overwrite);
+
+    args.into_iter()
+}
+
+/// 脱壳参数
+pub fn build_decompress_args(
+    options: &UpxOptions,
+    is_overwrite: bool,
+) -> impl Iterator<Item = String> + '_ {
+    let mut args = vec!["-d".to_string(), options.input_file.clone()];
+
+    if options.force {
+        args.push("--force".to_string());
+    }
+
+    push_output_args(&mut args, options, is_overwrite);
+
+    args.into_iter()
+}
+
+/// 测试参数（`upx -t`）
+pub fn build_test_args(options: &UpxOptions) -> impl Iterator<Item = String> + '_ {
+    ["-t".to_string(), options.input_file.clone()].into_iter()
+}
+
+/// 列表参数（`upx -l`）
+pub fn build_list_args(options: &UpxOptions) -> impl Iterator<Item = String> + '_ {
+    ["-l".to_string(), options.input_file.clone()].into_iter()
+}
+
+/// 按模式构建参数
+pub fn build_args(options: &UpxOptions, is_overwrite: bool) -> Vec<String> {
+    match options.mode {
+        UpxMode::Compress => build_compress_args(options, is_overwrite).collect(),
+        UpxMode::Decompress => build_decompress_args(options, is_overwrite).collect(),
+        UpxMode::Test => build_test_args(options).collect(),
+        UpxMode::List => build_list_args(options).collect(),
+    }
+}
@@ -0,0 +1,210 @@
+//! 批量处理：并发调用加壳后端并汇总各状态的数量
+
+use crate::dedup;
+use crate::error::{CoreError, Result};
+use crate::options::UpxOptions;
+use crate::packer::Packer;
+use crate::process::run_upx;
+use crate::result::{UpxResult, UpxStatus};
+use crate::tune::Strategy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// 各状态的文件数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub success: usize,
+    pub skipped: usize,
+    pub warning: usize,
+    pub failed: usize,
+}
+
+impl BatchSummary {
+    pub fn record(&mut self, status: UpxStatus) {
+        match status {
+            UpxStatus::Success => self.success += 1,
+            UpxStatus::Skipped => self.skipped += 1,
+            UpxStatus::Warning => self.warning += 1,
+            UpxStatus::Failed => self.failed += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: BatchSummary) {
+        self.success += other.success;
+        self.skipped += other.skipped;
+        self.warning += other.warning;
+        self.failed += other.failed;
+    }
+
+    pub fn total(&self) -> usize {
+        self.success + self.skipped + self.warning + self.failed
+    }
+
+    /// 是否应视为失败：跳过永远不算，警告仅在 `fail_on_warning` 时算
+    pub fn has_failures(&self, fail_on_warning: bool) -> bool {
+        self.failed > 0 || (fail_on_warning && self.warning > 0)
+    }
+}
+
+/// 默认并发数：CPU 核心数
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// 找出输出路径与前面任务重复的任务，返回 `重复任务的下标 -> 第一个任务的下标`
+///
+/// 只检查会写入文件的任务；Windows 上路径不区分大小写。
+pub fn duplicate_outputs(jobs: &[UpxOptions]) -> HashMap<usize, usize> {
+    let mut first_by_output = HashMap::new();
+    let mut duplicates = HashMap::new();
+
+    for (index, job) in jobs.iter().enumerate() {
+        if job.mode.is_inspect() {
+            continue;
+        }
+        let key = output_key(&job.output_file);
+        match first_by_output.get(&key) {
+            Some(&first) => {
+                duplicates.insert(index, first);
+            }
+            None => {
+                first_by_output.insert(key, index);
+            }
+        }
+    }
+
+    duplicates
+}
+
+fn output_key(output: &str) -> PathBuf {
+    let path = Path::new(output);
+    // 文件还不存在时规范化所在目录，以识别 `a/../b.exe` 与 `b.exe` 这类写法
+    let normalized = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        })
+        .map(|p| p.join(name))
+        .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    };
+
+    if cfg!(windows) {
+        PathBuf::from(normalized.to_string_lossy().to_lowercase())
+    } else {
+        normalized
+    }
+}
+
+/// 将复制代表任务结果的任务，返回 `重复任务的下标 -> 代表任务的下标`
+///
+/// `duplicates` 为 [`duplicate_outputs`] 的结果：输出路径冲突的任务直接失败，不参与去重。
+pub fn identical_jobs(
+    jobs: &[UpxOptions],
+    strategies: &[Strategy],
+    duplicates: &HashMap<usize, usize>,
+) -> HashMap<usize, usize> {
+    dedup::duplicate_inputs(jobs, strategies)
+        .into_iter()
+        .filter(|(index, first)| !duplicates.contains_key(index) && !duplicates.contains_key(first))
+        .collect()
+}
+
+/// 用 `threads` 个线程处理全部任务
+///
+/// 每完成一个文件就在调用线程中回调一次 `on_result`（按完成顺序）。
+/// 预检失败的文件也会转换为 [`UpxStatus::Failed`] 的结果；与前面任务输出路径
+/// 相同的任务不会执行，直接记为失败。内容与处理方式都与前面任务相同的任务
+/// 等代表任务完成后再处理，直接复制其结果（见 [`dedup`](crate::dedup)）。
+pub fn run_batch<F>(
+    packer: &dyn Packer,
+    jobs: &[UpxOptions],
+    threads: usize,
+    mut on_result: F,
+) -> BatchSummary
+where
+    F: FnMut(&UpxOptions, UpxResult),
+{
+    let mut summary = BatchSummary::default();
+
+    let duplicates = duplicate_outputs(jobs);
+    let identical = identical_jobs(jobs, packer.strategies(), &duplicates);
+    let (first_pass, second_pass): (Vec<usize>, Vec<usize>) =
+        (0..jobs.len()).partition(|index| !identical.contains_key(index));
+    let wanted: HashSet<usize> = identical.values().copied().collect();
+
+    let mut finish = |index: usize, result: UpxResult| {
+        summary.record(result.status);
+        on_result(&jobs[index], result);
+    };
+
+    let mut representatives = HashMap::new();
+    run_parallel(
+        jobs,
+        &first_pass,
+        threads,
+        |index| match duplicates.get(&index) {
+            Some(&first) => Err(CoreError::DuplicateOutput(jobs[first].input_file.clone())),
+            None => run_upx(packer, &jobs[index]),
+        },
+        |index, result| {
+            if wanted.contains(&index) {
+                representatives.insert(index, result.clone());
+            }
+            finish(index, result);
+        },
+    );
+    run_parallel(
+        jobs,
+        &second_pass,
+        threads,
+        |index| {
+            let representative = &representatives[&identical[&index]];
+            dedup::run_duplicate(packer, &jobs[index], representative)
+        },
+        &mut finish,
+    );
+
+    summary
+}
+
+/// 并发处理 `indices` 中的任务，在调用线程中按完成顺序回调 `done`
+fn run_parallel<R, D>(jobs: &[UpxOptions], indices: &[usize], threads: usize, run: R, mut done: D)
+where
+    R: Fn(usize) -> Result<UpxResult> + Sync,
+    D: FnMut(usize, UpxResult),
+{
+    let threads = threads.clamp(1, indices.len().max(1));
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next, run) = (&next, &run);
+            scope.spawn(move || {
+                while let Some(&index) = indices.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if tx.send((index, run(index))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (index, result) in rx {
+            let job = &jobs[index];
+            let result = result.unwrap_or_else(|e| {
+                UpxResult::failed(&job.input_file, &job.output_file, e.to_string())
+            });
+            done(index, result);
+        }
+    });
+}
@@ -0,0 +1,486 @@
+//! 基准测试：对每个文件、压缩方式与 UPX 版本的组合，在临时副本上加壳、测试并脱壳，
+//! 记录输出大小、加壳与脱壳耗时以及 `upx -t` 的结果，用于选定默认的压缩参数
+//!
+//! 不同版本通过 [`UpxRegistry`](crate::registry::UpxRegistry) 按 [`UpxOptions::upx_version`] 选用，
+//! 原文件始终不会被修改。
+
+use crate::error::{CoreError, Result};
+use crate::history::csv_field;
+use crate::naming::ConflictPolicy;
+use crate::options::{UpxMode, UpxOptions};
+use crate::output::{classify_upx_exit, format_bytes};
+use crate::packer::{Packer, PackerOutput};
+use crate::result::UpxStatus;
+use crate::tune::Strategy;
+use crate::ManifestMode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+/// 一个组合：文件 × 压缩方式 × UPX 版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchCase {
+    pub file: String,
+    pub strategy: Strategy,
+    /// 为空时使用默认的 UPX
+    pub upx_version: Option<String>,
+}
+
+/// 按版本、压缩方式、文件的顺序列出全部组合；`versions` 为空时只用默认的 UPX
+pub fn bench_cases(
+    files: &[String],
+    strategies: &[Strategy],
+    versions: &[Option<String>],
+) -> Vec<BenchCase> {
+    let default = [None];
+    let versions = if versions.is_empty() {
+        &default[..]
+    } else {
+        versions
+    };
+
+    let mut cases = Vec::with_capacity(files.len() * strategies.len() * versions.len());
+    for version in versions {
+        for strategy in strategies {
+            for file in files {
+                cases.push(BenchCase {
+                    file: file.clone(),
+                    strategy: strategy.clone(),
+                    upx_version: version.clone(),
+                });
+            }
+        }
+    }
+    cases
+}
+
+/// 一个组合的测量结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchRecord {
+    pub file: String,
+    pub upx_version: Option<String>,
+    pub strategy: String,
+    /// 加壳的结果：成功、跳过（已加壳、无法压缩等）或失败
+    pub status: UpxStatus,
+    pub original_size: u64,
+    /// 加壳后的大小，未能加壳时为 0
+    pub output_size: u64,
+    /// 输出占原文件的百分比，未能加壳时为 0
+    pub ratio: f64,
+    pub pack_ms: u64,
+    /// `upx -t` 是否通过，未能加壳时为空
+    pub test_passed: Option<bool>,
+    /// 脱壳耗时，未能加壳或脱壳失败时为空
+    pub unpack_ms: Option<u64>,
+    /// 加壳、测试或脱壳失败的原因
+    pub error: Option<String>,
+}
+
+impl BenchRecord {
+    fn new(case: &BenchCase, original_size: u64) -> Self {
+        Self {
+            file: case.file.clone(),
+            upx_version: case.upx_version.clone(),
+            strategy: case.strategy.to_string(),
+            status: UpxStatus::Failed,
+            original_size,
+            output_size: 0,
+            ratio: 0.0,
+            pack_ms: 0,
+            test_passed: None,
+            unpack_ms: None,
+            error: None,
+        }
+    }
+
+    /// 加壳、测试与脱壳都成功
+    pub fn is_ok(&self) -> bool {
+        self.status == UpxStatus::Success && self.test_passed == Some(true) && self.error.is_none()
+    }
+}
+
+/// 在 `dir` 中的副本上处理一个组合
+fn run_case(packer: &dyn Packer, case: &BenchCase, dir: &Path) -> BenchRecord {
+    let name = Path::new(&case.file)
+        .file_name()
+        .unwrap_or("input".as_ref());
+    let copy = dir.join(name);
+    let copied = fs::create_dir_all(dir).and_then(|_| fs::copy(&case.file, &copy));
+    let mut record = BenchRecord::new(case, *copied.as_ref().unwrap_or(&0));
+    if let Err(e) = copied {
+        record.error = Some(CoreError::Benchmark(e).to_string());
+        return record;
+    }
+
+    let copy = copy.to_string_lossy().into_owned();
+    let options = case.strategy.apply(&UpxOptions {
+        mode: UpxMode::Compress,
+        input_file: copy.clone(),
+        output_file: copy.clone(),
+        compression_level: String::new(),
+        backup: false,
+        lzma: false,
+        nrv: None,
+        ultra_brute: false,
+        force: false,
+        upx_version: case.upx_version.clone(),
+        on_conflict: ConflictPolicy::Overwrite,
+        manifest: ManifestMode::Off,
+        budget: None,
+    });
+    let classify = |mode, output: Result<PackerOutput>| match output {
+        Ok(output) => classify_upx_exit(mode, output.exit_code, &output.stdout, &output.stderr),
+        Err(e) => (UpxStatus::Failed, Some(e.to_string())),
+    };
+
+    let started = Instant::now();
+    let (status, reason) = classify(UpxMode::Compress, packer.compress(&options, true));
+    record.pack_ms = started.elapsed().as_millis() as u64;
+    record.status = match status {
+        UpxStatus::Warning => UpxStatus::Success,
+        status => status,
+    };
+    if record.status != UpxStatus::Success {
+        record.error = reason;
+        return record;
+    }
+    record.output_size = fs::metadata(&copy).map_or(0, |m| m.len());
+    if record.original_size > 0 {
+        record.ratio = record.output_size as f64 / record.original_size as f64 * 100.0;
+    }
+
+    let (status, reason) = classify(
+        UpxMode::Test,
+        packer.test(&UpxOptions {
+            mode: UpxMode::Test,
+            ..options.clone()
+        }),
+    );
+    record.test_passed = Some(status == UpxStatus::Success);
+    if status != UpxStatus::Success {
+        record.error = Some(format!("未通过 upx -t: {}", reason.unwrap_or_default()));
+    }
+
+    let started = Instant::now();
+    let (status, reason) = classify(
+        UpxMode::Decompress,
+        packer.decompress(
+            &UpxOptions {
+                mode: UpxMode::Decompress,
+                ..options
+            },
+            true,
+        ),
+    );
+    if status == UpxStatus::Success {
+        record.unpack_ms = Some(started.elapsed().as_millis() as u64);
+    } else if record.error.is_none() {
+        record.error = Some(format!("脱壳失败: {}", reason.unwrap_or_default()));
+    }
+    record
+}
+
+/// 用 `threads` 个线程处理全部组合，每完成一个在调用线程中回调一次 `on_record`
+///
+/// 并发会让各组合互相争用 CPU，需要准确的耗时时应使用 1 个线程。
+/// 结果按完成顺序回调，返回值按 `cases` 的顺序排列。
+pub fn run_benchmark<F>(
+    packer: &dyn Packer,
+    cases: &[BenchCase],
+    threads: usize,
+    mut on_record: F,
+) -> Result<Vec<BenchRecord>>
+where
+    F: FnMut(&BenchRecord),
+{
+    let temp = tempfile::Builder::new()
+        .prefix("upx-tools-bench")
+        .tempdir()
+        .map_err(CoreError::Benchmark)?;
+    let threads = threads.clamp(1, cases.len().max(1));
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    let mut records: Vec<Option<BenchRecord>> = vec![None; cases.len()];
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next, temp) = (&next, temp.path());
+            scope.spawn(move || {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(case) = cases.get(index) else {
+                        break;
+                    };
+                    let dir = temp.join(index.to_string());
+                    let record = run_case(packer, case, &dir);
+                    // 每个组合用完即删除副本，避免大批量测试占满临时目录
+                    let _ = fs::remove_dir_all(&dir);
+                    if tx.send((index, record)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (index, record) in rx {
+            on_record(&record);
+            records[index] = Some(record);
+        }
+    });
+
+    Ok(records.into_iter().flatten().collect())
+}
+
+// ============================================================================
+// 汇总
+// ============================================================================
+
+/// 一个 UPX 版本与压缩方式在全部文件上的合计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchTotal {
+    pub upx_version: Option<String>,
+    pub strategy: String,
+    pub files: usize,
+    /// 加壳、测试或脱壳失败的文件数
+    pub failed: usize,
+    /// 以下合计只统计加壳成功的文件
+    pub original_size: u64,
+    pub output_size: u64,
+    pub ratio: f64,
+    pub pack_ms: u64,
+    pub unpack_ms: u64,
+}
+
+/// 按版本与压缩方式汇总，保持首次出现的顺序
+pub fn summarize(records: &[BenchRecord]) -> Vec<BenchTotal> {
+    let mut totals: Vec<BenchTotal> = Vec::new();
+    for record in records {
+        let index = match totals
+            .iter()
+            .position(|t| t.upx_version == record.upx_version && t.strategy == record.strategy)
+        {
+            Some(index) => index,
+            None => {
+                totals.push(BenchTotal {
+                    upx_version: record.upx_version.clone(),
+                    strategy: record.strategy.clone(),
+                    files: 0,
+                    failed: 0,
+                    original_size: 0,
+                    output_size: 0,
+                    ratio: 0.0,
+                    pack_ms: 0,
+                    unpack_ms: 0,
+                });
+                totals.len() - 1
+            }
+        };
+        let total = &mut totals[index];
+        total.files += 1;
+        if !record.is_ok() && record.status != UpxStatus::Skipped {
+            total.failed += 1;
+        }
+        if record.status == UpxStatus::Success {
+            total.original_size += record.original_size;
+            total.output_size += record.output_size;
+            total.pack_ms += record.pack_ms;
+            total.unpack_ms += record.unpack_ms.unwrap_or_default();
+        }
+    }
+    for total in &mut totals {
+        if total.original_size > 0 {
+            total.ratio = total.output_size as f64 / total.original_size as f64 * 100.0;
+        }
+    }
+    totals
+}
+
+// ============================================================================
+// 输出格式
+// ============================================================================
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl BenchFormat {
+    /// 按扩展名选择：`.csv`、`.json`，其余为文本表格
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match ext.as_deref() {
+            Some("csv") => Self::Csv,
+            Some("json") => Self::Json,
+            _ => Self::Table,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "file,upx_version,strategy,status,original_size,output_size,ratio,\
+pack_ms,unpack_ms,test_passed,error";
+
+/// 每个组合一行，以 UTF-8 BOM 开头
+pub fn render_csv(records: &[BenchRecord], default_version: &str) -> String {
+    let mut csv = format!("\u{feff}{}\n", CSV_HEADER);
+    for record in records {
+        let fields = [
+            record.file.clone(),
+            record
+                .upx_version
+                .clone()
+                .unwrap_or_else(|| default_version.to_string()),
+            record.strategy.clone(),
+            record.status.as_str().to_string(),
+            record.original_size.to_string(),
+            record.output_size.to_string(),
+            format!("{:.2}", record.ratio),
+            record.pack_ms.to_string(),
+            record
+                .unpack_ms
+                .map(|ms| ms.to_string())
+                .unwrap_or_default(),
+            record
+                .test_passed
+                .map(|passed| passed.to_string())
+                .unwrap_or_default(),
+            record.error.clone().unwrap_or_default(),
+        ];
+        let line: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// 按版本与压缩方式的合计表，以及每个组合的明细表
+pub fn render_table(records: &[BenchRecord], default_version: &str) -> String {
+    let version = |v: &Option<String>| v.clone().unwrap_or_else(|| default_version.to_string());
+    let mut text = String::new();
+
+    let totals: Vec<Vec<String>> = summarize(records)
+        .iter()
+        .map(|t| {
+            vec![
+                version(&t.upx_version),
+                t.strategy.clone(),
+                t.files.to_string(),
+                t.failed.to_string(),
+                format_bytes(t.output_size),
+                format!("{:.1}%", t.ratio),
+                format!("{} ms", t.pack_ms),
+                format!("{} ms", t.unpack_ms),
+            ]
+        })
+        .collect();
+    text.push_str("合计（只统计加壳成功的文件）:\n");
+    text.push_str(&table(
+        &[
+            "UPX",
+            "压缩方式",
+            "文件",
+            "失败",
+            "加壳后",
+            "压缩率",
+            "加壳耗时",
+            "脱壳耗时",
+        ],
+        &totals,
+    ));
+
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|r| {
+            let test = match (r.status, r.test_passed) {
+                (UpxStatus::Skipped, _) => "跳过",
+                (_, Some(true)) => "通过",
+                _ => "失败",
+            };
+            vec![
+                r.file.clone(),
+                version(&r.upx_version),
+                r.strategy.clone(),
+                format_bytes(r.original_size),
+                if r.status == UpxStatus::Success {
+                    format_bytes(r.output_size)
+                } else {
+                    "-".to_string()
+                },
+                if r.status == UpxStatus::Success {
+                    format!("{:.1}%", r.ratio)
+                } else {
+                    "-".to_string()
+                },
+                format!("{} ms", r.pack_ms),
+                r.unpack_ms
+                    .map_or("-".to_string(), |ms| format!("{} ms", ms)),
+                test.to_string(),
+            ]
+        })
+        .collect();
+    text.push_str("\n明细:\n");
+    text.push_str(&table(
+        &[
+            "文件",
+            "UPX",
+            "压缩方式",
+            "原始",
+            "加壳后",
+            "压缩率",
+            "加壳",
+            "脱壳",
+            "测试",
+        ],
+        &rows,
+    ));
+    text
+}
+
+/// 按显示宽度对齐的文本表格，中日韩字符按两列计算
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let width = |s: &str| -> usize { s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum() };
+    let mut widths: Vec<usize> = header.iter().map(|h| width(h)).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(width(cell));
+        }
+    }
+
+    let mut text = String::new();
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!("{}{}", cell, " ".repeat(w - width(cell))))
+            .collect();
+        let _ = writeln!(text, "{}", cells.join("  ").trim_end());
+    }
+    text
+}
+
+/// 按格式生成并写入结果
+pub fn export_benchmark(
+    records: &[BenchRecord],
+    default_version: &str,
+    path: &Path,
+    format: BenchFormat,
+) -> Result<()> {
+    let content = match format {
+        BenchFormat::Table => render_table(records, default_version),
+        BenchFormat::Csv => render_csv(records, default_version),
+        BenchFormat::Json => serde_json::to_string_pretty(records)
+            .map_err(|e| CoreError::BenchmarkExport(e.into()))?,
+    };
+    fs::write(path, content).map_err(CoreError::BenchmarkExport)
+}
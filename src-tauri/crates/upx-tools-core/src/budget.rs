@@ -0,0 +1,157 @@
+//! 体积预算：任务声明输出的最大大小或压缩率，用于 CI 中的硬性体积限制
+//!
+//! 带预算的加壳任务由 [`AutoTuner`](crate::tune::AutoTuner) 按耗时从短到长逐个尝试候选策略，
+//! 第一个通过测试且满足预算的结果即被采用；都不满足时任务失败，并记录能达到的最小大小。
+
+use crate::error::{CoreError, Result};
+use crate::output::format_bytes;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 未满足预算时失败原因的开头
+pub const BUDGET_NOT_MET: &str = "[错误] 未达到体积预算";
+
+/// 输出的大小上限
+///
+/// 以字符串保存与传递：`500000`、`512KB`、`1.5MB` 或 `60%`（相对原文件）；
+/// 读取时也接受表示字节数的整数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawBudget", into = "String")]
+pub enum SizeBudget {
+    /// 最大字节数
+    MaxBytes(u64),
+    /// 输出与原文件大小之比的上限（百分比）
+    MaxRatio(u32),
+}
+
+impl SizeBudget {
+    /// 原文件为 `original_size` 字节时允许的最大输出
+    pub fn limit(self, original_size: u64) -> u64 {
+        match self {
+            Self::MaxBytes(bytes) => bytes,
+            Self::MaxRatio(percent) => original_size * u64::from(percent) / 100,
+        }
+    }
+
+    pub fn fits(self, original_size: u64, output_size: u64) -> bool {
+        output_size <= self.limit(original_size)
+    }
+
+    /// 面向用户的写法，例如 `≤ 512.00 KB`、`≤ 60%`
+    pub fn describe(self) -> String {
+        match self {
+            Self::MaxBytes(bytes) => format!("≤ {}", format_bytes(bytes)),
+            Self::MaxRatio(percent) => format!("≤ {}%", percent),
+        }
+    }
+}
+
+/// 保存时使用的写法：能整除时带单位，例如 `512KB`、`2MB`，否则为字节数
+impl fmt::Display for SizeBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u64, &str); 3] = [(1 << 30, "GB"), (1 << 20, "MB"), (1 << 10, "KB")];
+        match *self {
+            Self::MaxBytes(bytes) => match UNITS.iter().find(|(size, _)| bytes % size == 0) {
+                Some((size, unit)) => write!(f, "{}{}", bytes / size, unit),
+                None => write!(f, "{}", bytes),
+            },
+            Self::MaxRatio(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl FromStr for SizeBudget {
+    type Err = CoreError;
+
+    /// 单位不区分大小写，按 1024 进位（与 [`format_bytes`] 一致）
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || CoreError::InvalidBudget(s.to_string());
+        let text = s.trim();
+
+        if let Some(percent) = text.strip_suffix('%') {
+            return match percent.trim().parse::<u32>() {
+                Ok(percent @ 1..=100) => Ok(Self::MaxRatio(percent)),
+                _ => Err(invalid()),
+            };
+        }
+
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1024,
+            "m" | "mb" | "mib" => 1024 * 1024,
+            "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+            _ => return Err(invalid()),
+        };
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let bytes = (number * multiplier as f64).round();
+        if !bytes.is_finite() || bytes < 1.0 {
+            return Err(invalid());
+        }
+        Ok(Self::MaxBytes(bytes as u64))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawBudget {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<RawBudget> for SizeBudget {
+    type Error = CoreError;
+
+    fn try_from(value: RawBudget) -> Result<Self> {
+        match value {
+            RawBudget::Bytes(0) => Err(CoreError::InvalidBudget("0".to_string())),
+            RawBudget::Bytes(bytes) => Ok(Self::MaxBytes(bytes)),
+            RawBudget::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<SizeBudget> for String {
+    fn from(budget: SizeBudget) -> Self {
+        budget.to_string()
+    }
+}
+
+/// 带预算的任务的检查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetCheck {
+    pub budget: SizeBudget,
+    /// 按原文件大小换算出的最大字节数
+    pub limit_bytes: u64,
+    pub met: bool,
+    /// 通过测试的结果中最小的大小；没有策略通过测试时为空
+    pub best_size: Option<u64>,
+    pub best_strategy: Option<String>,
+}
+
+impl BudgetCheck {
+    /// 未满足预算时的失败原因
+    pub fn failure_reason(&self) -> String {
+        let best = match (self.best_size, &self.best_strategy) {
+            (Some(size), Some(strategy)) => format!("{}（{}）", format_bytes(size), strategy),
+            (Some(size), None) => format_bytes(size),
+            _ => "无（没有压缩方式通过测试）".to_string(),
+        };
+        let budget = match self.budget {
+            SizeBudget::MaxBytes(_) => self.budget.describe(),
+            SizeBudget::MaxRatio(_) => format!(
+                "{}（{}）",
+                self.budget.describe(),
+                format_bytes(self.limit_bytes)
+            ),
+        };
+        format!(
+            "{} {}\n\n能达到的最小大小: {}\n\n解决方案:\n  - 在候选压缩方式中加入更慢的方式，例如 --best --lzma、--brute\n  - 或放宽体积预算",
+            BUDGET_NOT_MET, budget, best
+        )
+    }
+}
@@ -0,0 +1,325 @@
+//! 加壳结果缓存：按输入文件哈希、压缩参数与 UPX 版本复用之前的输出
+//!
+//! 同一个第三方 DLL 在多个产品中重复加壳时，`--ultra-brute` 的耗时只需付出一次。
+//! 缓存默认关闭，由 [`CachedPacker`] 包装加壳后端接入；超过容量上限时淘汰最久未使用的条目。
+
+use crate::error::{CoreError, Result};
+use crate::hash::{sha256_file, sha256_hex};
+use crate::options::UpxOptions;
+use crate::output::UPX_EXIT_OK;
+use crate::packer::{Packer, PackerOutput};
+use crate::persist::{self, FileLock};
+use crate::report::method;
+use crate::UpxCapabilities;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 默认容量上限（MB）
+pub const DEFAULT_CACHE_MAX_MB: u64 = 2048;
+
+const INDEX_FILE_NAME: &str = "index.json";
+const OBJECTS_DIR: &str = "objects";
+
+/// 批量处理时多个任务同时读写索引，逐个进行
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 默认的缓存目录：`<本地数据目录>/UPX-Tools/cache`
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("UPX-Tools").join("cache"))
+}
+
+/// 缓存键：输入内容、规范化后的压缩参数（与路径、备份等无关）与 UPX 版本
+///
+/// 体积预算只决定选用哪个候选，不影响单次加壳的输出，因此不计入；
+/// 否则压缩方式只记为预算，不同压缩参数的输出会共用一个缓存键。
+pub fn cache_key(input_sha256: &str, options: &UpxOptions, upx_version: &str) -> String {
+    let packed_with = UpxOptions {
+        budget: None,
+        ..options.clone()
+    };
+    let material = format!(
+        "{}\n{}\n{}",
+        input_sha256,
+        method(&packed_with),
+        upx_version
+    );
+    sha256_hex(material.as_bytes())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    last_used: DateTime<Local>,
+}
+
+type CacheIndex = BTreeMap<String, CacheEntry>;
+
+/// 缓存占用情况
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CacheInfo {
+    pub path: String,
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+}
+
+/// 缓存目录
+#[derive(Debug, Clone)]
+pub struct PackCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl PackCache {
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+        }
+    }
+
+    /// 应用数据目录下的缓存
+    pub fn open_default(max_bytes: u64) -> Result<Self> {
+        cache_dir()
+            .map(|dir| Self::new(dir, max_bytes))
+            .ok_or(CoreError::DataDirUnavailable)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn object_path(&self, key: &str) -> PathBuf {
+        self.dir.join(OBJECTS_DIR).join(key)
+    }
+
+    /// 锁文件放在缓存目录之外，清空缓存时可以整个删除目录
+    fn lock_path(&self) -> PathBuf {
+        self.dir.with_extension("lock")
+    }
+
+    /// 独占缓存索引：同一进程的线程之间用 [`CACHE_LOCK`]，GUI 与命令行等多个进程之间用锁文件
+    fn lock(&self) -> Result<FileLock> {
+        persist::lock(&self.lock_path(), &CACHE_LOCK).map_err(CoreError::CacheWrite)
+    }
+
+    /// 索引不存在或损坏时视为空缓存
+    fn load_index(&self) -> CacheIndex {
+        fs::read_to_string(self.dir.join(INDEX_FILE_NAME))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_index(&self, index: &CacheIndex) -> Result<()> {
+        let content = serde_json::to_string(index).map_err(|e| CoreError::CacheWrite(e.into()))?;
+        persist::write_atomic(&self.dir.join(INDEX_FILE_NAME), content.as_bytes())
+            .map_err(CoreError::CacheWrite)
+    }
+
+    pub fn get(&self, key: &str, dest: &Path) -> Result<bool> {
+        let _lock = self.lock()?;
+        let mut index = self.load_index();
+        let Some(entry) = index.get_mut(key) else {
+            return Ok(false);
+        };
+
+        match fs::copy(self.object_path(key), dest) {
+            Ok(_) => {
+                entry.last_used = Local::now();
+                self.save_index(&index)?;
+                Ok(true)
+            }
+            // 文件被手动删除时丢弃这条记录
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                index.remove(key);
+                self.save_index(&index)?;
+                Ok(false)
+            }
+            Err(e) => Err(CoreError::CacheRead(e)),
+        }
+    }
+
+    /// 保存加壳结果，超过容量上限时淘汰最久未使用的条目；比上限还大的文件不缓存
+    pub fn put(&self, key: &str, source: &Path) -> Result<()> {
+        let size = fs::metadata(source).map_err(CoreError::CacheRead)?.len();
+        if size > self.max_bytes {
+            return Ok(());
+        }
+
+        let _lock = self.lock()?;
+        let object = self.object_path(key);
+        fs::create_dir_all(self.dir.join(OBJECTS_DIR)).map_err(CoreError::CacheWrite)?;
+        // 先写临时文件再改名，避免中断后留下不完整的条目
+        let partial = object.with_extension("partial");
+        fs::copy(source, &partial)
+            .and_then(|_| fs::rename(&partial, &object))
+            .map_err(CoreError::CacheWrite)?;
+
+        let mut index = self.load_index();
+        index.insert(
+            key.to_string(),
+            CacheEntry {
+                size,
+                last_used: Local::now(),
+            },
+        );
+        self.evict(&mut index);
+        self.save_index(&index)
+    }
+
+    fn evict(&self, index: &mut CacheIndex) {
+        let mut total: u64 = index.values().map(|e| e.size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        let mut by_age: Vec<_> = index
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+        by_age.sort();
+        for (_, key) in by_age {
+            if total <= self.max_bytes {
+                break;
+            }
+            if let Some(entry) = index.remove(&key) {
+                total -= entry.size;
+                let _ = fs::remove_file(self.object_path(&key));
+            }
+        }
+    }
+
+    /// 在共享锁下读取索引，不会读到其他进程写了一半的状态；无法加锁时仍然读取
+    pub fn info(&self) -> CacheInfo {
+        let _lock = persist::lock_shared(&self.lock_path(), &CACHE_LOCK);
+        self.read_info()
+    }
+
+    fn read_info(&self) -> CacheInfo {
+        let index = self.load_index();
+        CacheInfo {
+            path: self.dir.display().to_string(),
+            entries: index.len(),
+            total_bytes: index.values().map(|e| e.size).sum(),
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    /// 删除全部缓存，返回清空前的占用情况
+    pub fn clear(&self) -> Result<CacheInfo> {
+        let _lock = self.lock()?;
+        let info = self.read_info();
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(CoreError::CacheWrite(e)),
+            _ => Ok(info),
+        }
+    }
+}
+
+// ============================================================================
+// 带缓存的后端
+// ============================================================================
+
+/// 加壳时先查缓存的后端，其余操作直接交给内部后端；没有缓存时完全透明
+pub struct CachedPacker<P> {
+    inner: P,
+    cache: Option<PackCache>,
+}
+
+impl<P: Packer> CachedPacker<P> {
+    pub fn new(inner: P, cache: Option<PackCache>) -> Self {
+        Self { inner, cache }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// 任务指定的版本标签，未指定时为默认 UPX 的版本号
+    fn version_label(&self, options: &UpxOptions) -> Result<String> {
+        if let Some(version) = &options.upx_version {
+            return Ok(version.clone());
+        }
+        let capabilities = self.inner.capabilities()?;
+        Ok(capabilities
+            .version
+            .map(|v| v.to_string())
+            .unwrap_or(capabilities.version_line))
+    }
+}
+
+impl<P: Packer> Packer for CachedPacker<P> {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        // 未经自动选择展开的 auto 没有确定的压缩参数，不查缓存
+        let Some(cache) = self.cache.as_ref().filter(|_| !options.is_auto()) else {
+            return self.inner.compress(options, is_overwrite);
+        };
+        // 原地加壳会替换输入文件，必须在运行 UPX 之前计算
+        let Ok(input_sha256) = sha256_file(&options.input_file) else {
+            return self.inner.compress(options, is_overwrite);
+        };
+        let key = cache_key(&input_sha256, options, &self.version_label(options)?);
+        let output = Path::new(if is_overwrite {
+            &options.input_file
+        } else {
+            &options.output_file
+        });
+
+        // 缓存读写失败按未命中处理，不影响加壳
+        if let Ok(true) = cache.get(&key, output) {
+            return Ok(PackerOutput {
+                exit_code: Some(UPX_EXIT_OK),
+                stdout: format!("[缓存] 已复用缓存的加壳结果，未运行 UPX（{}）", &key[..16]),
+                stderr: String::new(),
+                strategies: Vec::new(),
+                budget: None,
+            });
+        }
+
+        let mut result = self.inner.compress(options, is_overwrite)?;
+        if result.exit_code == Some(UPX_EXIT_OK) {
+            if let Err(e) = cache.put(&key, output) {
+                result.stdout.push_str(&format!("\n[提示] {}", e));
+            }
+        }
+        Ok(result)
+    }
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.inner.decompress(options, is_overwrite)
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.test(options)
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.list(options)
+    }
+
+    fn version(&self) -> Result<String> {
+        self.inner.version()
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.inner.capabilities()
+    }
+
+    fn capabilities_for(&self, options: &UpxOptions) -> Result<UpxCapabilities> {
+        self.inner.capabilities_for(options)
+    }
+
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        self.inner.command_line(options, is_overwrite)
+    }
+
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        self.inner.is_packed(path)
+    }
+}
@@ -0,0 +1,160 @@
+//! 解析 `upx --version` 与 `upx --help`，得到版本信息和支持的格式、选项
+//!
+//! 探测结果按可执行文件的路径、修改时间和大小缓存在进程内，同一个 UPX
+//! 只会启动一次 `--version`/`--help`，替换文件后自动重新探测。
+
+use crate::error::{CoreError, Result};
+use crate::locate::UpxVersion;
+use crate::process::create_silent_command;
+use encoding_rs::GBK;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// UPX 的版本与功能
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpxCapabilities {
+    /// `upx --version` 的第一行，例如 `upx 4.2.4`
+    pub version_line: String,
+    pub version: Option<UpxVersion>,
+    /// 各压缩库的版本，例如 UCL `1.03`、zlib `1.3.1.1-motley`、LZMA `4.43`
+    pub ucl: Option<String>,
+    pub zlib: Option<String>,
+    pub lzma: Option<String>,
+    /// 支持的可执行文件格式，例如 `win64/pe`
+    pub formats: Vec<String>,
+    /// 支持的长选项，例如 `--lzma`；为空表示未能读取帮助信息
+    pub options: BTreeSet<String>,
+}
+
+impl UpxCapabilities {
+    /// 由两条命令的输出解析
+    pub fn parse(version_output: &str, help_output: &str) -> Self {
+        let mut lines = version_output.lines().map(str::trim);
+        let version_line = lines.next().unwrap_or_default().to_string();
+
+        let mut capabilities = Self {
+            version: UpxVersion::parse(&version_line),
+            version_line,
+            ..Self::default()
+        };
+
+        for line in lines {
+            let last_word = || line.split_whitespace().last().map(str::to_string);
+            if line.starts_with("UCL data compression library") {
+                capabilities.ucl = last_word();
+            } else if line.starts_with("zlib data compression library") {
+                capabilities.zlib = last_word();
+            } else if line.starts_with("LZMA SDK version") {
+                capabilities.lzma = last_word();
+            }
+        }
+
+        capabilities.formats = parse_formats(help_output);
+        capabilities.options = parse_options(help_output);
+        capabilities
+    }
+
+    /// 是否支持某个长选项；未能读取帮助信息时不做限制
+    pub fn supports(&self, option: &str) -> bool {
+        self.options.is_empty() || self.options.contains(option)
+    }
+}
+
+/// `This version supports:` 之后缩进的每一行，取最后一列，例如 `win64/pe`
+fn parse_formats(help: &str) -> Vec<String> {
+    let mut formats = Vec::new();
+    let mut in_list = false;
+
+    for line in help.lines() {
+        if line.trim_start().starts_with("This version supports") {
+            in_list = true;
+            continue;
+        }
+        if !in_list {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+            // 列表之后的第一个非缩进行表示列表结束
+            if !formats.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if let Some(format) = line.split_whitespace().last() {
+            formats.push(format.to_string());
+        }
+    }
+
+    formats
+}
+
+/// 帮助信息中出现的所有 `--xxx` 选项
+fn parse_options(help: &str) -> BTreeSet<String> {
+    help.split(|c: char| c.is_whitespace() || matches!(c, '[' | ']' | ',' | '(' | ')'))
+        .filter_map(|token| {
+            let name = token.strip_prefix("--")?;
+            let name: String = name
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect();
+            name.starts_with(|c: char| c.is_ascii_alphabetic())
+                .then(|| format!("--{}", name))
+        })
+        .collect()
+}
+
+// ============================================================================
+// 探测与缓存
+// ============================================================================
+
+/// 可执行文件的修改时间与大小，变化时重新探测
+type FileStamp = (Option<SystemTime>, u64);
+
+type CapabilityCache = HashMap<PathBuf, (FileStamp, UpxCapabilities)>;
+
+static CACHE: OnceLock<Mutex<CapabilityCache>> = OnceLock::new();
+
+/// 探测指定 UPX 的功能，结果在进程内缓存
+pub fn probe_capabilities(path: &Path) -> Result<UpxCapabilities> {
+    let stamp = fs::metadata(path)
+        .map(|m| (m.modified().ok(), m.len()))
+        .map_err(CoreError::UpxNotExecutable)?;
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some((cached_stamp, capabilities)) = cache.lock().ok().and_then(|c| c.get(path).cloned())
+    {
+        if cached_stamp == stamp {
+            return Ok(capabilities);
+        }
+    }
+
+    let capabilities = run_probe(path)?;
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(path.to_path_buf(), (stamp, capabilities.clone()));
+    }
+    Ok(capabilities)
+}
+
+fn run_probe(path: &Path) -> Result<UpxCapabilities> {
+    let output = create_silent_command(path)
+        .arg("--version")
+        .output()
+        .map_err(CoreError::UpxNotExecutable)?;
+    let (version_output, _, _) = GBK.decode(&output.stdout);
+    if version_output.trim().is_empty() {
+        return Err(CoreError::UpxVersionUnavailable);
+    }
+
+    // 帮助信息只用于判断支持的选项，读取失败不影响使用
+    let help_output = create_silent_command(path)
+        .arg("--help")
+        .output()
+        .map(|o| GBK.decode(&o.stdout).0.into_owned())
+        .unwrap_or_default();
+
+    Ok(UpxCapabilities::parse(&version_output, &help_output))
+}
@@ -0,0 +1,146 @@
+//! 配置持久化，GUI 与命令行读写同一个 `upx_gui_config.json`
+
+use crate::budget::SizeBudget;
+use crate::cache::DEFAULT_CACHE_MAX_MB;
+use crate::error::{CoreError, Result};
+use crate::manifest::ManifestMode;
+use crate::naming::ConflictPolicy;
+use crate::registry::RegisteredUpx;
+use crate::tune::DEFAULT_STRATEGIES;
+use crate::upx_update::DEFAULT_UPX_RELEASE_API;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppConfig {
+    /// 1-9，10 表示 best
+    pub compression_level: i32,
+    pub overwrite: bool,
+    pub backup: bool,
+    pub lzma: bool,
+    pub ultra_brute: bool,
+    pub include_subfolders: bool,
+    pub force_compress: bool,
+    #[serde(default = "default_auto_check_update")]
+    pub auto_check_update: bool,
+    /// 指定 UPX 可执行文件，为空时按 PATH、程序目录、内嵌副本的顺序查找
+    #[serde(default)]
+    pub upx_path: Option<String>,
+    /// 额外注册的 UPX，可按版本号选用
+    #[serde(default)]
+    pub upx_binaries: Vec<RegisteredUpx>,
+    /// 默认使用的已注册版本，为空时使用自动查找到的 UPX
+    #[serde(default)]
+    pub upx_version: Option<String>,
+    /// 不覆盖原文件时，批量结果按源目录结构写入此目录；为空时另存为 `*_packed.*`
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// 输出目录模式下，把不处理的文件也复制过去
+    #[serde(default)]
+    pub copy_unsupported: bool,
+    /// 不覆盖原文件时的输出文件名模板，例如 `{stem}.packed.{ext}`；
+    /// 为空时图形界面逐个选择保存位置，命令行使用 `{stem}_packed.{ext}`
+    #[serde(default)]
+    pub name_template: Option<String>,
+    /// 输出文件已存在时的处理方式
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    /// 加壳成功后写入清单的方式
+    #[serde(default)]
+    pub manifest: ManifestMode,
+    /// 启用加壳结果缓存
+    #[serde(default)]
+    pub cache_enabled: bool,
+    /// 加壳缓存的容量上限（MB）
+    #[serde(default = "default_cache_max_mb")]
+    pub cache_max_mb: u64,
+    /// 批量处理文件夹时跳过自上次处理后未变化的文件
+    #[serde(default)]
+    pub incremental: bool,
+    /// 加壳时自动选择压缩方式，代替 `compression_level`
+    #[serde(default)]
+    pub auto_tune: bool,
+    /// 自动选择时尝试的压缩方式，例如 `--best --lzma`；为空时使用默认策略
+    #[serde(default = "default_tune_strategies")]
+    pub tune_strategies: Vec<String>,
+    /// 加壳输出的体积预算，例如 `512KB` 或 `60%`；为空时不限制
+    #[serde(default)]
+    pub size_budget: Option<SizeBudget>,
+    /// UPX 发行信息接口，默认为 GitHub 上的 upx/upx，可指向镜像或本地测试服务
+    #[serde(default = "default_upx_release_api")]
+    pub upx_release_api: String,
+}
+
+fn default_auto_check_update() -> bool {
+    true
+}
+
+fn default_cache_max_mb() -> u64 {
+    DEFAULT_CACHE_MAX_MB
+}
+
+fn default_tune_strategies() -> Vec<String> {
+    DEFAULT_STRATEGIES.iter().map(|s| s.to_string()).collect()
+}
+
+fn default_upx_release_api() -> String {
+    DEFAULT_UPX_RELEASE_API.to_string()
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            compression_level: 9,
+            overwrite: true,
+            backup: false,
+            lzma: false,
+            ultra_brute: false,
+            include_subfolders: false,
+            force_compress: false,
+            auto_check_update: true,
+            upx_path: None,
+            upx_binaries: Vec::new(),
+            upx_version: None,
+            output_dir: None,
+            copy_unsupported: false,
+            name_template: None,
+            on_conflict: ConflictPolicy::default(),
+            manifest: ManifestMode::default(),
+            cache_enabled: false,
+            cache_max_mb: DEFAULT_CACHE_MAX_MB,
+            incremental: false,
+            auto_tune: false,
+            tune_strategies: default_tune_strategies(),
+            size_budget: None,
+            upx_release_api: default_upx_release_api(),
+        }
+    }
+}
+
+/// 配置文件位于程序所在目录
+pub fn get_config_path() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .parent()
+        .map(|p| p.join("upx_gui_config.json"))
+}
+
+pub fn save_config(config: &AppConfig) -> Result<()> {
+    let config_path = get_config_path().ok_or(CoreError::ConfigPathUnavailable)?;
+    let json = serde_json::to_string_pretty(config).map_err(CoreError::ConfigSerialize)?;
+    fs::write(&config_path, json).map_err(CoreError::ConfigWrite)
+}
+
+/// 配置文件不存在时返回默认配置
+pub fn load_config() -> Result<AppConfig> {
+    let config_path = get_config_path().ok_or(CoreError::ConfigPathUnavailable)?;
+
+    if !config_path.exists() {
+        return Ok(AppConfig::default());
+    }
+
+    let json = fs::read_to_string(&config_path).map_err(CoreError::ConfigRead)?;
+
+    serde_json::from_str(&json).map_err(CoreError::ConfigParse)
+}
@@ -0,0 +1,192 @@
+//! 批次内去重：内容与处理方式都相同的输入只交给 UPX 处理一次，其余复制其结果
+//!
+//! 安装目录中同一个运行库 DLL 常常出现在多处。先按大小分组，大小相同的再比较 SHA-256。
+//! 重复的文件仍走完整的 [`run_upx`] 流程（备份、冲突处理、清单与哈希），
+//! 只是调用 UPX 的那一步改为复制代表文件的输出。
+
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::hash::sha256_file;
+use crate::options::UpxOptions;
+use crate::output::UPX_EXIT_OK;
+use crate::packer::{Packer, PackerOutput};
+use crate::process::run_upx;
+use crate::result::{UpxResult, UpxStatus};
+use crate::tune::{method_with_candidates, Strategy};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 找出内容与前面文件相同的文件，返回 `重复文件的下标 -> 第一个相同文件的下标`
+///
+/// 只有大小相同的文件才计算哈希；无法读取的文件不参与比较。
+pub fn identical_files<S: AsRef<str>>(files: &[S]) -> HashMap<usize, usize> {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        if let Ok(metadata) = fs::metadata(file.as_ref()) {
+            by_size.entry(metadata.len()).or_default().push(index);
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for group in by_size.into_values().filter(|group| group.len() > 1) {
+        let mut first_by_hash = HashMap::new();
+        for index in group {
+            let Ok(hash) = sha256_file(files[index].as_ref()) else {
+                continue;
+            };
+            // 同一大小组内按下标顺序遍历，第一个出现的作为代表
+            match first_by_hash.get(&hash) {
+                Some(&first) => {
+                    duplicates.insert(index, first);
+                }
+                None => {
+                    first_by_hash.insert(hash, index);
+                }
+            }
+        }
+    }
+    duplicates
+}
+
+/// 找出输入内容与处理方式都与前面任务相同的任务，返回 `重复任务的下标 -> 代表任务的下标`
+///
+/// 处理方式包括模式、压缩参数（自动选择或体积预算时含 `strategies`）、UPX 版本、
+/// 输出冲突处理与备份；测试与列出不产生输出，不参与去重。
+pub fn duplicate_inputs(jobs: &[UpxOptions], strategies: &[Strategy]) -> HashMap<usize, usize> {
+    let mut by_settings: HashMap<_, Vec<usize>> = HashMap::new();
+    for (index, job) in jobs.iter().enumerate() {
+        if !job.mode.is_inspect() {
+            let settings = (
+                job.mode,
+                method_with_candidates(job, strategies),
+                job.upx_version.clone(),
+                job.on_conflict,
+                job.backup,
+            );
+            by_settings.entry(settings).or_default().push(index);
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for group in by_settings.into_values().filter(|group| group.len() > 1) {
+        let files: Vec<&str> = group.iter().map(|&i| jobs[i].input_file.as_str()).collect();
+        for (duplicate, first) in identical_files(&files) {
+            duplicates.insert(group[duplicate], group[first]);
+        }
+    }
+    duplicates
+}
+
+/// 把代表文件的输出复制为结果的后端，其余操作交给内部后端
+struct CopyPacker<'a> {
+    inner: &'a dyn Packer,
+    representative: &'a UpxResult,
+}
+
+impl CopyPacker<'_> {
+    fn copy(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        let target = if is_overwrite {
+            &options.input_file
+        } else {
+            &options.output_file
+        };
+        fs::copy(&self.representative.output_file, target).map_err(CoreError::DuplicateCopy)?;
+        Ok(PackerOutput {
+            exit_code: Some(UPX_EXIT_OK),
+            stdout: format!(
+                "[去重] 与 {} 内容相同，已复制其结果，未运行 UPX",
+                self.representative.input_file
+            ),
+            stderr: String::new(),
+            strategies: self.representative.strategies.clone(),
+            budget: self.representative.budget.clone(),
+        })
+    }
+}
+
+impl Packer for CopyPacker<'_> {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.copy(options, is_overwrite)
+    }
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.copy(options, is_overwrite)
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.test(options)
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.list(options)
+    }
+
+    fn version(&self) -> Result<String> {
+        self.inner.version()
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.inner.capabilities()
+    }
+
+    fn capabilities_for(&self, options: &UpxOptions) -> Result<UpxCapabilities> {
+        self.inner.capabilities_for(options)
+    }
+
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        self.inner.command_line(options, is_overwrite)
+    }
+
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        self.inner.is_packed(path)
+    }
+}
+
+/// 处理与 `representative` 内容相同的任务
+///
+/// 代表文件处理成功（含警告）时复制其输出，结果中记下代表文件；
+/// 否则照常调用 UPX，以便得到该文件自己的失败原因。
+pub fn run_duplicate(
+    packer: &dyn Packer,
+    job: &UpxOptions,
+    representative: &UpxResult,
+) -> Result<UpxResult> {
+    if !matches!(
+        representative.status,
+        UpxStatus::Success | UpxStatus::Warning
+    ) {
+        return run_upx(packer, job);
+    }
+
+    let copier = CopyPacker {
+        inner: packer,
+        representative,
+    };
+    let mut result = run_upx(&copier, job)?;
+    if matches!(result.status, UpxStatus::Success | UpxStatus::Warning) {
+        result.duplicate_of = Some(representative.input_file.clone());
+    }
+    Ok(result)
+}
+
+/// 去重省下的处理量
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DedupSavings {
+    /// 复制结果、没有调用 UPX 的文件数
+    pub files: usize,
+    /// 这些文件的原始大小之和
+    pub bytes: u64,
+}
+
+impl DedupSavings {
+    /// 累计一个文件；`duplicate_of` 即结果或报告条目中记下的代表文件
+    pub fn record(&mut self, duplicate_of: Option<&str>, original_size: u64) {
+        if duplicate_of.is_some() {
+            self.files += 1;
+            self.bytes += original_size;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::io;
+use thiserror::Error;
+
+/// 核心库的错误类型，`Display` 输出即界面上显示的提示文本
+#[derive(Debug, Error)]
+pub enum CoreError {
+    #[error("未找到 UPX 工具！请确保安装完整")]
+    UpxNotFound,
+
+    #[error("未找到可用的 UPX 工具: {0}")]
+    UpxUnusable(String),
+
+    #[error("内嵌的 UPX 校验失败，程序文件可能已损坏或被篡改")]
+    EmbeddedUpxCorrupt,
+
+    #[error("释放内嵌的 UPX 失败: {0}")]
+    EmbeddedUpxExtract(#[source] io::Error),
+
+    #[error("UPX 工具无法执行！")]
+    UpxNotExecutable(#[source] io::Error),
+
+    #[error("无法获取UPX版本")]
+    UpxVersionUnavailable,
+
+    #[error("未注册 UPX 版本: {0}")]
+    UpxVersionNotRegistered(String),
+
+    #[error("输入文件不存在: {0}")]
+    InputNotFound(String),
+
+    #[error("无法读取文件属性: {0}")]
+    Metadata(#[source] io::Error),
+
+    #[error("文件为只读，请先修改文件属性")]
+    ReadOnly,
+
+    #[error("备份文件失败: {0}")]
+    Backup(#[source] io::Error),
+
+    #[error("创建输出目录失败: {0}")]
+    CreateOutputDir(#[source] io::Error),
+
+    #[error("输出目录不能是源文件所在的目录: {0}")]
+    OutputDirIsSource(String),
+
+    #[error("复制文件失败: {0}: {1}")]
+    CopyUnsupported(String, #[source] io::Error),
+
+    #[error("无效的命名模板 {0}")]
+    InvalidNameTemplate(String),
+
+    #[error("输出文件已存在: {0}")]
+    OutputExists(String),
+
+    #[error("与 {0} 的输出路径相同")]
+    DuplicateOutput(String),
+
+    #[error("执行 UPX 命令失败: {0}")]
+    Spawn(#[source] io::Error),
+
+    #[error("路径不存在: {0}")]
+    PathNotFound(String),
+
+    #[error("不是文件夹: {0}")]
+    NotADirectory(String),
+
+    #[error("无法获取配置文件路径")]
+    ConfigPathUnavailable,
+
+    #[error("读取配置文件失败: {0}")]
+    ConfigRead(#[source] io::Error),
+
+    #[error("解析配置文件失败: {0}")]
+    ConfigParse(#[source] serde_json::Error),
+
+    #[error("序列化配置失败: {0}")]
+    ConfigSerialize(#[source] serde_json::Error),
+
+    #[error("保存配置文件失败: {0}")]
+    ConfigWrite(#[source] io::Error),
+
+    #[error("写入报告失败: {0}")]
+    ReportWrite(#[source] io::Error),
+
+    #[error("无法获取应用数据目录")]
+    DataDirUnavailable,
+
+    #[error("读取历史记录失败: {0}")]
+    HistoryRead(#[source] io::Error),
+
+    #[error("写入历史记录失败: {0}")]
+    HistoryWrite(#[source] io::Error),
+
+    #[error("导出历史记录失败: {0}")]
+    HistoryExport(#[source] io::Error),
+
+    #[error("创建基准测试临时副本失败: {0}")]
+    Benchmark(#[source] io::Error),
+
+    #[error("导出基准测试结果失败: {0}")]
+    BenchmarkExport(#[source] io::Error),
+
+    #[error("无效的日期 {0}，格式应为 YYYY-MM-DD")]
+    InvalidDate(String),
+
+    #[error("读取清单失败 {path}: {source}")]
+    ManifestRead {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("清单格式错误 {path}: {source}")]
+    ManifestParse {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("写入清单失败: {0}")]
+    ManifestWrite(#[source] io::Error),
+
+    #[error("无效的压缩策略 {0}，例如 -9、--best、--best --lzma、--brute、--best --nrv2e")]
+    InvalidStrategy(String),
+
+    #[error("无效的体积预算 {0}，例如 524288、512KB、1.5MB 或 60%")]
+    InvalidBudget(String),
+
+    #[error("自动选择压缩方式时读写临时文件失败: {0}")]
+    AutoTune(#[source] io::Error),
+
+    #[error("复制重复文件的处理结果失败: {0}")]
+    DuplicateCopy(#[source] io::Error),
+
+    #[error("保存增量处理索引失败: {0}")]
+    IndexWrite(#[source] io::Error),
+
+    #[error("读取加壳缓存失败: {0}")]
+    CacheRead(#[source] io::Error),
+
+    #[error("写入加壳缓存失败: {0}")]
+    CacheWrite(#[source] io::Error),
+
+    #[error("文件夹中没有清单（upx-manifest.json 或 *.upx.json）: {0}")]
+    ManifestNotFound(String),
+
+    #[error("获取 UPX 发行信息失败: {0}")]
+    UpxUpdateRequest(String),
+
+    #[error("下载 UPX 发行包失败: {0}")]
+    UpxUpdateDownload(String),
+
+    #[error("当前平台没有可用的 UPX 发行包: {0}")]
+    UpxUpdateNoAsset(String),
+
+    #[error("发行包 {0} 没有提供 SHA-256 摘要，无法校验")]
+    UpxUpdateUnverified(String),
+
+    #[error("UPX 发行包校验失败（期望 {expected}，实际 {actual}）")]
+    UpxUpdateChecksum { expected: String, actual: String },
+
+    #[error("解压 UPX 发行包失败: {0}")]
+    UpxUpdateArchive(String),
+
+    #[error("安装 UPX 失败: {0}")]
+    UpxUpdateInstall(#[source] io::Error),
+
+    #[error("安装的 UPX 版本为 {actual}，与发行版 {expected} 不一致")]
+    UpxUpdateVersionMismatch { expected: String, actual: String },
+}
+
+pub type Result<T, E = CoreError> = std::result::Result<T, E>;
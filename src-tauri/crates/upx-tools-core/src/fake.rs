@@ -0,0 +1,378 @@
+//! 内存中的加壳后端，用于确定性地测试批处理、错误映射与备份逻辑
+//!
+//! "压缩"是在文件头写入 [`FAKE_MAGIC`] 后做一次游程编码，可以完整还原；
+//! 输出文本与退出码模仿真实 UPX，因此会走与真实后端相同的解析流程。
+
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::options::{UpxMode, UpxOptions};
+use crate::output::{UPX_EXIT_ERROR, UPX_EXIT_OK, UPX_EXIT_WARN};
+use crate::packer::{Packer, PackerOutput};
+use crate::report::method;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 假加壳文件的文件头
+pub const FAKE_MAGIC: &[u8] = b"FAKEUPX!";
+
+/// 可注入的失败，覆盖 [`crate::output::parse_upx_error`] 识别的全部异常
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeFailure {
+    AlreadyPacked,
+    NotPacked,
+    CantPack,
+    Overlay,
+    Io,
+    NotCompressible,
+    /// 以警告退出码（2）结束，输出为给定文本
+    Warning(String),
+    /// 以错误退出码（1）结束，输出为给定文本
+    Error(String),
+    /// 后端无法启动
+    Spawn,
+}
+
+impl FakeFailure {
+    fn output(&self, file: &str) -> Result<PackerOutput> {
+        let (exit_code, stderr) = match self {
+            Self::AlreadyPacked => (
+                UPX_EXIT_WARN,
+                format!(
+                    "upx: {}: AlreadyPackedException: already packed by UPX",
+                    file
+                ),
+            ),
+            Self::NotPacked => (
+                UPX_EXIT_WARN,
+                format!("upx: {}: NotPackedException: not packed by UPX", file),
+            ),
+            Self::CantPack => (
+                UPX_EXIT_ERROR,
+                format!("upx: {}: CantPackException: can't pack new-exe", file),
+            ),
+            Self::Overlay => (
+                UPX_EXIT_ERROR,
+                format!(
+                    "upx: {}: OverlayException: file has overlay -- skipped; try '--overlay=copy'",
+                    file
+                ),
+            ),
+            Self::Io => (
+                UPX_EXIT_ERROR,
+                format!("upx: {}: IOException: can't open", file),
+            ),
+            Self::NotCompressible => (
+                UPX_EXIT_WARN,
+                format!("upx: {}: NotCompressibleException", file),
+            ),
+            Self::Warning(text) => (UPX_EXIT_WARN, format!("upx: {}: {}", file, text)),
+            Self::Error(text) => (UPX_EXIT_ERROR, format!("upx: {}: {}", file, text)),
+            Self::Spawn => {
+                return Err(CoreError::Spawn(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "fake packer spawn failure",
+                )))
+            }
+        };
+
+        Ok(PackerOutput {
+            exit_code: Some(exit_code),
+            stdout: String::new(),
+            stderr,
+            strategies: Vec::new(),
+            budget: None,
+        })
+    }
+}
+
+/// 一次后端调用的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeCall {
+    pub mode: UpxMode,
+    pub input_file: String,
+    pub output_file: String,
+}
+
+/// 内存中的加壳后端
+#[derive(Debug)]
+pub struct FakePacker {
+    version: String,
+    help: String,
+    available: bool,
+    failures: Vec<(String, FakeFailure)>,
+    /// 按压缩方式（`report::method`）追加的字节数
+    padding: Vec<(String, usize)>,
+    /// 输出无法通过测试的压缩方式
+    corrupt: Vec<String>,
+    calls: Mutex<Vec<FakeCall>>,
+}
+
+impl Default for FakePacker {
+    fn default() -> Self {
+        Self {
+            version: "upx 4.2.4".to_string(),
+            help: String::new(),
+            available: true,
+            failures: Vec::new(),
+            padding: Vec::new(),
+            corrupt: Vec::new(),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl FakePacker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入路径包含 `pattern` 时返回指定的失败
+    pub fn fail_on(mut self, pattern: impl Into<String>, failure: FakeFailure) -> Self {
+        self.failures.push((pattern.into(), failure));
+        self
+    }
+
+    /// 以 `method`（例如 `--best --lzma`）加壳时输出多出 `bytes` 字节（按偶数取整），
+    /// 用于让不同压缩方式得到不同大小；多出的部分不影响还原
+    pub fn pad_method(mut self, method: impl Into<String>, bytes: usize) -> Self {
+        self.padding.push((method.into(), bytes));
+        self
+    }
+
+    /// 以 `method` 加壳的输出已损坏，无法通过测试与脱壳
+    pub fn corrupt_method(mut self, method: impl Into<String>) -> Self {
+        self.corrupt.push(method.into());
+        self
+    }
+
+    /// `--version` 输出的第一行
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// `--help` 的输出，用于限制支持的选项；默认为空，表示支持全部选项
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = help.into();
+        self
+    }
+
+    /// 模拟无法执行的 UPX
+    pub fn unavailable(mut self) -> Self {
+        self.available = false;
+        self
+    }
+
+    /// 至今为止的全部调用（不含 `version`）
+    pub fn calls(&self) -> Vec<FakeCall> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    fn record(&self, options: &UpxOptions, mode: UpxMode) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(FakeCall {
+                mode,
+                input_file: options.input_file.clone(),
+                output_file: options.output_file.clone(),
+            });
+        }
+    }
+
+    fn injected(&self, file: &str) -> Option<&FakeFailure> {
+        self.failures
+            .iter()
+            .find(|(pattern, _)| file.contains(pattern.as_str()))
+            .map(|(_, failure)| failure)
+    }
+
+    fn target(options: &UpxOptions, is_overwrite: bool) -> &str {
+        if is_overwrite {
+            &options.input_file
+        } else {
+            &options.output_file
+        }
+    }
+}
+
+fn ok(stdout: String) -> PackerOutput {
+    PackerOutput {
+        exit_code: Some(UPX_EXIT_OK),
+        stdout,
+        stderr: String::new(),
+        strategies: Vec::new(),
+        budget: None,
+    }
+}
+
+fn io_failure(file: &str) -> Result<PackerOutput> {
+    FakeFailure::Io.output(file)
+}
+
+/// 游程编码：每段为（次数，字节）
+pub fn fake_pack(data: &[u8]) -> Vec<u8> {
+    let mut packed = FAKE_MAGIC.to_vec();
+    let mut iter = data.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        let mut count: u8 = 1;
+        while count < u8::MAX && iter.peek() == Some(&&byte) {
+            iter.next();
+            count += 1;
+        }
+        packed.push(count);
+        packed.push(byte);
+    }
+    packed
+}
+
+/// 还原 [`fake_pack`] 的结果；不是假加壳文件或数据损坏时返回 `None`
+pub fn fake_unpack(data: &[u8]) -> Option<Vec<u8>> {
+    let body = data.strip_prefix(FAKE_MAGIC)?;
+    if body.len() % 2 != 0 {
+        return None;
+    }
+    let mut unpacked = Vec::with_capacity(body.len());
+    for pair in body.chunks_exact(2) {
+        unpacked.extend(std::iter::repeat_n(pair[1], pair[0] as usize));
+    }
+    Some(unpacked)
+}
+
+impl Packer for FakePacker {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.record(options, UpxMode::Compress);
+        let input = &options.input_file;
+        if let Some(failure) = self.injected(input) {
+            return failure.output(input);
+        }
+
+        let Ok(data) = fs::read(input) else {
+            return io_failure(input);
+        };
+        if data.starts_with(FAKE_MAGIC) {
+            return FakeFailure::AlreadyPacked.output(input);
+        }
+
+        let mut packed = fake_pack(&data);
+        let method = method(options);
+        for (_, bytes) in self.padding.iter().filter(|(m, _)| *m == method) {
+            // 次数为 0 的游程还原后为空
+            packed.resize(packed.len() + bytes / 2 * 2, 0);
+        }
+        if self.corrupt.contains(&method) {
+            packed.push(0);
+        }
+        if packed.len() >= data.len() {
+            return FakeFailure::NotCompressible.output(input);
+        }
+
+        let target = Self::target(options, is_overwrite);
+        if fs::write(target, &packed).is_err() {
+            return io_failure(target);
+        }
+
+        Ok(ok(format!(
+            "{:>20} ->{:>12}   {:>6.2}%    fake/test    {}\n\nPacked 1 file.\n",
+            data.len(),
+            packed.len(),
+            packed.len() as f64 / data.len().max(1) as f64 * 100.0,
+            target
+        )))
+    }
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.record(options, UpxMode::Decompress);
+        let input = &options.input_file;
+        if let Some(failure) = self.injected(input) {
+            return failure.output(input);
+        }
+
+        let Ok(data) = fs::read(input) else {
+            return io_failure(input);
+        };
+        if !data.starts_with(FAKE_MAGIC) {
+            return FakeFailure::NotPacked.output(input);
+        }
+        let Some(unpacked) = fake_unpack(&data) else {
+            return FakeFailure::Error("CantUnpackException: header corrupted".to_string())
+                .output(input);
+        };
+
+        let target = Self::target(options, is_overwrite);
+        if fs::write(target, &unpacked).is_err() {
+            return io_failure(target);
+        }
+
+        Ok(ok(format!(
+            "{:>20} <-{:>12}    fake/test    {}\n\nUnpacked 1 file.\n",
+            unpacked.len(),
+            data.len(),
+            target
+        )))
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.record(options, UpxMode::Test);
+        let input = &options.input_file;
+        if let Some(failure) = self.injected(input) {
+            return failure.output(input);
+        }
+
+        let Ok(data) = fs::read(input) else {
+            return io_failure(input);
+        };
+        if !data.starts_with(FAKE_MAGIC) {
+            return FakeFailure::NotPacked.output(input);
+        }
+        if fake_unpack(&data).is_none() {
+            return FakeFailure::Error("CantUnpackException: header corrupted".to_string())
+                .output(input);
+        }
+
+        Ok(ok(format!("testing {} [OK]\n\nTested 1 file.\n", input)))
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.record(options, UpxMode::List);
+        let input = &options.input_file;
+        if let Some(failure) = self.injected(input) {
+            return failure.output(input);
+        }
+
+        let Ok(data) = fs::read(input) else {
+            return io_failure(input);
+        };
+        let Some(unpacked) = fake_unpack(&data) else {
+            return FakeFailure::NotPacked.output(input);
+        };
+
+        Ok(ok(format!(
+            "        File size         Ratio      Format      Name\n   --------------------   ------   -----------   -----------\n{:>12} ->{:>8}   {:>6.2}%    fake/test    {}\n",
+            unpacked.len(),
+            data.len(),
+            data.len() as f64 / unpacked.len().max(1) as f64 * 100.0,
+            input
+        )))
+    }
+
+    /// 假后端的加壳文件以 [`FAKE_MAGIC`] 开头
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        Ok(fs::read(path)?.starts_with(FAKE_MAGIC))
+    }
+
+    fn version(&self) -> Result<String> {
+        if !self.available {
+            return Err(CoreError::UpxNotExecutable(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fake packer unavailable",
+            )));
+        }
+        Ok(self.version.clone())
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.version()
+            .map(|version| UpxCapabilities::parse(&version, &self.help))
+    }
+}
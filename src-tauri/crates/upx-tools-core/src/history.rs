@@ -0,0 +1,276 @@
+//! 处理历史：每次处理的结果追加到应用数据目录下的 JSON Lines 文件
+//!
+//! 一行一条记录，追加写入，清空日志面板或重启程序后仍可按路径、日期与状态查询，
+//! 并导出为 JSON 或 CSV。无法解析的行（例如写入时断电留下的半行）在读取时跳过。
+
+use crate::error::{CoreError, Result};
+use crate::options::UpxOptions;
+use crate::result::{UpxResult, UpxStatus};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 历史文件名，位于 `<本地数据目录>/UPX-Tools/`
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// 同一进程内并发追加时逐条写入，避免多行交错
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// 默认的历史文件路径
+pub fn history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("UPX-Tools").join(HISTORY_FILE_NAME))
+}
+
+/// 一次处理的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub timestamp: DateTime<Local>,
+    pub status: UpxStatus,
+    pub input_file: String,
+    /// 实际写入的输出文件（冲突改名后的路径）
+    pub output_file: String,
+    /// 注册的版本号，未指定时为默认 UPX 的版本
+    pub upx_version: Option<String>,
+    /// 提交的处理选项
+    pub options: UpxOptions,
+    pub original_size: u64,
+    pub output_size: u64,
+    pub ratio: i32,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub reason: Option<String>,
+    /// 处理前输入文件与处理后输出文件的 SHA-256，见 [`UpxResult`]
+    #[serde(default)]
+    pub input_sha256: Option<String>,
+    #[serde(default)]
+    pub output_sha256: Option<String>,
+    /// 自动选择或体积预算最终保留的压缩方式，写法与 [`crate::report::method`] 一致
+    #[serde(default)]
+    pub chosen_strategy: Option<String>,
+}
+
+impl HistoryRecord {
+    pub fn new(options: &UpxOptions, upx_version: Option<String>, result: &UpxResult) -> Self {
+        Self {
+            timestamp: Local::now(),
+            status: result.status,
+            input_file: result.input_file.clone(),
+            output_file: result.output_file.clone(),
+            upx_version,
+            options: options.clone(),
+            original_size: result.original_size,
+            output_size: result.output_size,
+            ratio: result.ratio,
+            exit_code: result.exit_code,
+            duration_ms: result.duration_ms,
+            reason: result.reason.clone(),
+            input_sha256: result.input_sha256.clone(),
+            output_sha256: result.output_sha256.clone(),
+            chosen_strategy: result
+                .strategies
+                .iter()
+                .find(|t| t.chosen)
+                .map(|t| t.strategy.clone()),
+        }
+    }
+
+    /// 预检失败、没有运行 UPX 的记录
+    pub fn failed(options: &UpxOptions, upx_version: Option<String>, error: &CoreError) -> Self {
+        let result =
+            UpxResult::failed(&options.input_file, &options.output_file, error.to_string());
+        Self::new(options, upx_version, &result)
+    }
+}
+
+/// 查询条件，未设置的条件不过滤
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// 输入或输出路径包含此文本（不区分大小写）
+    pub path: Option<String>,
+    /// 起始日期（本地时间，含当天）
+    pub since: Option<NaiveDate>,
+    /// 截止日期（本地时间，含当天）
+    pub until: Option<NaiveDate>,
+    pub status: Option<UpxStatus>,
+    /// 最多返回的条数，从最新的记录开始
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        let date = record.timestamp.date_naive();
+        self.since.is_none_or(|since| date >= since)
+            && self.until.is_none_or(|until| date <= until)
+            && self.status.is_none_or(|status| record.status == status)
+            && self.path.as_deref().is_none_or(|path| {
+                let path = path.to_lowercase();
+                record.input_file.to_lowercase().contains(&path)
+                    || record.output_file.to_lowercase().contains(&path)
+            })
+    }
+}
+
+/// 解析查询条件中的日期（YYYY-MM-DD）
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| CoreError::InvalidDate(date.to_string()))
+}
+
+/// 历史文件
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 应用数据目录下的历史文件
+    pub fn open_default() -> Result<Self> {
+        history_path()
+            .map(Self::new)
+            .ok_or(CoreError::DataDirUnavailable)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录，所在目录不存在时创建
+    pub fn append(&self, record: &HistoryRecord) -> Result<()> {
+        let mut line =
+            serde_json::to_string(record).map_err(|e| CoreError::HistoryWrite(e.into()))?;
+        line.push('\n');
+
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(CoreError::HistoryWrite)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(CoreError::HistoryWrite)
+    }
+
+    /// 按写入顺序读取全部记录，文件不存在时为空
+    pub fn load(&self) -> Result<Vec<HistoryRecord>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(CoreError::HistoryRead(e)),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(CoreError::HistoryRead)?;
+            if let Ok(record) = serde_json::from_str(&line) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// 符合条件的记录，最新的在前
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryRecord>> {
+        let mut records = self.load()?;
+        records.retain(|record| query.matches(record));
+        records.reverse();
+        if let Some(limit) = query.limit {
+            records.truncate(limit);
+        }
+        Ok(records)
+    }
+}
+
+// ============================================================================
+// 导出
+// ============================================================================
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// 按扩展名选择，`.csv` 为 CSV，其余为 JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "timestamp,mode,status,input_file,output_file,upx_version,\
+compression_level,lzma,ultra_brute,force,backup,original_size,output_size,ratio,\
+duration_ms,exit_code,reason,input_sha256,output_sha256";
+
+/// 把记录导出为 JSON 数组或 CSV
+///
+/// CSV 以 UTF-8 BOM 开头，Excel 打开时中文路径不会乱码。
+pub fn export_history(records: &[HistoryRecord], path: &Path, format: ExportFormat) -> Result<()> {
+    let file = File::create(path).map_err(CoreError::HistoryExport)?;
+    let mut writer = BufWriter::new(file);
+
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, records)
+            .map_err(|e| CoreError::HistoryExport(e.into()))?,
+        ExportFormat::Csv => write_csv(&mut writer, records).map_err(CoreError::HistoryExport)?,
+    }
+    writer.flush().map_err(CoreError::HistoryExport)
+}
+
+fn write_csv(writer: &mut impl Write, records: &[HistoryRecord]) -> std::io::Result<()> {
+    writer.write_all("\u{feff}".as_bytes())?;
+    writeln!(writer, "{}", CSV_HEADER)?;
+
+    for record in records {
+        let options = &record.options;
+        let fields = [
+            record.timestamp.to_rfc3339(),
+            options.mode.as_str().to_string(),
+            record.status.as_str().to_string(),
+            record.input_file.clone(),
+            record.output_file.clone(),
+            record.upx_version.clone().unwrap_or_default(),
+            options.compression_level.clone(),
+            options.lzma.to_string(),
+            options.ultra_brute.to_string(),
+            options.force.to_string(),
+            options.backup.to_string(),
+            record.original_size.to_string(),
+            record.output_size.to_string(),
+            record.ratio.to_string(),
+            record.duration_ms.to_string(),
+            record.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            record.reason.clone().unwrap_or_default(),
+            record.input_sha256.clone().unwrap_or_default(),
+            record.output_sha256.clone().unwrap_or_default(),
+        ];
+        let line: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(writer, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+/// 含逗号、引号或换行的字段加双引号，引号加倍
+pub(crate) fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
@@ -0,0 +1,59 @@
+//! 图形界面与 UPX 更新共用的 HTTP 请求
+//!
+//! 请求 GitHub 发行信息接口和下载发行包都经过这里，统一设置 User-Agent、
+//! GitHub API 的请求头与超时。错误只返回文本，由调用方决定对应的提示。
+
+use serde::de::DeserializeOwned;
+use std::io::Read;
+use std::time::Duration;
+
+const USER_AGENT: &str = "UPX-Tools/1.0";
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .user_agent(USER_AGENT)
+        .timeout_connect(Duration::from_secs(15))
+        .timeout_read(Duration::from_secs(60))
+        .build()
+}
+
+/// HTTP 状态错误只保留状态码，其余保留底层错误文本
+fn error_text(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, _) => format!("HTTP {}", code),
+        ureq::Error::Transport(e) => e.to_string(),
+    }
+}
+
+/// 请求 GitHub API 并解析 JSON
+///
+/// 设置了环境变量 `GITHUB_TOKEN` 时带上令牌，以提高 GitHub API 的请求配额。
+pub fn get_github_json<T: DeserializeOwned>(url: &str) -> Result<T, String> {
+    let mut request = agent()
+        .get(url)
+        .set("Accept", "application/vnd.github+json")
+        .set("X-GitHub-Api-Version", "2022-11-28");
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        if !token.is_empty() {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+    }
+
+    request
+        .call()
+        .map_err(error_text)?
+        .into_json()
+        .map_err(|e| format!("解析响应失败: {}", e))
+}
+
+/// 下载 `url` 的全部内容，`size_hint` 为预计的字节数
+pub fn download(url: &str, size_hint: u64) -> Result<Vec<u8>, String> {
+    let response = agent().get(url).call().map_err(error_text)?;
+
+    let mut data = Vec::with_capacity(size_hint as usize);
+    response
+        .into_reader()
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
@@ -0,0 +1,249 @@
+//! 增量处理：按根文件夹记录每个文件上次处理后的状态，再次批量处理时跳过没有变化的文件
+//!
+//! 索引保存在应用数据目录的 `UPX-Tools/index/` 中，每个根文件夹一个 JSON 文件，
+//! 不会在源文件夹里留下额外文件。文件的大小、修改时间、处理方式与 UPX 版本都没变，
+//! 且上次的输出文件仍与记录的哈希一致时才跳过；其余情况照常处理并更新记录。
+
+use crate::error::{CoreError, Result};
+use crate::hash::{sha256_file, sha256_hex};
+use crate::options::UpxOptions;
+use crate::persist;
+use crate::result::{UpxResult, UpxStatus};
+use crate::tune::{method_with_candidates, Strategy};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// 图形界面逐个文件并发处理时，读改写索引文件逐个进行；跨进程另由锁文件保证
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 默认的索引目录：`<本地数据目录>/UPX-Tools/index`
+pub fn index_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("UPX-Tools").join("index"))
+}
+
+/// 处理方式、压缩参数（含候选压缩方式与体积预算）与 UPX 版本，任一变化都需要重新处理
+fn fingerprint(job: &UpxOptions, strategies: &[Strategy], default_version: &str) -> String {
+    format!(
+        "{} {} upx {}",
+        job.mode.as_str(),
+        method_with_candidates(job, strategies),
+        job.upx_version.as_deref().unwrap_or(default_version)
+    )
+}
+
+fn file_state(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// 一个文件上次处理后的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// 处理后输入文件的大小与修改时间（原地处理时即为输出文件）
+    pub size: u64,
+    pub modified: SystemTime,
+    /// 请求的输出路径（冲突改名前）
+    pub target: String,
+    /// 实际写入的输出文件
+    pub output_file: String,
+    pub output_sha256: String,
+    pub options: String,
+    pub processed_at: DateTime<Local>,
+}
+
+/// 一个根文件夹的索引文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexFile {
+    root: String,
+    /// 以相对根文件夹、`/` 分隔的路径为键
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Debug)]
+struct FolderIndex {
+    root: PathBuf,
+    path: PathBuf,
+    entries: BTreeMap<String, IndexEntry>,
+    /// 本次新增或更新的记录，保存时合并到磁盘上的最新内容
+    updated: BTreeMap<String, IndexEntry>,
+}
+
+impl FolderIndex {
+    /// 文件相对根文件夹的键，不在根文件夹中时为空
+    fn key(&self, file: &Path) -> Option<String> {
+        let file = fs::canonicalize(file).ok()?;
+        let relative = file.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// 一次批量处理涉及的全部根文件夹的索引
+#[derive(Debug)]
+pub struct IncrementalIndex {
+    folders: Vec<FolderIndex>,
+}
+
+impl IncrementalIndex {
+    /// 读取 `dir` 中各根文件夹的索引；根为文件时取其所在的文件夹
+    ///
+    /// 索引不存在或无法解析时视为空，所有文件都会被处理。
+    pub fn open(dir: &Path, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut folders: Vec<FolderIndex> = Vec::new();
+        for root in roots {
+            let root = if root.is_file() {
+                root.parent().map(Path::to_path_buf).unwrap_or_default()
+            } else {
+                root
+            };
+            let Ok(root) = fs::canonicalize(&root) else {
+                continue;
+            };
+            if folders.iter().any(|f| f.root == root) {
+                continue;
+            }
+
+            let name = sha256_hex(root.to_string_lossy().as_bytes());
+            let path = dir.join(format!("{}.json", &name[..16]));
+            let entries = load_index_file(&path).entries;
+            folders.push(FolderIndex {
+                root,
+                path,
+                entries,
+                updated: BTreeMap::new(),
+            });
+        }
+        // 根文件夹嵌套时由最深的一个负责
+        folders.sort_by_key(|f| std::cmp::Reverse(f.root.components().count()));
+        Self { folders }
+    }
+
+    /// 使用应用数据目录中的索引
+    pub fn open_default(roots: impl IntoIterator<Item = PathBuf>) -> Result<Self> {
+        let dir = index_dir().ok_or(CoreError::DataDirUnavailable)?;
+        Ok(Self::open(&dir, roots))
+    }
+
+    fn locate(&self, file: &Path) -> Option<(usize, String)> {
+        self.folders
+            .iter()
+            .enumerate()
+            .find_map(|(i, folder)| folder.key(file).map(|key| (i, key)))
+    }
+
+    /// 自上次处理后没有变化时返回跳过的结果
+    ///
+    /// `strategies` 为自动选择或体积预算时尝试的压缩方式，`default_version` 为未指定
+    /// `upx_version` 时默认 UPX 的版本。
+    pub fn unchanged(
+        &self,
+        job: &UpxOptions,
+        strategies: &[Strategy],
+        default_version: &str,
+    ) -> Option<UpxResult> {
+        let (i, key) = self.locate(Path::new(&job.input_file))?;
+        let entry = self.folders[i].entries.get(&key)?;
+
+        let matches = entry.options == fingerprint(job, strategies, default_version)
+            && entry.target == job.output_file
+            && file_state(Path::new(&job.input_file)) == Some((entry.size, entry.modified))
+            && sha256_file(&entry.output_file).ok().as_deref() == Some(&entry.output_sha256);
+        if !matches {
+            return None;
+        }
+
+        let output_size = fs::metadata(&entry.output_file).map_or(0, |m| m.len());
+        Some(UpxResult {
+            output_size,
+            output_sha256: Some(entry.output_sha256.clone()),
+            ..UpxResult::skipped(
+                &job.input_file,
+                &entry.output_file,
+                "自上次处理后未变化".to_string(),
+            )
+        })
+    }
+
+    /// 把批量任务分为需要处理的与未变化而跳过的
+    pub fn partition(
+        &self,
+        jobs: Vec<UpxOptions>,
+        strategies: &[Strategy],
+        default_version: &str,
+    ) -> (Vec<UpxOptions>, Vec<(UpxOptions, UpxResult)>) {
+        let mut pending = Vec::with_capacity(jobs.len());
+        let mut unchanged = Vec::new();
+        for job in jobs {
+            match self.unchanged(&job, strategies, default_version) {
+                Some(result) => unchanged.push((job, result)),
+                None => pending.push(job),
+            }
+        }
+        (pending, unchanged)
+    }
+
+    /// 记录处理成功（含警告）的文件，其余结果不影响索引
+    pub fn record(
+        &mut self,
+        job: &UpxOptions,
+        strategies: &[Strategy],
+        default_version: &str,
+        result: &UpxResult,
+    ) {
+        if !matches!(result.status, UpxStatus::Success | UpxStatus::Warning) {
+            return;
+        }
+        let Some(output_sha256) = result.output_sha256.clone() else {
+            return;
+        };
+        let Some((size, modified)) = file_state(Path::new(&job.input_file)) else {
+            return;
+        };
+        let Some((i, key)) = self.locate(Path::new(&job.input_file)) else {
+            return;
+        };
+
+        let entry = IndexEntry {
+            size,
+            modified,
+            target: job.output_file.clone(),
+            output_file: result.output_file.clone(),
+            output_sha256,
+            options: fingerprint(job, strategies, default_version),
+            processed_at: Local::now(),
+        };
+        let folder = &mut self.folders[i];
+        folder.entries.insert(key.clone(), entry.clone());
+        folder.updated.insert(key, entry);
+    }
+
+    /// 把本次的记录合并到磁盘上的索引中
+    ///
+    /// 与缓存索引一样在锁文件保护下读改写并原子替换，GUI 与命令行同时保存时不会互相覆盖。
+    pub fn save(&self) -> Result<()> {
+        for folder in self.folders.iter().filter(|f| !f.updated.is_empty()) {
+            let _lock = persist::lock(&folder.path.with_extension("lock"), &SAVE_LOCK)
+                .map_err(CoreError::IndexWrite)?;
+            let mut file = load_index_file(&folder.path);
+            file.root = folder.root.display().to_string();
+            file.entries.extend(folder.updated.clone());
+
+            let content =
+                serde_json::to_string(&file).map_err(|e| CoreError::IndexWrite(e.into()))?;
+            persist::write_atomic(&folder.path, content.as_bytes())
+                .map_err(CoreError::IndexWrite)?;
+        }
+        Ok(())
+    }
+}
+
+fn load_index_file(path: &Path) -> IndexFile {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,105 @@
+//! JUnit XML 报告（供 CI 面板展示，每个文件对应一个测试用例）
+
+use crate::error::{CoreError, Result};
+use crate::result::{UpxResult, UpxStatus};
+use std::fs;
+use std::path::Path;
+
+/// 转义 XML 特殊字符，并去掉 XML 1.0 不允许的控制字符
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+}
+
+/// 生成 JUnit XML；`fail_on_warning` 时警告也记为失败
+pub fn render(suite_name: &str, results: &[UpxResult], fail_on_warning: bool) -> String {
+    let is_failure = |r: &UpxResult| {
+        r.status == UpxStatus::Failed || (fail_on_warning && r.status == UpxStatus::Warning)
+    };
+
+    let tests = results.len();
+    let failures = results.iter().filter(|r| is_failure(r)).count();
+    let skipped = results
+        .iter()
+        .filter(|r| r.status == UpxStatus::Skipped)
+        .count();
+    let total_time = seconds(results.iter().map(|r| r.duration_ms).sum());
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"UPX-Tools\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\">\n",
+        tests, failures, skipped, total_time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\">\n",
+        escape_xml(suite_name),
+        tests,
+        failures,
+        skipped,
+        total_time
+    ));
+
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n",
+            escape_xml(suite_name),
+            escape_xml(&result.input_file),
+            seconds(result.duration_ms)
+        ));
+
+        if is_failure(result) {
+            xml.push_str(&format!(
+                "      <failure message=\"{}\" type=\"{:?}\">{}</failure>\n",
+                escape_xml(first_line(&result.message)),
+                result.status,
+                escape_xml(&result.message)
+            ));
+        } else if result.status == UpxStatus::Skipped {
+            xml.push_str(&format!(
+                "      <skipped message=\"{}\"/>\n",
+                escape_xml(result.reason.as_deref().unwrap_or_default())
+            ));
+        }
+
+        xml.push_str(&format!(
+            "      <system-out>{}</system-out>\n",
+            escape_xml(&result.message)
+        ));
+        xml.push_str("    </testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// 生成并写入 JUnit XML 文件
+pub fn write_report(
+    path: &Path,
+    suite_name: &str,
+    results: &[UpxResult],
+    fail_on_warning: bool,
+) -> Result<()> {
+    fs::write(path, render(suite_name, results, fail_on_warning)).map_err(CoreError::ReportWrite)
+}
@@ -0,0 +1,77 @@
+//! UPX-Tools 核心库
+//!
+//! 不依赖 Tauri，图形界面与命令行共用同一套逻辑：
+//!
+//! - [`args`]：根据 [`UpxOptions`] 构建 UPX 命令行参数
+//! - [`packer`]：加壳后端抽象 [`Packer`] 与调用 UPX 进程的 [`UpxProcess`]
+//! - [`process`]：通过后端处理单个文件，并把退出码、输出整理为 [`UpxResult`]
+//! - [`output`]：UPX 输出过滤、错误提示与状态分类
+//! - [`batch`]：并发批量处理与状态汇总
+//! - [`dedup`]：批次内内容相同的文件只处理一次，其余复制结果
+//! - [`scan`]：扫描文件夹中可处理的文件
+//! - [`output_dir`]：输出目录模式，按源目录结构生成输出路径
+//! - [`naming`]：输出文件命名模板与已存在时的冲突处理
+//! - [`plan`]：预览批量处理的动作、命令行与警告，不修改文件
+//! - [`config`]：读写与 GUI 共用的配置文件
+//! - [`history`]：在应用数据目录中保存处理历史，按条件查询与导出
+//! - [`manifest`]：写入记录哈希、UPX 版本与参数的加壳清单，并按清单校验文件夹
+//! - [`stats`]：由处理历史汇总节省的空间、压缩率、失败原因与处理速度
+//! - [`incremental`]：按根文件夹记录处理后的文件状态，批量处理时跳过未变化的文件
+//! - [`tune`]：在临时副本上并行尝试多种压缩方式，保留通过测试且最小的结果
+//! - [`budget`]：体积预算，逐个尝试压缩方式直到输出满足最大大小或压缩率
+//! - [`bench`]：在临时副本上比较文件、压缩方式与 UPX 版本各组合的大小与耗时
+//! - [`cache`]：按输入哈希、压缩参数与 UPX 版本缓存加壳结果
+//! - [`capabilities`]：解析并缓存 UPX 的版本、支持的格式与选项
+//! - [`locate`]：查找或校验后释放 UPX 可执行文件
+//! - [`hash`]：SHA-256 校验
+//! - [`http`]：请求 GitHub 发行信息接口与下载文件
+//! - [`registry`]：注册多个 UPX 版本，按任务选用并在脱壳失败时换版本重试
+//! - [`upx_update`]：选择、校验并解压 UPX 官方发行包
+//! - [`junit`]：生成 JUnit XML 报告
+//! - [`report`]：生成批量处理的 JSON / CSV 明细与 HTML 摘要
+//! - [`fake`]：模拟各种 UPX 异常的内存后端，用于测试
+//!
+//! 所有可能失败的操作都返回 [`CoreError`]，其 `Display` 即面向用户的提示文本。
+
+pub mod args;
+pub mod batch;
+pub mod bench;
+pub mod budget;
+pub mod cache;
+pub mod capabilities;
+pub mod config;
+pub mod dedup;
+pub mod error;
+pub mod fake;
+pub mod hash;
+pub mod history;
+pub mod http;
+pub mod incremental;
+pub mod junit;
+pub mod locate;
+pub mod manifest;
+pub mod naming;
+pub mod options;
+pub mod output;
+pub mod output_dir;
+pub mod packer;
+mod persist;
+pub mod plan;
+pub mod process;
+pub mod registry;
+pub mod report;
+pub mod result;
+pub mod scan;
+pub mod stats;
+pub mod tune;
+pub mod upx_update;
+
+pub use budget::SizeBudget;
+pub use capabilities::UpxCapabilities;
+pub use config::AppConfig;
+pub use error::{CoreError, Result};
+pub use manifest::ManifestMode;
+pub use naming::ConflictPolicy;
+pub use options::{NrvMethod, ScanFolderOptions, UpxMode, UpxOptions};
+pub use packer::{Packer, PackerOutput, UpxProcess};
+pub use result::{UpxResult, UpxStatus};
@@ -0,0 +1,354 @@
+//! 查找 UPX 可执行文件
+//!
+//! 依次检查配置文件指定的路径、`PATH`、随程序分发的位置和内嵌副本，
+//! 每个候选都会运行 `--version`，低于 [`MIN_UPX_VERSION`] 的版本会被跳过。
+
+use crate::error::{CoreError, Result};
+use crate::hash;
+use crate::packer::{Packer, UpxProcess};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tempfile::NamedTempFile;
+
+// 按内嵌数据的 SHA-256 缓存已释放的 UPX 路径
+static EXTRACTED_UPX_PATHS: OnceLock<Mutex<HashMap<String, PathBuf>>> = OnceLock::new();
+
+/// 支持的最低 UPX 版本，更早的版本缺少本工具使用的部分参数
+pub const MIN_UPX_VERSION: UpxVersion = UpxVersion::new(3, 95, 0);
+
+/// 当前平台上 UPX 可执行文件的名称
+const UPX_FILE_NAME: &str = if cfg!(windows) { "upx.exe" } else { "upx" };
+
+// ============================================================================
+// 版本号
+// ============================================================================
+
+/// 从 `upx --version` 解析出的版本号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct UpxVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl UpxVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// 解析 `upx 4.2.4`、`upx 3.96-git-d7ba31cab8ce` 这类版本行
+    pub fn parse(line: &str) -> Option<Self> {
+        let token = line
+            .split_whitespace()
+            .find(|t| t.starts_with(|c: char| c.is_ascii_digit()))?;
+
+        let mut parts = token.split('.').map(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u32>().ok()
+        });
+
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for UpxVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// ============================================================================
+// 查找结果
+// ============================================================================
+
+/// 选中的 UPX 来自哪里，按查找顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpxSource {
+    /// 配置文件中的 `upx_path`
+    Config,
+    /// 环境变量 `PATH`
+    Path,
+    /// 安装版随程序分发的 `_up_/upx/`
+    Installed,
+    /// 开发环境的 `../upx/`
+    Development,
+    /// 便携版内嵌的副本
+    Embedded,
+}
+
+impl UpxSource {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            UpxSource::Config => "配置文件指定的路径",
+            UpxSource::Path => "系统 PATH",
+            UpxSource::Installed => "程序目录",
+            UpxSource::Development => "开发环境目录",
+            UpxSource::Embedded => "内嵌副本",
+        }
+    }
+}
+
+/// 选中的 UPX 及选择理由
+#[derive(Debug, Clone, Serialize)]
+pub struct UpxLocation {
+    pub path: PathBuf,
+    pub source: UpxSource,
+    /// `upx --version` 的第一行
+    pub version: String,
+    /// 例如"系统 PATH: upx 4.2.4，满足最低要求 3.95.0（已跳过: ...）"
+    pub reason: String,
+}
+
+impl UpxLocation {
+    pub fn packer(&self) -> UpxProcess {
+        UpxProcess::new(&self.path)
+    }
+}
+
+// ============================================================================
+// 查找
+// ============================================================================
+
+/// 按配置路径、`PATH`、安装版、开发环境、内嵌资源的顺序查找可用的 UPX
+///
+/// `configured` 来自 [`crate::AppConfig::upx_path`]；`embedded` 为便携版内嵌的
+/// UPX 数据，前面都找不到时校验后释放到用户缓存目录。不存在的位置直接略过，存在但
+/// 无法运行或版本过低的会记入选择理由；全部不可用时返回 [`CoreError::UpxUnusable`]。
+pub fn find_upx(configured: Option<&Path>, embedded: Option<EmbeddedUpx>) -> Result<UpxLocation> {
+    let mut rejected = Vec::new();
+
+    // 1. 用户在配置中指定的路径，不存在时也要提示
+    if let Some(path) = configured {
+        if path.is_file() {
+            if let Some(found) = probe(path, UpxSource::Config, &mut rejected) {
+                return Ok(found);
+            }
+        } else {
+            rejected.push(format!("{}: 配置的路径不存在", path.display()));
+        }
+    }
+
+    // 2. 系统 PATH，前面的版本过低时继续找后面的
+    for path in find_in_path() {
+        if let Some(found) = probe(&path, UpxSource::Path, &mut rejected) {
+            return Ok(found);
+        }
+    }
+
+    // 3. 打包后的位置（安装版）
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            let upx = exe_dir.join("_up_/upx").join(UPX_FILE_NAME);
+            if upx.is_file() {
+                if let Some(found) = probe(&upx, UpxSource::Installed, &mut rejected) {
+                    return Ok(found);
+                }
+            }
+        }
+    }
+
+    // 4. 开发环境
+    let dev_upx = Path::new("../upx").join(UPX_FILE_NAME);
+    if dev_upx.is_file() {
+        if let Some(found) = probe(&dev_upx, UpxSource::Development, &mut rejected) {
+            return Ok(found);
+        }
+    }
+
+    // 5. 便携版：校验后从嵌入资源释放
+    if let Some(embedded) = embedded {
+        match extract_embedded_upx(&embedded) {
+            Ok(path) => {
+                if let Some(found) = probe(&path, UpxSource::Embedded, &mut rejected) {
+                    return Ok(found);
+                }
+            }
+            Err(e) => rejected.push(format!("{}: {}", UpxSource::Embedded.describe(), e)),
+        }
+    }
+
+    if rejected.is_empty() {
+        Err(CoreError::UpxNotFound)
+    } else {
+        Err(CoreError::UpxUnusable(rejected.join("；")))
+    }
+}
+
+/// 按 `PATH` 中的顺序列出所有 UPX
+fn find_in_path() -> Vec<PathBuf> {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(UPX_FILE_NAME))
+        .filter(|candidate| candidate.is_file())
+        .collect()
+}
+
+/// 运行 `--version` 检查候选，不可用时把原因记入 `rejected`
+fn probe(path: &Path, source: UpxSource, rejected: &mut Vec<String>) -> Option<UpxLocation> {
+    let version = match UpxProcess::new(path).version() {
+        Ok(version) => version,
+        Err(e) => {
+            rejected.push(format!("{}: {}", path.display(), e));
+            return None;
+        }
+    };
+
+    match UpxVersion::parse(&version) {
+        Some(parsed) if parsed >= MIN_UPX_VERSION => {}
+        Some(parsed) => {
+            rejected.push(format!(
+                "{}: 版本 {} 低于最低要求 {}",
+                path.display(),
+                parsed,
+                MIN_UPX_VERSION
+            ));
+            return None;
+        }
+        None => {
+            rejected.push(format!(
+                "{}: 无法识别的版本信息 \"{}\"",
+                path.display(),
+                version
+            ));
+            return None;
+        }
+    }
+
+    let mut reason = format!(
+        "{}: {}，满足最低要求 {}",
+        source.describe(),
+        version,
+        MIN_UPX_VERSION
+    );
+    if !rejected.is_empty() {
+        reason.push_str(&format!("（已跳过: {}）", rejected.join("；")));
+    }
+
+    Some(UpxLocation {
+        path: path.to_path_buf(),
+        source,
+        version,
+        reason,
+    })
+}
+
+// ============================================================================
+// 便携版内嵌 UPX
+// ============================================================================
+
+/// 便携版内嵌的 UPX 及其构建时计算的 SHA-256
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedUpx<'a> {
+    /// gzip 压缩后的数据
+    pub compressed: &'a [u8],
+    /// 解压后数据的 SHA-256，小写十六进制
+    pub sha256: &'a str,
+}
+
+/// 释放内嵌的 UPX 到当前用户的缓存目录
+///
+/// 目录为 `<缓存目录>/UPX-Tools/upx/<sha256>/`，不同用户、不同版本互不共用。
+/// 已存在的文件校验通过才会复用，否则解压后写入同目录的临时文件，校验后再原子替换。
+pub fn extract_embedded_upx(embedded: &EmbeddedUpx) -> Result<PathBuf> {
+    // 同一份数据每个进程只需校验、释放一次
+    let extracted = EXTRACTED_UPX_PATHS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(path) = extracted
+        .lock()
+        .ok()
+        .and_then(|paths| paths.get(embedded.sha256).cloned())
+    {
+        return Ok(path);
+    }
+
+    let cache_dir = dirs::cache_dir().ok_or_else(|| {
+        CoreError::EmbeddedUpxExtract(io::Error::new(
+            io::ErrorKind::NotFound,
+            "无法获取用户缓存目录",
+        ))
+    })?;
+    let app_dir = cache_dir.join("UPX-Tools");
+    let dir = app_dir.join("upx").join(embedded.sha256);
+    create_private_dir(&app_dir).map_err(CoreError::EmbeddedUpxExtract)?;
+    create_private_dir(&dir).map_err(CoreError::EmbeddedUpxExtract)?;
+
+    let upx_path = dir.join(UPX_FILE_NAME);
+    if !is_intact(&upx_path, embedded.sha256) {
+        write_verified(&dir, &upx_path, embedded)?;
+    }
+
+    if let Ok(mut paths) = extracted.lock() {
+        paths.insert(embedded.sha256.to_string(), upx_path.clone());
+    }
+    Ok(upx_path)
+}
+
+/// 文件存在且摘要一致
+fn is_intact(path: &Path, sha256: &str) -> bool {
+    hash::sha256_file(path).is_ok_and(|actual| actual == sha256)
+}
+
+/// 解压并写入临时文件、校验后重命名为目标文件
+fn write_verified(dir: &Path, upx_path: &Path, embedded: &EmbeddedUpx) -> Result<()> {
+    // 解压失败或与构建时的摘要不符，说明程序文件已被篡改
+    let mut data = Vec::new();
+    GzDecoder::new(embedded.compressed)
+        .read_to_end(&mut data)
+        .map_err(|_| CoreError::EmbeddedUpxCorrupt)?;
+    if hash::sha256_hex(&data) != embedded.sha256 {
+        return Err(CoreError::EmbeddedUpxCorrupt);
+    }
+
+    let mut temp = NamedTempFile::new_in(dir).map_err(CoreError::EmbeddedUpxExtract)?;
+    temp.write_all(&data)
+        .and_then(|_| temp.as_file().sync_all())
+        .map_err(CoreError::EmbeddedUpxExtract)?;
+
+    if !is_intact(temp.path(), embedded.sha256) {
+        return Err(CoreError::EmbeddedUpxCorrupt);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(temp.path(), fs::Permissions::from_mode(0o700))
+            .map_err(CoreError::EmbeddedUpxExtract)?;
+    }
+
+    match temp.persist(upx_path) {
+        Ok(_) => Ok(()),
+        // 另一个进程正在使用目标文件时无法替换；它已校验通过则直接使用
+        Err(_) if is_intact(upx_path, embedded.sha256) => Ok(()),
+        Err(e) => Err(CoreError::EmbeddedUpxExtract(e.error)),
+    }
+}
+
+/// 创建仅当前用户可访问的目录
+///
+/// Windows 上缓存目录位于用户配置文件中，已由系统 ACL 限制访问。
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(())
+}
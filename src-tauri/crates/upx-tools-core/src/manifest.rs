@@ -0,0 +1,338 @@
+//! 加壳清单：记录每个加壳文件的原始哈希、加壳后哈希、UPX 版本与参数，用于复现与审计
+//!
+//! 可以在每个输出文件旁写一个 `<文件名>.upx.json`，也可以每个输出文件夹写一个
+//! `upx-manifest.json`。[`verify_manifest`] 按清单检查文件夹，报告被修改、缺失与未登记的文件。
+
+use crate::args::build_compress_args;
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::hash::sha256_file;
+use crate::options::{UpxMode, UpxOptions};
+use crate::result::{UpxResult, UpxStatus};
+use crate::scan;
+use crate::tune::Strategy;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// 每个文件夹一个清单时的文件名
+pub const FOLDER_MANIFEST_NAME: &str = "upx-manifest.json";
+
+/// 单个文件的清单后缀：`app.exe` -> `app.exe.upx.json`
+pub const SIDECAR_SUFFIX: &str = ".upx.json";
+
+/// 批量处理时多个文件写入同一个文件夹清单，逐个读改写
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 清单写法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestMode {
+    /// 不写清单
+    #[default]
+    Off,
+    /// 每个加壳文件旁一个 `<文件名>.upx.json`
+    Sidecar,
+    /// 每个输出文件夹一个 `upx-manifest.json`
+    Folder,
+}
+
+impl ManifestMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Sidecar => "sidecar",
+            Self::Folder => "folder",
+        }
+    }
+
+    /// 输出文件对应的清单路径，不写清单时为空
+    pub fn manifest_path(self, output_file: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Off => None,
+            Self::Sidecar => {
+                let mut name = output_file.file_name()?.to_os_string();
+                name.push(SIDECAR_SUFFIX);
+                Some(output_file.with_file_name(name))
+            }
+            Self::Folder => Some(output_file.with_file_name(FOLDER_MANIFEST_NAME)),
+        }
+    }
+}
+
+impl fmt::Display for ManifestMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ManifestMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "sidecar" => Ok(Self::Sidecar),
+            "folder" => Ok(Self::Folder),
+            _ => Err(format!("无效的清单方式 {}，可选 off、sidecar、folder", s)),
+        }
+    }
+}
+
+/// 一个加壳文件的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 加壳后的文件名，相对清单所在的文件夹
+    pub file: String,
+    /// 加壳前的文件路径（绝对路径）
+    pub source_file: String,
+    pub original_sha256: String,
+    pub packed_sha256: String,
+    pub original_size: u64,
+    pub packed_size: u64,
+    /// `upx --version` 的第一行，例如 `upx 4.2.4`
+    pub upx_version: String,
+    /// 传给 UPX 的参数，由 [`build_compress_args`] 生成；自动选择或体积预算时为实际采用的压缩方式
+    pub args: Vec<String>,
+    pub packed_at: DateTime<Local>,
+}
+
+impl ManifestEntry {
+    /// 由加壳成功的结果生成；`options` 为实际执行时的选项（已去掉不支持的选项、冲突改名后）
+    ///
+    /// 不是加壳成功（含警告）或缺少哈希时为空。
+    pub fn new(
+        options: &UpxOptions,
+        capabilities: &UpxCapabilities,
+        result: &UpxResult,
+    ) -> Option<Self> {
+        if options.mode != UpxMode::Compress
+            || !matches!(result.status, UpxStatus::Success | UpxStatus::Warning)
+        {
+            return None;
+        }
+        // 自动选择或体积预算时记录实际采用的压缩方式，而不是请求中的 auto 或固定级别
+        let chosen = result
+            .strategies
+            .iter()
+            .find(|trial| trial.chosen)
+            .and_then(|trial| trial.strategy.parse::<Strategy>().ok());
+        let options = match &chosen {
+            Some(strategy) => strategy.apply(options),
+            None => options.clone(),
+        };
+
+        Some(Self {
+            file: Path::new(&result.output_file)
+                .file_name()?
+                .to_string_lossy()
+                .into_owned(),
+            source_file: std::path::absolute(&result.input_file)
+                .map_or_else(|_| result.input_file.clone(), |p| p.display().to_string()),
+            original_sha256: result.input_sha256.clone()?,
+            packed_sha256: result.output_sha256.clone()?,
+            original_size: result.original_size,
+            packed_size: result.output_size,
+            upx_version: capabilities.version_line.clone(),
+            args: build_compress_args(&options, options.is_overwrite()).collect(),
+            packed_at: Local::now(),
+        })
+    }
+}
+
+/// 清单文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|source| CoreError::ManifestRead {
+            path: path.display().to_string(),
+            source,
+        })?;
+        serde_json::from_str(&content).map_err(|source| CoreError::ManifestParse {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| CoreError::ManifestWrite(e.into()))?;
+        fs::write(path, content).map_err(CoreError::ManifestWrite)
+    }
+}
+
+/// 按写法把记录写入清单，返回清单路径
+///
+/// 文件夹清单中同名文件的旧记录会被替换。
+pub fn write_manifest(
+    mode: ManifestMode,
+    output_file: &Path,
+    entry: ManifestEntry,
+) -> Result<Option<PathBuf>> {
+    let Some(path) = mode.manifest_path(output_file) else {
+        return Ok(None);
+    };
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = match mode {
+        ManifestMode::Folder if path.exists() => Manifest::load(&path)?,
+        _ => Manifest::default(),
+    };
+    manifest.entries.retain(|e| e.file != entry.file);
+    manifest.entries.push(entry);
+    manifest.entries.sort_by(|a, b| a.file.cmp(&b.file));
+    manifest.save(&path)?;
+    Ok(Some(path))
+}
+
+// ============================================================================
+// 校验
+// ============================================================================
+
+/// 文件与清单的比对结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftStatus {
+    /// 与清单中的加壳后哈希一致
+    Ok,
+    /// 内容与清单不一致
+    Modified,
+    /// 清单中有记录，文件不存在
+    Missing,
+    /// 可处理的文件不在任何清单中
+    Untracked,
+}
+
+impl DriftStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "一致",
+            Self::Modified => "已修改",
+            Self::Missing => "缺失",
+            Self::Untracked => "未登记",
+        }
+    }
+}
+
+/// 一个文件的比对结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DriftEntry {
+    pub status: DriftStatus,
+    pub file: String,
+    /// 记录该文件的清单，未登记时为空
+    pub manifest: Option<String>,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+}
+
+/// 文件夹的校验结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// 找到的清单文件
+    pub manifests: Vec<String>,
+    pub entries: Vec<DriftEntry>,
+}
+
+impl VerifyReport {
+    /// 与清单不一致的文件
+    pub fn drift(&self) -> impl Iterator<Item = &DriftEntry> {
+        self.entries.iter().filter(|e| e.status != DriftStatus::Ok)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.drift().next().is_none()
+    }
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == FOLDER_MANIFEST_NAME || name.ends_with(SIDECAR_SUFFIX))
+}
+
+/// 比较路径时使用规范路径，相对路径、`..` 与符号链接都指向同一个键；文件不存在时保持原样
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 按文件夹中的清单（文件夹清单与单文件清单）校验加壳文件
+///
+/// 清单中记录的加壳前文件不算未登记，方便输出与源文件在同一文件夹的情况。
+pub fn verify_manifest(folder: &Path, include_subfolders: bool) -> Result<VerifyReport> {
+    if !folder.exists() {
+        return Err(CoreError::PathNotFound(folder.display().to_string()));
+    }
+    if !folder.is_dir() {
+        return Err(CoreError::NotADirectory(folder.display().to_string()));
+    }
+
+    let files = scan::list_files(folder, include_subfolders);
+    let mut report = VerifyReport::default();
+    let mut tracked = HashSet::new();
+
+    for manifest_path in files.iter().filter(|path| is_manifest(path)) {
+        let manifest = Manifest::load(manifest_path)?;
+        let dir = manifest_path.parent().unwrap_or(folder);
+        let manifest_name = manifest_path.display().to_string();
+
+        for entry in manifest.entries {
+            let file = dir.join(&entry.file);
+            let actual = match sha256_file(&file) {
+                Ok(hash) => Some(hash),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(source) => {
+                    return Err(CoreError::ManifestRead {
+                        path: file.display().to_string(),
+                        source,
+                    })
+                }
+            };
+            let status = match &actual {
+                None => DriftStatus::Missing,
+                Some(hash) if *hash == entry.packed_sha256 => DriftStatus::Ok,
+                Some(_) => DriftStatus::Modified,
+            };
+
+            report.entries.push(DriftEntry {
+                status,
+                file: file.display().to_string(),
+                manifest: Some(manifest_name.clone()),
+                expected_sha256: Some(entry.packed_sha256),
+                actual_sha256: actual,
+            });
+            tracked.insert(canonical(&file));
+            tracked.insert(canonical(Path::new(&entry.source_file)));
+        }
+        report.manifests.push(manifest_name);
+    }
+
+    if report.manifests.is_empty() {
+        return Err(CoreError::ManifestNotFound(folder.display().to_string()));
+    }
+
+    for file in files {
+        if scan::is_supported(&file) && !tracked.contains(&canonical(&file)) {
+            report.entries.push(DriftEntry {
+                status: DriftStatus::Untracked,
+                file: file.display().to_string(),
+                manifest: None,
+                expected_sha256: None,
+                actual_sha256: sha256_file(&file).ok(),
+            });
+        }
+    }
+
+    report.manifests.sort();
+    report.entries.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(report)
+}
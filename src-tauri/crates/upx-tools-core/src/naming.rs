@@ -0,0 +1,148 @@
+//! 输出文件命名模板与冲突处理
+//!
+//! 模板中可以使用以下占位符，相对路径相对于输入文件所在目录：
+//!
+//! - `{dir}`：输入文件所在目录
+//! - `{name}`：完整文件名，例如 `app.exe`
+//! - `{stem}`：不含扩展名的文件名，例如 `app`
+//! - `{ext}`：不含点的扩展名，例如 `exe`；没有扩展名时为空，末尾多余的 `.` 会被去掉
+//!
+//! 例如 `{stem}.packed.{ext}` 输出 `app.packed.exe`，`{dir}/packed/{name}`
+//! 输出到同目录下的 `packed` 子目录。
+
+use crate::error::{CoreError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 不覆盖原文件时的默认模板，与旧版本的 `name_packed.ext` 一致
+pub const DEFAULT_NAME_TEMPLATE: &str = "{stem}_packed.{ext}";
+
+/// 按模板生成输出路径
+pub fn render_output_name(template: &str, input: &Path) -> Result<PathBuf> {
+    let invalid =
+        |reason: &str| CoreError::InvalidNameTemplate(format!("{}: {}", template, reason));
+
+    let dir = input.parent().unwrap_or(Path::new(""));
+    // 相对路径的输入没有目录部分，`{dir}/x` 不能变成根目录下的 `/x`
+    let dir_text = if dir.as_os_str().is_empty() {
+        ".".into()
+    } else {
+        dir.to_string_lossy()
+    };
+    let name = input
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let ext = input
+        .extension()
+        .map(|e| e.to_string_lossy())
+        .unwrap_or_default();
+
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| invalid("缺少 }"))? + start;
+        match &rest[start + 1..end] {
+            "dir" => rendered.push_str(&dir_text),
+            "name" => rendered.push_str(&name),
+            "stem" => rendered.push_str(&stem),
+            "ext" => rendered.push_str(&ext),
+            other => return Err(invalid(&format!("未知的占位符 {{{}}}", other))),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    let rendered = rendered.trim_end_matches('.');
+    if rendered.is_empty() || rendered.ends_with(['/', '\\']) {
+        return Err(invalid("生成的文件名为空"));
+    }
+
+    // 使用了 `{dir}` 的模板已包含目录，其余相对于输入文件所在目录
+    if template.contains("{dir}") {
+        Ok(PathBuf::from(rendered))
+    } else {
+        Ok(dir.join(rendered))
+    }
+}
+
+// ============================================================================
+// 冲突处理
+// ============================================================================
+
+/// 输出文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// 覆盖已有文件（向 UPX 传入 `--force-overwrite`）
+    Overwrite,
+    /// 跳过该文件，不算失败
+    #[default]
+    Skip,
+    /// 改用 `name_1.ext`、`name_2.ext` 等未被占用的文件名
+    Rename,
+    /// 视为失败
+    Fail,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Overwrite => "overwrite",
+            Self::Skip => "skip",
+            Self::Rename => "rename",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            "rename" => Ok(Self::Rename),
+            "fail" => Ok(Self::Fail),
+            _ => Err(format!(
+                "无效的冲突处理方式 {}，可选 overwrite、skip、rename、fail",
+                s
+            )),
+        }
+    }
+}
+
+/// 在同一目录下找一个不存在的文件名：`app.exe` -> `app_1.exe`、`app_2.exe` ...
+pub fn available_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .expect("无限序列中总能找到未被占用的文件名")
+}
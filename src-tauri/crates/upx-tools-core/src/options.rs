@@ -0,0 +1,114 @@
+use crate::budget::SizeBudget;
+use crate::manifest::ManifestMode;
+use crate::naming::ConflictPolicy;
+use serde::{Deserialize, Serialize};
+
+/// UPX 操作模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpxMode {
+    /// 加壳压缩（默认参数）
+    Compress,
+    /// 脱壳解压（`-d`）
+    Decompress,
+    /// 测试已加壳文件（`-t`）
+    Test,
+    /// 列出压缩信息（`-l`）
+    List,
+}
+
+impl UpxMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Compress => "compress",
+            Self::Decompress => "decompress",
+            Self::Test => "test",
+            Self::List => "list",
+        }
+    }
+
+    /// 是否为只读取文件、不产生输出的模式
+    pub fn is_inspect(self) -> bool {
+        matches!(self, Self::Test | Self::List)
+    }
+}
+
+/// NRV 压缩算法的变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NrvMethod {
+    Nrv2b,
+    Nrv2d,
+    Nrv2e,
+}
+
+impl NrvMethod {
+    pub const ALL: [Self; 3] = [Self::Nrv2b, Self::Nrv2d, Self::Nrv2e];
+
+    /// 对应的 UPX 参数，例如 `--nrv2e`
+    pub fn as_arg(self) -> &'static str {
+        match self {
+            Self::Nrv2b => "--nrv2b",
+            Self::Nrv2d => "--nrv2d",
+            Self::Nrv2e => "--nrv2e",
+        }
+    }
+}
+
+/// 单个文件的处理选项，与前端 `process_upx` 传入的结构一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpxOptions {
+    pub mode: UpxMode,
+    pub input_file: String,
+    pub output_file: String,
+    /// `1`-`9`、`best`、`brute`，或 `auto` 由 [`AutoTuner`](crate::tune::AutoTuner)
+    /// 逐个尝试候选的压缩方式
+    pub compression_level: String,
+    pub backup: bool,
+    pub lzma: bool,
+    /// 指定 NRV 算法变体，开启 LZMA 时不生效；为空时由 UPX 选择
+    #[serde(default)]
+    pub nrv: Option<NrvMethod>,
+    pub ultra_brute: bool,
+    pub force: bool,
+    /// 使用已注册的指定版本 UPX，例如 `3.96.0`；为空时使用默认的 UPX
+    #[serde(default)]
+    pub upx_version: Option<String>,
+    /// 输出到其他文件且该文件已存在时的处理方式
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    /// 加壳成功后写入清单的方式
+    #[serde(default)]
+    pub manifest: ManifestMode,
+    /// 加壳输出的体积预算，设置后逐个尝试候选压缩方式直到满足
+    #[serde(default)]
+    pub budget: Option<SizeBudget>,
+}
+
+impl UpxOptions {
+    /// 输出路径与输入相同时直接覆盖原文件
+    pub fn is_overwrite(&self) -> bool {
+        self.input_file == self.output_file
+    }
+
+    /// 是否为自动选择压缩方式的加壳任务
+    pub fn is_auto(&self) -> bool {
+        self.mode == UpxMode::Compress && self.compression_level == "auto"
+    }
+
+    /// 带体积预算的加壳任务的预算
+    pub fn compress_budget(&self) -> Option<SizeBudget> {
+        self.budget.filter(|_| self.mode == UpxMode::Compress)
+    }
+
+    /// 是否需要逐个尝试候选压缩方式（自动选择或设置了体积预算）
+    pub fn tries_strategies(&self) -> bool {
+        self.is_auto() || self.compress_budget().is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFolderOptions {
+    pub folder_path: String,
+    pub include_subfolders: bool,
+}
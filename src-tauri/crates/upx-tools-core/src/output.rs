@@ -0,0 +1,192 @@
+//! UPX 输出解析与结果分类
+
+use crate::budget::BUDGET_NOT_MET;
+use crate::options::UpxMode;
+use crate::result::UpxStatus;
+
+const IGNORED_PREFIXES: &[&str] = &[
+    "---",
+    "File size",
+    "Ratio",
+    "Format",
+    "Name",
+    "Ultimate Packer",
+    "Copyright",
+    "UPX ",
+];
+
+/// 过滤掉 UPX 的横幅、表头等无用行
+pub fn filter_output_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .filter_map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                return None;
+            }
+            let should_keep = !IGNORED_PREFIXES
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix));
+            should_keep.then_some(trimmed)
+        })
+        .collect()
+}
+
+/// 成功时附加到日志中的 UPX 输出
+pub fn format_upx_output(stdout: &str, stderr: &str) -> String {
+    let combined = format!("{}{}", stdout, stderr);
+    let lines = filter_output_lines(&combined);
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!("\n\nUPX 输出:\n{}", lines.join("\n"))
+}
+
+/// `upx -l` 的表格输出需要原样保留
+pub fn format_upx_listing(stdout: &str) -> String {
+    let lines: Vec<&str> = stdout
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| {
+            !["Ultimate Packer", "Copyright", "UPX "]
+                .iter()
+                .any(|prefix| line.trim_start().starts_with(prefix))
+        })
+        .collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!("\n\nUPX 输出:\n{}", lines.join("\n"))
+}
+
+// UPX 异常类型、输出中的特征文本与对应的提示
+const ERROR_PATTERNS: &[(&str, &[&str], &str)] = &[
+    ("AlreadyPackedException", &["AlreadyPackedException", "already packed"], "[错误] 文件已经被 UPX 加壳过了\n\n解决方案:\n  - 如果要重新压缩，请先使用「脱壳解压」功能\n  - 或者选择其他未加壳的文件"),
+    ("NotPackedException", &["NotPackedException", "not packed"], "[错误] 文件未被 UPX 加壳，无法脱壳\n\n解决方案:\n  - 请确认文件是否使用 UPX 加壳\n  - 或者选择「加壳压缩」功能"),
+    ("CantPackException", &["CantPackException"], "[错误] 无法压缩此文件\n\n可能原因:\n  - 文件格式不支持\n  - 文件已损坏\n  - 文件受保护（尝试启用「强制压缩」选项）"),
+    ("OverlayException", &["OverlayException"], "[错误] 文件包含附加数据（Overlay）\n\n解决方案:\n  - 某些文件在末尾附加了额外数据\n  - 尝试启用「强制压缩」选项\n  - 或使用其他工具移除附加数据"),
+    ("IOException", &["IOException", "can't open"], "[错误] 文件访问失败\n\n可能原因:\n  - 文件被其他程序占用\n  - 文件权限不足\n  - 文件路径包含特殊字符"),
+    ("NotCompressibleException", &["NotCompressibleException"], "[错误] 文件无法压缩\n\n可能原因:\n  - 文件已经高度压缩\n  - 压缩后反而会变大\n  - UPX 自动跳过了此文件"),
+];
+
+// 未识别出异常类型时的提示开头
+const GENERIC_ERROR: &str = "[错误] UPX 处理失败";
+
+/// 将 UPX 的错误输出转换为带解决方案的提示
+pub fn parse_upx_error(stdout: &str, stderr: &str) -> String {
+    let combined = format!("{}{}", stdout, stderr);
+
+    for (_, patterns, message) in ERROR_PATTERNS {
+        if patterns.iter().any(|p| combined.contains(p)) {
+            return message.to_string();
+        }
+    }
+
+    // 通用错误信息
+    let lines = filter_output_lines(&combined);
+    if lines.is_empty() {
+        return format!("{}\n\n请检查文件是否正常，或尝试其他选项", GENERIC_ERROR);
+    }
+    format!("{}\n\n错误信息:\n{}", GENERIC_ERROR, lines.join("\n"))
+}
+
+/// 由失败原因（[`parse_upx_error`] 的提示或原始输出）归类出异常类型
+///
+/// 识别不出的 UPX 错误为 `UpxError`，没有运行 UPX 的失败（预检、启动失败等）为 `Other`。
+pub fn failure_kind(reason: &str) -> &'static str {
+    ERROR_PATTERNS
+        .iter()
+        .find(|(_, patterns, message)| {
+            reason.starts_with(message) || patterns.iter().any(|p| reason.contains(p))
+        })
+        .map(|(kind, _, _)| *kind)
+        .unwrap_or(if reason.starts_with(BUDGET_NOT_MET) {
+            "BudgetNotMet"
+        } else if reason.starts_with(GENERIC_ERROR) {
+            "UpxError"
+        } else {
+            "Other"
+        })
+}
+
+// UPX 退出码：0 成功，1 错误，2 警告
+pub const UPX_EXIT_OK: i32 = 0;
+pub const UPX_EXIT_ERROR: i32 = 1;
+pub const UPX_EXIT_WARN: i32 = 2;
+
+// 不算失败、只需跳过的情况
+const SKIP_PATTERNS: &[(&[&str], &str)] = &[
+    (
+        &["AlreadyPackedException", "already packed"],
+        "文件已经被 UPX 加壳过了",
+    ),
+    (&["NotCompressibleException"], "文件无法进一步压缩"),
+];
+
+/// 匹配无需处理、应记为跳过的情况；未加壳的文件只在脱壳时跳过
+pub fn match_skip_reason(mode: UpxMode, stdout: &str, stderr: &str) -> Option<&'static str> {
+    let combined = format!("{}{}", stdout, stderr);
+    if mode == UpxMode::Decompress && combined.contains("NotPackedException") {
+        return Some("文件未被 UPX 加壳");
+    }
+    SKIP_PATTERNS
+        .iter()
+        .find(|(patterns, _)| patterns.iter().any(|p| combined.contains(p)))
+        .map(|(_, reason)| *reason)
+}
+
+// 可能换一个 UPX 版本就能脱壳的情况：UPX 明确指出格式需要其他版本。
+// 未加壳的普通文件同样报 NotPackedException，不能据此重试
+const VERSION_MISMATCH_PATTERNS: &[&str] = &[
+    "need a newer version",
+    "need an older version",
+    "obsolete version",
+];
+
+/// 脱壳失败是否可能与 UPX 版本有关，用于决定是否换其他已注册版本重试
+pub fn is_version_mismatch(stdout: &str, stderr: &str) -> bool {
+    let combined = format!("{}{}", stdout, stderr);
+    VERSION_MISMATCH_PATTERNS
+        .iter()
+        .any(|p| combined.contains(p))
+}
+
+/// 根据退出码和输出内容判断处理状态
+pub fn classify_upx_exit(
+    mode: UpxMode,
+    exit_code: Option<i32>,
+    stdout: &str,
+    stderr: &str,
+) -> (UpxStatus, Option<String>) {
+    if exit_code == Some(UPX_EXIT_OK) {
+        return (UpxStatus::Success, None);
+    }
+
+    if let Some(reason) = match_skip_reason(mode, stdout, stderr) {
+        return (UpxStatus::Skipped, Some(reason.to_string()));
+    }
+
+    if exit_code == Some(UPX_EXIT_WARN) {
+        let lines = filter_output_lines(&format!("{}{}", stdout, stderr)).join("\n");
+        return (UpxStatus::Warning, Some(lines));
+    }
+
+    (UpxStatus::Failed, Some(parse_upx_error(stdout, stderr)))
+}
+
+/// 以 KB/MB/GB 显示文件大小
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
@@ -0,0 +1,100 @@
+//! 输出目录模式：处理结果按源目录结构写入输出根目录，原文件保持不变
+//!
+//! 相对路径以用户选择的文件或文件夹为起点，并保留其名称。例如处理
+//! `D:\build\release` 时，`release\bin\app.exe` 输出到 `<输出目录>\release\bin\app.exe`；
+//! 同时处理多个文件夹也不会互相覆盖。
+
+use crate::error::{CoreError, Result};
+use crate::scan;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// `input` 在输出根目录下对应的路径
+///
+/// `source` 为用户选择的文件或文件夹，`input` 为其中的文件（或就是 `source` 本身）。
+/// 结果与输入相同（输出目录就是源文件所在目录）时返回 [`CoreError::OutputDirIsSource`]。
+pub fn mirrored_output(source: &Path, input: &Path, output_root: &Path) -> Result<PathBuf> {
+    let base = source.parent().unwrap_or(Path::new(""));
+    let relative = input
+        .strip_prefix(base)
+        .ok()
+        .filter(|r| is_plain_relative(r))
+        .map(Path::to_path_buf)
+        .or_else(|| input.file_name().map(PathBuf::from))
+        .unwrap_or_default();
+
+    let output = output_root.join(relative);
+    if same_path(&output, input) {
+        return Err(CoreError::OutputDirIsSource(
+            output_root.to_string_lossy().into_owned(),
+        ));
+    }
+    Ok(output)
+}
+
+/// 只包含普通路径段，拼接到输出根目录后不会跳出该目录
+fn is_plain_relative(path: &Path) -> bool {
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// 尽量按规范化后的路径比较，文件不存在时比较原始路径
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// `path` 是否位于 `root` 之内，用于在扫描结果中排除输出目录本身
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    path.starts_with(root)
+}
+
+/// `source` 中不会被处理的文件及其在输出目录中的位置，不修改任何文件
+///
+/// `source` 为单个文件时返回空列表。位于输出目录内的文件会被跳过，
+/// 以免输出目录放在源文件夹中时重复复制。
+pub fn unsupported_copies(
+    source: &Path,
+    include_subfolders: bool,
+    output_root: &Path,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if !source.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    scan::list_files(source, include_subfolders)
+        .into_iter()
+        .filter(|file| !scan::is_supported(file) && !is_within(file, output_root))
+        .map(|file| {
+            let target = mirrored_output(source, &file, output_root)?;
+            Ok((file, target))
+        })
+        .collect()
+}
+
+/// 把 `source` 中不会被处理的文件原样复制到输出目录，使输出成为完整的目录树
+///
+/// 返回复制的文件数。
+pub fn copy_unsupported_files(
+    source: &Path,
+    include_subfolders: bool,
+    output_root: &Path,
+) -> Result<usize> {
+    let copies = unsupported_copies(source, include_subfolders, output_root)?;
+
+    for (file, target) in &copies {
+        let copy_error = |e| CoreError::CopyUnsupported(file.to_string_lossy().into_owned(), e);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(copy_error)?;
+        }
+        fs::copy(file, target).map_err(copy_error)?;
+    }
+
+    Ok(copies.len())
+}
@@ -0,0 +1,155 @@
+//! 加壳后端抽象：真实的 UPX 进程或测试用的内存实现
+
+use crate::args::{
+    build_args, build_compress_args, build_decompress_args, build_list_args, build_test_args,
+};
+use crate::budget::BudgetCheck;
+use crate::capabilities::{probe_capabilities, UpxCapabilities};
+use crate::error::{CoreError, Result};
+use crate::options::UpxOptions;
+use crate::process::create_silent_command;
+use crate::tune::{Strategy, StrategyTrial};
+use encoding_rs::GBK;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 一次 UPX 调用的原始结果，由 [`crate::output::classify_upx_exit`] 解析
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackerOutput {
+    /// 被信号终止等情况下没有退出码
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// 自动选择压缩方式时各候选策略的结果
+    pub strategies: Vec<StrategyTrial>,
+    /// 带体积预算的任务的检查结果
+    pub budget: Option<BudgetCheck>,
+}
+
+/// 加壳后端
+///
+/// 实现只负责执行并返回原始输出；状态分类、备份、大小统计都在
+/// [`crate::process::run_upx`] 中完成，因此不同后端的行为保持一致。
+/// 只有无法启动后端时才返回 `Err`。
+pub trait Packer: Send + Sync {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput>;
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput>;
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput>;
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput>;
+
+    /// `upx --version` 的第一行
+    fn version(&self) -> Result<String>;
+
+    /// 版本与支持的格式、选项；默认只包含版本行
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.version()
+            .map(|version| UpxCapabilities::parse(&version, ""))
+    }
+
+    /// 处理 `options` 时所用 UPX 的版本与功能；多版本后端按任务指定的 `upx_version` 选择
+    fn capabilities_for(&self, _options: &UpxOptions) -> Result<UpxCapabilities> {
+        self.capabilities()
+    }
+
+    /// 处理该任务时实际执行的命令（程序与参数），用于预览
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        Ok(std::iter::once("upx".to_string())
+            .chain(build_args(options, is_overwrite))
+            .collect())
+    }
+
+    /// 不运行 UPX 判断文件是否已被加壳，用于预览
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        has_upx_magic(path)
+    }
+
+    /// 自动选择或体积预算时尝试的压缩方式；不做选择的后端为空
+    fn strategies(&self) -> &[Strategy] {
+        &[]
+    }
+}
+
+/// 调用 UPX 可执行文件的后端
+#[derive(Debug, Clone)]
+pub struct UpxProcess {
+    path: PathBuf,
+}
+
+impl UpxProcess {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn run<I>(&self, args: I) -> Result<PackerOutput>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut cmd: Command = create_silent_command(&self.path);
+        let output = cmd.args(args).output().map_err(CoreError::Spawn)?;
+
+        let (stdout, _, _) = GBK.decode(&output.stdout);
+        let (stderr, _, _) = GBK.decode(&output.stderr);
+
+        Ok(PackerOutput {
+            exit_code: output.status.code(),
+            stdout: stdout.into_owned(),
+            stderr: stderr.into_owned(),
+            strategies: Vec::new(),
+            budget: None,
+        })
+    }
+}
+
+impl Packer for UpxProcess {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.run(build_compress_args(options, is_overwrite))
+    }
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.run(build_decompress_args(options, is_overwrite))
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.run(build_test_args(options))
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.run(build_list_args(options))
+    }
+
+    /// 来自缓存的探测结果，不会每次都启动 UPX
+    fn version(&self) -> Result<String> {
+        self.capabilities().map(|c| c.version_line)
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        probe_capabilities(&self.path)
+    }
+
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        Ok(std::iter::once(self.path.to_string_lossy().into_owned())
+            .chain(build_args(options, is_overwrite))
+            .collect())
+    }
+}
+
+/// UPX 加壳后在文件头部写入的标记
+const UPX_MAGIC: &[u8] = b"UPX!";
+
+/// 在文件开头 64 KiB 内查找 UPX 标记（PE 的区段表、ELF 的程序头都在这个范围内）
+pub fn has_upx_magic(path: &Path) -> io::Result<bool> {
+    let mut head = Vec::with_capacity(64 * 1024);
+    fs::File::open(path)?
+        .take(64 * 1024)
+        .read_to_end(&mut head)?;
+    Ok(head.windows(UPX_MAGIC.len()).any(|w| w == UPX_MAGIC))
+}
@@ -0,0 +1,59 @@
+//! 缓存索引与增量索引共用的持久化方式：跨进程文件锁加原子替换
+//!
+//! GUI 与命令行可能同时读改写同一个索引文件，写入方先取得锁，
+//! 再写入同目录的临时文件后改名替换，读取方不会看到写了一半的内容。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use tempfile::NamedTempFile;
+
+/// 持有期间锁定：同一进程的线程之间用互斥锁，多个进程之间用锁文件
+pub(crate) struct FileLock {
+    _guard: MutexGuard<'static, ()>,
+    _file: File,
+}
+
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+/// 独占锁，用于读改写
+///
+/// 先等其他进程释放锁文件，等待期间不占用进程内的锁。
+pub(crate) fn lock(path: &Path, mutex: &'static Mutex<()>) -> io::Result<FileLock> {
+    let file = open_lock_file(path)?;
+    file.lock()?;
+    Ok(FileLock {
+        _guard: mutex.lock().unwrap_or_else(|e| e.into_inner()),
+        _file: file,
+    })
+}
+
+/// 共享锁，用于只读：多个进程可以同时读取，但要等正在进行的写入完成
+pub(crate) fn lock_shared(path: &Path, mutex: &'static Mutex<()>) -> io::Result<FileLock> {
+    let file = open_lock_file(path)?;
+    file.lock_shared()?;
+    Ok(FileLock {
+        _guard: mutex.lock().unwrap_or_else(|e| e.into_inner()),
+        _file: file,
+    })
+}
+
+/// 写入同目录的临时文件后改名替换 `path`
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let mut temp = NamedTempFile::new_in(dir)?;
+    temp.write_all(content)?;
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! 预览模式：列出批量处理将对每个文件做什么，不修改任何文件
+//!
+//! 检查顺序与 [`crate::process::run_upx`] 一致，命令行来自
+//! [`Packer::command_line`]，即实际执行时传给 UPX 的参数。批量预览同样按增量索引
+//! 跳过未变化的文件，并标出去重时将复制代表文件结果的文件。
+
+use crate::batch::{duplicate_outputs, identical_jobs};
+use crate::error::CoreError;
+use crate::incremental::IncrementalIndex;
+use crate::naming::{available_path, ConflictPolicy};
+use crate::options::{UpxMode, UpxOptions};
+use crate::packer::Packer;
+use crate::process::{
+    backup_path, supported_options, validate_file_writable, validate_upx_and_file,
+};
+use crate::result::UpxResult;
+use serde::Serialize;
+use std::path::Path;
+
+/// 预览中文件将得到的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Pack,
+    Unpack,
+    Test,
+    List,
+    /// 不会调用 UPX，或 UPX 会以跳过结束
+    Skip,
+    /// 预检就会失败
+    Fail,
+}
+
+impl PlanAction {
+    fn for_mode(mode: UpxMode) -> Self {
+        match mode {
+            UpxMode::Compress => Self::Pack,
+            UpxMode::Decompress => Self::Unpack,
+            UpxMode::Test => Self::Test,
+            UpxMode::List => Self::List,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Pack => "加壳",
+            Self::Unpack => "脱壳",
+            Self::Test => "测试",
+            Self::List => "列出",
+            Self::Skip => "跳过",
+            Self::Fail => "失败",
+        }
+    }
+}
+
+/// 单个文件的预览结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanEntry {
+    pub action: PlanAction,
+    pub input_file: String,
+    pub output_file: String,
+    /// 将创建的备份文件
+    pub backup_file: Option<String>,
+    /// 程序与参数，预检失败时为空
+    pub command: Vec<String>,
+    /// 可直接复制到终端的命令行
+    pub command_line: String,
+    /// 跳过或失败的原因
+    pub reason: Option<String>,
+    pub warnings: Vec<String>,
+    /// 内容与处理方式相同的代表文件；代表文件处理成功时复制其结果，不运行 UPX
+    pub duplicate_of: Option<String>,
+}
+
+impl PlanEntry {
+    /// 预检失败的条目
+    pub fn failed(input_file: &str, output_file: &str, reason: String) -> Self {
+        Self {
+            action: PlanAction::Fail,
+            input_file: input_file.to_string(),
+            output_file: output_file.to_string(),
+            backup_file: None,
+            command: Vec::new(),
+            command_line: String::new(),
+            reason: Some(reason),
+            warnings: Vec::new(),
+            duplicate_of: None,
+        }
+    }
+
+    /// 增量处理中自上次处理后未变化的文件，`result` 为 [`IncrementalIndex::unchanged`] 的结果
+    pub fn unchanged(result: &UpxResult) -> Self {
+        Self {
+            action: PlanAction::Skip,
+            ..Self::failed(
+                &result.input_file,
+                &result.output_file,
+                result.reason.clone().unwrap_or_default(),
+            )
+        }
+    }
+
+    /// 去重时将复制 `representative` 的结果；不会运行 UPX 的条目保持不变
+    pub fn mark_duplicate(&mut self, representative: &str) {
+        if matches!(self.action, PlanAction::Pack | PlanAction::Unpack) {
+            self.warnings.push(format!(
+                "与 {} 内容相同，其处理成功时将复制结果，不运行 UPX",
+                representative
+            ));
+            self.duplicate_of = Some(representative.to_string());
+        }
+    }
+}
+
+/// 预览单个任务
+pub fn plan_job(packer: &dyn Packer, options: &UpxOptions) -> PlanEntry {
+    let fail =
+        |reason: String| PlanEntry::failed(&options.input_file, &options.output_file, reason);
+
+    let capabilities = match validate_upx_and_file(packer, options) {
+        Ok(capabilities) => capabilities,
+        Err(e) => return fail(e.to_string()),
+    };
+    let input = Path::new(&options.input_file);
+
+    let requested = options;
+    let mut options = supported_options(requested, &capabilities);
+    let mut warnings: Vec<String> = [
+        ("--lzma", requested.lzma && !options.lzma),
+        (
+            "--ultra-brute",
+            requested.ultra_brute && !options.ultra_brute,
+        ),
+        ("--force", requested.force && !options.force),
+    ]
+    .into_iter()
+    .filter(|&(_, dropped)| dropped)
+    .map(|(option, _)| format!("当前 UPX 不支持 {}，将被忽略", option))
+    .collect();
+
+    let inspect_only = options.mode.is_inspect();
+    let is_overwrite = options.is_overwrite();
+    let mut skip_reason = None;
+
+    if is_overwrite && !inspect_only {
+        if let Err(e) = validate_file_writable(&options.input_file) {
+            return fail(e.to_string());
+        }
+    }
+
+    if !is_overwrite && !inspect_only {
+        let output = Path::new(&options.output_file);
+        if output.exists() {
+            match options.on_conflict {
+                ConflictPolicy::Overwrite => {
+                    warnings.push(format!("将覆盖已存在的输出文件 {}", options.output_file))
+                }
+                ConflictPolicy::Skip => {
+                    skip_reason = Some(format!("输出文件已存在: {}", options.output_file))
+                }
+                ConflictPolicy::Rename => {
+                    let renamed = available_path(output).to_string_lossy().into_owned();
+                    warnings.push(format!("输出文件已存在，将改名为 {}", renamed));
+                    options.output_file = renamed;
+                }
+                ConflictPolicy::Fail => {
+                    return fail(CoreError::OutputExists(options.output_file.clone()).to_string())
+                }
+            }
+        }
+        if let Some(parent) = Path::new(&options.output_file).parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                warnings.push(format!("将创建目录 {}", parent.display()));
+            }
+        }
+    }
+
+    // 与 UPX 的判断相同：已加壳的不再加壳，未加壳的无法脱壳、测试或列出
+    match packer.is_packed(input) {
+        Ok(true) if options.mode == UpxMode::Compress => {
+            skip_reason.get_or_insert_with(|| "文件已被 UPX 加壳".to_string());
+        }
+        Ok(false) if options.mode != UpxMode::Compress => {
+            skip_reason.get_or_insert_with(|| "文件未被 UPX 加壳".to_string());
+        }
+        Ok(_) => {}
+        Err(e) => warnings.push(format!("无法读取文件: {}", e)),
+    }
+
+    let backup_file = (options.backup && !inspect_only && skip_reason.is_none())
+        .then(|| backup_path(&options.input_file));
+    if let Some(backup) = &backup_file {
+        if Path::new(backup).exists() {
+            warnings.push(format!("将覆盖已有的备份 {}", backup));
+        }
+    }
+
+    let command = match packer.command_line(&options, is_overwrite) {
+        Ok(command) => command,
+        Err(e) => return fail(e.to_string()),
+    };
+    let output_file = if inspect_only {
+        options.input_file.clone()
+    } else {
+        options.output_file.clone()
+    };
+
+    PlanEntry {
+        action: if skip_reason.is_some() {
+            PlanAction::Skip
+        } else {
+            PlanAction::for_mode(options.mode)
+        },
+        input_file: options.input_file.clone(),
+        output_file,
+        backup_file,
+        command_line: format_command_line(&command),
+        command,
+        reason: skip_reason,
+        warnings,
+        duplicate_of: None,
+    }
+}
+
+/// 预览整个批次，跳过与去重的判断与 [`crate::batch::run_batch`] 相同
+///
+/// 提供 `index` 时，自上次处理后未变化的文件记为跳过（`default_version` 见
+/// [`IncrementalIndex::unchanged`]）；其余任务中输出路径与前面任务重复的记为失败，
+/// 与前面任务内容相同的记下代表文件。
+pub fn plan_batch(
+    packer: &dyn Packer,
+    jobs: &[UpxOptions],
+    index: Option<&IncrementalIndex>,
+    default_version: &str,
+) -> Vec<PlanEntry> {
+    let strategies = packer.strategies();
+    let mut entries: Vec<Option<PlanEntry>> = jobs
+        .iter()
+        .map(|job| {
+            let result = index?.unchanged(job, strategies, default_version)?;
+            Some(PlanEntry::unchanged(&result))
+        })
+        .collect();
+
+    let pending: Vec<usize> = (0..jobs.len()).filter(|&i| entries[i].is_none()).collect();
+    let pending_jobs: Vec<UpxOptions> = pending.iter().map(|&i| jobs[i].clone()).collect();
+    let duplicates = duplicate_outputs(&pending_jobs);
+    let identical = identical_jobs(&pending_jobs, strategies, &duplicates);
+
+    for (position, job) in pending_jobs.iter().enumerate() {
+        let entry = match duplicates.get(&position) {
+            Some(&first) => PlanEntry::failed(
+                &job.input_file,
+                &job.output_file,
+                CoreError::DuplicateOutput(pending_jobs[first].input_file.clone()).to_string(),
+            ),
+            None => {
+                let mut entry = plan_job(packer, job);
+                if let Some(&first) = identical.get(&position) {
+                    entry.mark_duplicate(&pending_jobs[first].input_file);
+                }
+                entry
+            }
+        };
+        entries[pending[position]] = Some(entry);
+    }
+
+    entries.into_iter().flatten().collect()
+}
+
+/// 把参数拼成命令行，含空白或引号的参数加双引号
+pub fn format_command_line(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '"') {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,271 @@
+//! 调用 UPX 处理单个文件
+
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::hash::sha256_file;
+use crate::manifest::{write_manifest, ManifestEntry};
+use crate::naming::{available_path, ConflictPolicy};
+use crate::options::{UpxMode, UpxOptions};
+use crate::output::{classify_upx_exit, format_bytes, format_upx_listing, format_upx_output};
+use crate::packer::Packer;
+use crate::result::{UpxResult, UpxStatus};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 创建不弹出控制台窗口的命令
+pub fn create_silent_command(program: impl AsRef<std::ffi::OsStr>) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// 检查任务所用的 UPX 能否执行以及输入文件是否存在，返回该 UPX 支持的功能
+///
+/// [`UpxProcess`](crate::UpxProcess) 的探测结果有缓存，批量处理时不会为每个文件重新启动 UPX。
+pub fn validate_upx_and_file(packer: &dyn Packer, options: &UpxOptions) -> Result<UpxCapabilities> {
+    // 检查 UPX 可用性
+    let capabilities = packer.capabilities_for(options)?;
+
+    // 检查输入文件
+    if !Path::new(&options.input_file).exists() {
+        return Err(CoreError::InputNotFound(options.input_file.clone()));
+    }
+
+    Ok(capabilities)
+}
+
+/// 去掉当前 UPX 不支持的可选压缩选项
+pub fn supported_options(options: &UpxOptions, capabilities: &UpxCapabilities) -> UpxOptions {
+    // 不支持 --brute 的旧版本退回 --best
+    let compression_level = match options.compression_level.as_str() {
+        "brute" if !capabilities.supports("--brute") => "best".to_string(),
+        level => level.to_string(),
+    };
+    UpxOptions {
+        compression_level,
+        lzma: options.lzma && capabilities.supports("--lzma"),
+        ultra_brute: options.ultra_brute && capabilities.supports("--ultra-brute"),
+        force: options.force && capabilities.supports("--force"),
+        ..options.clone()
+    }
+}
+
+/// 覆盖原文件前确认文件可写
+pub fn validate_file_writable(file: &str) -> Result<()> {
+    let metadata = fs::metadata(file).map_err(CoreError::Metadata)?;
+    if metadata.permissions().readonly() {
+        return Err(CoreError::ReadOnly);
+    }
+    Ok(())
+}
+
+/// 备份文件路径：同目录下的 `文件名.bak`
+pub fn backup_path(file: &str) -> String {
+    format!("{}.bak", file)
+}
+
+/// 备份为同目录下的 `文件名.bak`
+pub fn create_backup(file: &str) -> Result<()> {
+    fs::copy(file, backup_path(file))
+        .map(|_| ())
+        .map_err(CoreError::Backup)
+}
+
+/// 输出文件已存在时按 [`UpxOptions::on_conflict`] 处理
+///
+/// 跳过时返回跳过的结果；改名时直接修改 `options.output_file`；失败时返回
+/// [`CoreError::OutputExists`]。覆盖由 UPX 的 `--force-overwrite` 完成。
+pub fn resolve_output_conflict(options: &mut UpxOptions) -> Result<Option<UpxResult>> {
+    let output = Path::new(&options.output_file);
+    if !output.exists() {
+        return Ok(None);
+    }
+
+    match options.on_conflict {
+        ConflictPolicy::Overwrite => Ok(None),
+        ConflictPolicy::Skip => Ok(Some(UpxResult::skipped(
+            &options.input_file,
+            &options.output_file,
+            format!("输出文件已存在: {}", options.output_file),
+        ))),
+        ConflictPolicy::Rename => {
+            options.output_file = available_path(output).to_string_lossy().into_owned();
+            Ok(None)
+        }
+        ConflictPolicy::Fail => Err(CoreError::OutputExists(options.output_file.clone())),
+    }
+}
+
+/// 输出到其他位置时创建所在目录
+pub fn create_output_dir(file: &str) -> Result<()> {
+    match Path::new(file).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            fs::create_dir_all(parent).map_err(CoreError::CreateOutputDir)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 按模式调用加壳后端，并把退出码与输出整理为 [`UpxResult`]
+pub fn execute_upx(
+    packer: &dyn Packer,
+    options: &UpxOptions,
+    is_overwrite: bool,
+    original_size: u64,
+) -> Result<UpxResult> {
+    let mode = options.mode;
+    let input_file = options.input_file.clone();
+    let output_file = if mode.is_inspect() {
+        options.input_file.clone()
+    } else {
+        options.output_file.clone()
+    };
+
+    let started = Instant::now();
+    let output = match mode {
+        UpxMode::Compress => packer.compress(options, is_overwrite)?,
+        UpxMode::Decompress => packer.decompress(options, is_overwrite)?,
+        UpxMode::Test => packer.test(options)?,
+        UpxMode::List => packer.list(options)?,
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let strategies = output.strategies;
+    let budget = output.budget;
+    let (stdout, stderr) = (output.stdout.as_str(), output.stderr.as_str());
+    let exit_code = output.exit_code;
+    let (status, reason) = match &budget {
+        Some(check) if !check.met => (UpxStatus::Failed, Some(check.failure_reason())),
+        _ => classify_upx_exit(mode, exit_code, stdout, stderr),
+    };
+
+    let output_size = match status {
+        UpxStatus::Success | UpxStatus::Warning if !mode.is_inspect() => {
+            fs::metadata(&output_file).map(|m| m.len()).unwrap_or(0)
+        }
+        _ => original_size,
+    };
+
+    let ratio = if original_size > 0 {
+        (output_size as f64 / original_size as f64 * 100.0) as i32
+    } else {
+        100
+    };
+
+    let message = match status {
+        UpxStatus::Success if mode == UpxMode::List => format!(
+            "操作成功!\n文件: {}{}",
+            input_file,
+            format_upx_listing(stdout)
+        ),
+        UpxStatus::Success if mode == UpxMode::Test => format!(
+            "操作成功!\n文件: {}{}",
+            input_file,
+            format_upx_output(stdout, stderr)
+        ),
+        UpxStatus::Success => format!(
+            "操作成功!\n输出: {}\n原始大小: {}\n处理后大小: {}\n压缩率: {}%{}",
+            output_file,
+            format_bytes(original_size),
+            format_bytes(output_size),
+            ratio,
+            format_upx_output(stdout, stderr)
+        ),
+        UpxStatus::Skipped => format!(
+            "[跳过] {}: {}",
+            input_file,
+            reason.as_deref().unwrap_or_default()
+        ),
+        UpxStatus::Warning => format!(
+            "操作完成（有警告）\n输出: {}\n原始大小: {}\n处理后大小: {}\n压缩率: {}%\n\n[警告] {}",
+            output_file,
+            format_bytes(original_size),
+            format_bytes(output_size),
+            ratio,
+            reason.as_deref().unwrap_or_default()
+        ),
+        UpxStatus::Failed => reason.clone().unwrap_or_default(),
+    };
+
+    Ok(UpxResult {
+        status,
+        input_file,
+        output_file,
+        original_size,
+        output_size,
+        ratio,
+        exit_code,
+        duration_ms,
+        reason,
+        message,
+        input_sha256: None,
+        output_sha256: None,
+        duplicate_of: None,
+        strategies,
+        budget,
+    })
+}
+
+/// 处理单个文件，GUI 与命令行共用
+///
+/// 预检失败（文件不存在、只读、备份失败等）返回 `Err`；
+/// UPX 本身的失败以 [`UpxStatus::Failed`] 的结果返回。
+/// 结果中带有处理前输入与处理后输出的 SHA-256，均为分块读取。
+pub fn run_upx(packer: &dyn Packer, options: &UpxOptions) -> Result<UpxResult> {
+    let capabilities = validate_upx_and_file(packer, options)?;
+    let mut options = supported_options(options, &capabilities);
+    // 原地处理会替换原文件，必须在调用 UPX 之前计算
+    let input_sha256 = sha256_file(&options.input_file).ok();
+
+    let inspect_only = options.mode.is_inspect();
+    let is_overwrite = options.is_overwrite();
+    if is_overwrite && !inspect_only {
+        validate_file_writable(&options.input_file)?;
+    }
+
+    if !is_overwrite && !inspect_only {
+        if let Some(skipped) = resolve_output_conflict(&mut options)? {
+            return Ok(UpxResult {
+                input_sha256,
+                ..skipped
+            });
+        }
+        create_output_dir(&options.output_file)?;
+    }
+    let options = &options;
+
+    if options.backup && !inspect_only {
+        create_backup(&options.input_file)?;
+    }
+
+    let original_size = fs::metadata(&options.input_file)
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let mut result = execute_upx(packer, options, is_overwrite, original_size)?;
+    result.input_sha256 = input_sha256;
+    if matches!(result.status, UpxStatus::Success | UpxStatus::Warning) && !inspect_only {
+        result.output_sha256 = sha256_file(&result.output_file).ok();
+    }
+
+    // 清单写入失败不影响已完成的加壳，记为警告
+    if let Some(entry) = ManifestEntry::new(options, &capabilities, &result) {
+        if let Err(e) = write_manifest(options.manifest, Path::new(&result.output_file), entry) {
+            let reason = e.to_string();
+            result.status = UpxStatus::Warning;
+            result.message.push_str(&format!("\n\n[警告] {}", reason));
+            result.reason = Some(reason);
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,214 @@
+//! 多个 UPX 版本的注册与选用
+//!
+//! 旧版 UPX 加壳的文件有时只能用相同版本脱壳，新版本的压缩率也会变化。
+//! 用户可以注册多个 UPX，以版本号作为标签，通过 [`UpxOptions::upx_version`]
+//! 指定使用哪一个；脱壳遇到版本不匹配的错误时自动换其他版本重试。
+
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::locate::{UpxLocation, UpxVersion};
+use crate::options::UpxOptions;
+use crate::output::{is_version_mismatch, UPX_EXIT_OK};
+use crate::packer::{Packer, PackerOutput, UpxProcess};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// 保存在配置文件中的已注册 UPX
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredUpx {
+    pub path: String,
+    /// 从 `upx --version` 解析出的版本号，作为选用时的标签
+    pub version: String,
+}
+
+/// 运行 `--version` 并解析版本号
+///
+/// 注册不检查最低版本，旧版本正是为了处理旧文件才需要注册。
+pub fn probe_upx(path: impl AsRef<Path>) -> Result<RegisteredUpx> {
+    let path = path.as_ref();
+    let line = UpxProcess::new(path).version()?;
+    let version = UpxVersion::parse(&line).ok_or(CoreError::UpxVersionUnavailable)?;
+
+    Ok(RegisteredUpx {
+        path: path.to_string_lossy().into_owned(),
+        version: version.to_string(),
+    })
+}
+
+/// 注册 UPX，同一版本只保留最后注册的路径；列表按版本从新到旧排列
+pub fn register_upx(
+    binaries: &mut Vec<RegisteredUpx>,
+    path: impl AsRef<Path>,
+) -> Result<RegisteredUpx> {
+    let entry = probe_upx(path)?;
+    add_registered(binaries, entry.clone());
+    Ok(entry)
+}
+
+/// 加入已由 [`probe_upx`] 确认版本的 UPX，替换同一版本或路径的旧条目
+pub fn add_registered(binaries: &mut Vec<RegisteredUpx>, entry: RegisteredUpx) {
+    binaries.retain(|b| b.version != entry.version && b.path != entry.path);
+    binaries.push(entry);
+    binaries.sort_by_key(|b| std::cmp::Reverse(UpxVersion::parse(&b.version)));
+}
+
+/// 按版本号或路径移除，返回是否有条目被移除
+pub fn unregister_upx(binaries: &mut Vec<RegisteredUpx>, version_or_path: &str) -> bool {
+    let before = binaries.len();
+    binaries.retain(|b| b.version != version_or_path && b.path != version_or_path);
+    binaries.len() != before
+}
+
+// ============================================================================
+// 按任务选用版本的后端
+// ============================================================================
+
+/// 持有默认 UPX 与所有已注册版本的后端
+///
+/// 任务未指定 `upx_version` 时使用默认 UPX；指定了未注册的版本时返回
+/// [`CoreError::UpxVersionNotRegistered`]。
+pub struct UpxRegistry {
+    default_label: String,
+    default: Box<dyn Packer>,
+    registered: Vec<(String, Box<dyn Packer>)>,
+}
+
+impl UpxRegistry {
+    pub fn new(default_label: impl Into<String>, default: impl Packer + 'static) -> Self {
+        Self {
+            default_label: default_label.into(),
+            default: Box::new(default),
+            registered: Vec::new(),
+        }
+    }
+
+    /// 添加一个已注册版本，与已有标签重复时忽略
+    pub fn with_version(mut self, label: impl Into<String>, packer: impl Packer + 'static) -> Self {
+        let label = label.into();
+        if label != self.default_label && !self.registered.iter().any(|(l, _)| *l == label) {
+            self.registered.push((label, Box::new(packer)));
+        }
+        self
+    }
+
+    /// 由自动查找到的 UPX 与配置中注册的版本构建
+    pub fn from_config(location: &UpxLocation, binaries: &[RegisteredUpx]) -> Self {
+        let default_label = UpxVersion::parse(&location.version)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| location.version.clone());
+
+        binaries.iter().fold(
+            Self::new(default_label, location.packer()),
+            |registry, binary| {
+                registry.with_version(binary.version.clone(), UpxProcess::new(&binary.path))
+            },
+        )
+    }
+
+    pub fn default_label(&self) -> &str {
+        &self.default_label
+    }
+
+    /// 所有可选的版本标签，默认版本在最前
+    pub fn labels(&self) -> Vec<&str> {
+        std::iter::once(self.default_label.as_str())
+            .chain(self.registered.iter().map(|(l, _)| l.as_str()))
+            .collect()
+    }
+
+    /// 按标签查找，`None` 表示默认版本
+    fn select(&self, label: Option<&str>) -> Result<(&str, &dyn Packer)> {
+        match label {
+            None => Ok((&self.default_label, self.default.as_ref())),
+            Some(label) if label == self.default_label => {
+                Ok((&self.default_label, self.default.as_ref()))
+            }
+            Some(label) => self
+                .registered
+                .iter()
+                .find(|(l, _)| l == label)
+                .map(|(l, p)| (l.as_str(), p.as_ref()))
+                .ok_or_else(|| CoreError::UpxVersionNotRegistered(label.to_string())),
+        }
+    }
+
+    /// 除 `used` 以外的所有版本，已注册版本按新到旧在前，默认版本在后
+    fn fallbacks<'a>(&'a self, used: &'a str) -> impl Iterator<Item = (&'a str, &'a dyn Packer)> {
+        self.registered
+            .iter()
+            .map(|(l, p)| (l.as_str(), p.as_ref()))
+            .chain(std::iter::once((
+                self.default_label.as_str(),
+                self.default.as_ref(),
+            )))
+            .filter(move |(l, _)| *l != used)
+    }
+}
+
+impl Packer for UpxRegistry {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        let (_, packer) = self.select(options.upx_version.as_deref())?;
+        packer.compress(options, is_overwrite)
+    }
+
+    /// 版本不匹配导致脱壳失败时依次尝试其他版本，全部失败则返回第一次的结果
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        let (used, packer) = self.select(options.upx_version.as_deref())?;
+        let first = packer.decompress(options, is_overwrite)?;
+
+        if first.exit_code == Some(UPX_EXIT_OK)
+            || !is_version_mismatch(&first.stdout, &first.stderr)
+        {
+            return Ok(first);
+        }
+
+        for (label, fallback) in self.fallbacks(used) {
+            // 某个版本无法启动时继续尝试下一个
+            let Ok(mut output) = fallback.decompress(options, is_overwrite) else {
+                continue;
+            };
+            if output.exit_code == Some(UPX_EXIT_OK) {
+                output.stdout = format!(
+                    "[提示] UPX {} 无法脱壳，已改用 UPX {} 完成\n{}",
+                    used, label, output.stdout
+                );
+                return Ok(output);
+            }
+        }
+
+        Ok(first)
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        let (_, packer) = self.select(options.upx_version.as_deref())?;
+        packer.test(options)
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        let (_, packer) = self.select(options.upx_version.as_deref())?;
+        packer.list(options)
+    }
+
+    fn version(&self) -> Result<String> {
+        self.default.version()
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.default.capabilities()
+    }
+
+    fn capabilities_for(&self, options: &UpxOptions) -> Result<UpxCapabilities> {
+        let (_, packer) = self.select(options.upx_version.as_deref())?;
+        packer.capabilities_for(options)
+    }
+
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        let (_, packer) = self.select(options.upx_version.as_deref())?;
+        packer.command_line(options, is_overwrite)
+    }
+
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        self.default.is_packed(path)
+    }
+}
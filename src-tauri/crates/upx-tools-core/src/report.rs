@@ -0,0 +1,468 @@
+//! 批量处理报告：每个文件一行的 JSON / CSV，以及可单独打开的 HTML 摘要
+//!
+//! HTML 不引用任何外部资源，可以直接作为附件发送。
+
+use crate::batch::BatchSummary;
+use crate::budget::BudgetCheck;
+use crate::dedup::DedupSavings;
+use crate::error::{CoreError, Result};
+use crate::history::csv_field;
+use crate::options::{UpxMode, UpxOptions};
+use crate::output::format_bytes;
+use crate::result::{UpxResult, UpxStatus};
+use crate::tune::{format_trials, StrategyTrial};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// HTML 摘要中列出的节省最多的文件数
+pub const TOP_SAVINGS: usize = 10;
+
+/// 报告中的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub status: UpxStatus,
+    pub input_file: String,
+    pub output_file: String,
+    pub original_size: u64,
+    pub output_size: u64,
+    /// 节省的字节数，输出比原文件大时为 0
+    pub saved_bytes: u64,
+    pub ratio: i32,
+    /// 压缩方式，例如 `-9 --lzma`；脱壳为 `-d`
+    pub method: String,
+    pub duration_ms: u64,
+    /// 处理前输入文件的 SHA-256，原地处理时即原文件
+    pub input_sha256: Option<String>,
+    pub output_sha256: Option<String>,
+    pub reason: Option<String>,
+    /// 内容相同、复制了其结果的代表文件
+    pub duplicate_of: Option<String>,
+    /// 自动选择压缩方式时各候选策略的结果
+    pub strategies: Vec<StrategyTrial>,
+    /// 带体积预算的任务的检查结果
+    pub budget: Option<BudgetCheck>,
+}
+
+impl ReportEntry {
+    /// 由处理结果生成，SHA-256 取自结果
+    ///
+    /// `options` 为空表示没有提交给 UPX 的文件（例如路径不存在）。
+    pub fn from_result(options: Option<&UpxOptions>, result: &UpxResult) -> Self {
+        let mode = options.map(|o| o.mode);
+        let has_output = matches!(result.status, UpxStatus::Success | UpxStatus::Warning)
+            && !mode.is_some_and(UpxMode::is_inspect);
+
+        Self {
+            status: result.status,
+            input_file: result.input_file.clone(),
+            output_file: result.output_file.clone(),
+            original_size: result.original_size,
+            output_size: result.output_size,
+            saved_bytes: if has_output {
+                result.original_size.saturating_sub(result.output_size)
+            } else {
+                0
+            },
+            ratio: result.ratio,
+            method: match (result.strategies.iter().find(|t| t.chosen), &result.budget) {
+                (Some(chosen), Some(check)) => {
+                    format!("budget {}: {}", check.budget.describe(), chosen.strategy)
+                }
+                (Some(chosen), None) => format!("auto: {}", chosen.strategy),
+                (None, _) => options.map(method).unwrap_or_default(),
+            },
+            duration_ms: result.duration_ms,
+            input_sha256: result.input_sha256.clone(),
+            output_sha256: result.output_sha256.clone(),
+            reason: result.reason.clone(),
+            duplicate_of: result.duplicate_of.clone(),
+            strategies: result.strategies.clone(),
+            budget: result.budget.clone(),
+        }
+    }
+}
+
+/// 任务使用的压缩方式
+pub fn method(options: &UpxOptions) -> String {
+    if let Some(budget) = options.compress_budget() {
+        return format!("budget {}", budget.describe());
+    }
+    match options.mode {
+        UpxMode::Compress if options.is_auto() => "auto".to_string(),
+        UpxMode::Compress => {
+            // 与 `build_compress_args` 一致：极限压缩取代压缩级别
+            let mut method = if options.ultra_brute {
+                "--ultra-brute".to_string()
+            } else if let "best" | "brute" = options.compression_level.as_str() {
+                format!("--{}", options.compression_level)
+            } else {
+                format!("-{}", options.compression_level)
+            };
+            if options.lzma {
+                method.push_str(" --lzma");
+            } else if let Some(nrv) = options.nrv {
+                method.push(' ');
+                method.push_str(nrv.as_arg());
+            }
+            if options.force {
+                method.push_str(" --force");
+            }
+            method
+        }
+        UpxMode::Decompress => "-d".to_string(),
+        UpxMode::Test => "-t".to_string(),
+        UpxMode::List => "-l".to_string(),
+    }
+}
+
+/// 一个批次的报告
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub generated_at: DateTime<Local>,
+    pub mode: UpxMode,
+    pub summary: BatchSummary,
+    /// 以下合计只统计成功与警告的文件
+    pub original_size: u64,
+    pub output_size: u64,
+    pub saved_bytes: u64,
+    /// 所有文件的 UPX 耗时之和
+    pub duration_ms: u64,
+    /// 与其他文件内容相同、直接复制结果的文件
+    pub deduplicated: DedupSavings,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl BatchReport {
+    pub fn new(mode: UpxMode, entries: Vec<ReportEntry>) -> Self {
+        let mut summary = BatchSummary::default();
+        let (mut original_size, mut output_size) = (0, 0);
+        let mut deduplicated = DedupSavings::default();
+        for entry in &entries {
+            deduplicated.record(entry.duplicate_of.as_deref(), entry.original_size);
+            summary.record(entry.status);
+            if matches!(entry.status, UpxStatus::Success | UpxStatus::Warning) {
+                original_size += entry.original_size;
+                output_size += entry.output_size;
+            }
+        }
+
+        Self {
+            generated_at: Local::now(),
+            mode,
+            summary,
+            original_size,
+            output_size,
+            saved_bytes: entries.iter().map(|e| e.saved_bytes).sum(),
+            duration_ms: entries.iter().map(|e| e.duration_ms).sum(),
+            deduplicated,
+            entries,
+        }
+    }
+
+    /// 节省最多的文件，从多到少
+    pub fn top_savings(&self, count: usize) -> Vec<&ReportEntry> {
+        let mut entries: Vec<_> = self.entries.iter().filter(|e| e.saved_bytes > 0).collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.saved_bytes));
+        entries.truncate(count);
+        entries
+    }
+
+    /// 失败与有警告的文件
+    pub fn problems(&self) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.status, UpxStatus::Failed | UpxStatus::Warning))
+    }
+}
+
+// ============================================================================
+// 输出格式
+// ============================================================================
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    /// 按扩展名选择：`.csv`、`.html`/`.htm`，其余为 JSON
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match ext.as_deref() {
+            Some("csv") => Self::Csv,
+            Some("html" | "htm") => Self::Html,
+            _ => Self::Json,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "status,input_file,output_file,original_size,output_size,saved_bytes,\
+ratio,method,duration_ms,input_sha256,output_sha256,reason,duplicate_of,strategies,budget,budget_met";
+
+/// 每个文件一行，以 UTF-8 BOM 开头
+pub fn render_csv(report: &BatchReport) -> String {
+    let mut csv = format!("\u{feff}{}\n", CSV_HEADER);
+    for entry in &report.entries {
+        let fields = [
+            entry.status.as_str().to_string(),
+            entry.input_file.clone(),
+            entry.output_file.clone(),
+            entry.original_size.to_string(),
+            entry.output_size.to_string(),
+            entry.saved_bytes.to_string(),
+            entry.ratio.to_string(),
+            entry.method.clone(),
+            entry.duration_ms.to_string(),
+            entry.input_sha256.clone().unwrap_or_default(),
+            entry.output_sha256.clone().unwrap_or_default(),
+            entry.reason.clone().unwrap_or_default(),
+            entry.duplicate_of.clone().unwrap_or_default(),
+            csv_trials(&entry.strategies),
+            entry
+                .budget
+                .as_ref()
+                .map(|check| check.budget.to_string())
+                .unwrap_or_default(),
+            entry
+                .budget
+                .as_ref()
+                .map(|check| check.met.to_string())
+                .unwrap_or_default(),
+        ];
+        let line: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// 例如 `-9=4102;--best --lzma=3980*;--brute=failed`，`*` 为选用的策略
+fn csv_trials(trials: &[StrategyTrial]) -> String {
+    trials
+        .iter()
+        .map(|t| match (t.passed, t.chosen) {
+            (false, _) => format!("{}=failed", t.strategy),
+            (true, false) => format!("{}={}", t.strategy, t.output_size),
+            (true, true) => format!("{}={}*", t.strategy, t.output_size),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn mode_label(mode: UpxMode) -> &'static str {
+    match mode {
+        UpxMode::Compress => "加壳压缩",
+        UpxMode::Decompress => "脱壳解压",
+        UpxMode::Test => "测试",
+        UpxMode::List => "列出",
+    }
+}
+
+const HTML_STYLE: &str = "\
+body{font-family:system-ui,-apple-system,'Segoe UI','Microsoft YaHei',sans-serif;margin:32px;color:#1f2937;background:#fff}
+h1{font-size:22px;margin:0 0 4px}
+h2{font-size:16px;margin:28px 0 8px}
+.meta{color:#6b7280;font-size:13px}
+.cards{display:flex;flex-wrap:wrap;gap:12px;margin-top:20px}
+.card{border:1px solid #e5e7eb;border-radius:6px;padding:10px 16px;min-width:110px}
+.card b{display:block;font-size:20px}
+.card span{color:#6b7280;font-size:12px}
+table{border-collapse:collapse;width:100%;font-size:13px}
+th,td{border-bottom:1px solid #e5e7eb;padding:6px 8px;text-align:left;vertical-align:top}
+th{background:#f9fafb;font-weight:600}
+td.num{text-align:right;white-space:nowrap}
+td.path{word-break:break-all}
+td.reason{white-space:pre-wrap}
+.success{color:#15803d}.skipped{color:#6b7280}.warning{color:#b45309}.failed{color:#b91c1c}
+.empty{color:#6b7280;font-size:13px}
+";
+
+/// 可单独打开的 HTML 摘要：合计、节省最多的文件、失败与警告原因，以及完整列表
+pub fn render_html(report: &BatchReport) -> String {
+    let summary = &report.summary;
+    let saved_percent = if report.original_size > 0 {
+        report.saved_bytes as f64 / report.original_size as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+<title>UPX-Tools 批量处理报告</title>\n<style>\n{}</style>\n</head>\n<body>\n\
+<h1>UPX-Tools 批量处理报告</h1>\n<div class=\"meta\">{} · {} · 共 {} 个文件</div>\n",
+        HTML_STYLE,
+        mode_label(report.mode),
+        report.generated_at.format("%Y-%m-%d %H:%M:%S"),
+        summary.total()
+    );
+
+    html.push_str("<div class=\"cards\">\n");
+    for (value, label, class) in [
+        (summary.success.to_string(), "成功", "success"),
+        (summary.skipped.to_string(), "跳过", "skipped"),
+        (summary.warning.to_string(), "警告", "warning"),
+        (summary.failed.to_string(), "失败", "failed"),
+        (format_bytes(report.original_size), "原始大小", ""),
+        (format_bytes(report.output_size), "处理后大小", ""),
+        (
+            format!(
+                "{} ({:.1}%)",
+                format_bytes(report.saved_bytes),
+                saved_percent
+            ),
+            "节省",
+            "",
+        ),
+        (
+            format!("{:.1} 秒", report.duration_ms as f64 / 1000.0),
+            "UPX 耗时",
+            "",
+        ),
+        (
+            format!(
+                "{} 个 ({})",
+                report.deduplicated.files,
+                format_bytes(report.deduplicated.bytes)
+            ),
+            "去重复用",
+            "",
+        ),
+    ] {
+        let _ = writeln!(
+            html,
+            "<div class=\"card\"><b class=\"{}\">{}</b><span>{}</span></div>",
+            class,
+            escape_html(&value),
+            label
+        );
+    }
+    html.push_str("</div>\n");
+
+    html.push_str("<h2>节省最多的文件</h2>\n");
+    let top = report.top_savings(TOP_SAVINGS);
+    if top.is_empty() {
+        html.push_str("<p class=\"empty\">没有节省空间的文件</p>\n");
+    } else {
+        html.push_str(
+            "<table>\n<tr><th>文件</th><th>原始大小</th><th>处理后大小</th><th>节省</th><th>压缩率</th><th>方式</th></tr>\n",
+        );
+        for entry in top {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"path\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+<td class=\"num\">{}</td><td class=\"num\">{}%</td><td>{}</td></tr>",
+                escape_html(&entry.input_file),
+                format_bytes(entry.original_size),
+                format_bytes(entry.output_size),
+                format_bytes(entry.saved_bytes),
+                entry.ratio,
+                escape_html(&entry.method)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let tuned: Vec<_> = report
+        .entries
+        .iter()
+        .filter(|e| !e.strategies.is_empty())
+        .collect();
+    if !tuned.is_empty() {
+        html.push_str(
+            "<h2>自动选择的压缩方式</h2>\n<table>\n<tr><th>文件</th><th>各策略的大小</th></tr>\n",
+        );
+        for entry in tuned {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"path\">{}</td><td>{}</td></tr>",
+                escape_html(&entry.input_file),
+                escape_html(&format_trials(&entry.strategies))
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>失败与警告</h2>\n");
+    let problems: Vec<_> = report.problems().collect();
+    if problems.is_empty() {
+        html.push_str("<p class=\"empty\">没有失败或警告</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>状态</th><th>文件</th><th>原因</th></tr>\n");
+        for entry in problems {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"{}\">{}</td><td class=\"path\">{}</td><td class=\"reason\">{}</td></tr>",
+                entry.status.as_str(),
+                entry.status.label(),
+                escape_html(&entry.input_file),
+                escape_html(entry.reason.as_deref().unwrap_or_default())
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let _ = writeln!(
+        html,
+        "<h2>全部文件</h2>\n<details>\n<summary>展开 {} 个文件</summary>\n<table>\n\
+<tr><th>状态</th><th>文件</th><th>输出</th><th>原始大小</th><th>处理后大小</th><th>压缩率</th>\
+<th>方式</th><th>耗时</th><th>输出 SHA-256</th></tr>",
+        report.entries.len()
+    );
+    for entry in &report.entries {
+        let _ = writeln!(
+            html,
+            "<tr><td class=\"{}\">{}</td><td class=\"path\">{}</td><td class=\"path\">{}</td>\
+<td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}%</td><td>{}</td>\
+<td class=\"num\">{} ms</td><td class=\"path\">{}</td></tr>",
+            entry.status.as_str(),
+            entry.status.label(),
+            escape_html(&entry.input_file),
+            escape_html(&entry.output_file),
+            format_bytes(entry.original_size),
+            format_bytes(entry.output_size),
+            entry.ratio,
+            escape_html(&entry.method),
+            entry.duration_ms,
+            entry.output_sha256.as_deref().unwrap_or_default()
+        );
+    }
+    html.push_str("</table>\n</details>\n</body>\n</html>\n");
+    html
+}
+
+/// 按格式生成并写入报告
+pub fn write_report(report: &BatchReport, path: &Path, format: ReportFormat) -> Result<()> {
+    let content = match format {
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(report).map_err(|e| CoreError::ReportWrite(e.into()))?
+        }
+        ReportFormat::Csv => render_csv(report),
+        ReportFormat::Html => render_html(report),
+    };
+    fs::write(path, content).map_err(CoreError::ReportWrite)
+}
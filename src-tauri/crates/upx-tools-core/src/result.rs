@@ -0,0 +1,127 @@
+use crate::budget::BudgetCheck;
+use crate::tune::StrategyTrial;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 单个文件的处理状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum UpxStatus {
+    Success,
+    /// 已加壳、无法压缩等无需处理的情况，不算失败
+    Skipped,
+    /// UPX 以警告退出码（2）结束
+    Warning,
+    Failed,
+}
+
+impl UpxStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Skipped => "skipped",
+            Self::Warning => "warning",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// 界面与报告中显示的名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Success => "成功",
+            Self::Skipped => "跳过",
+            Self::Warning => "警告",
+            Self::Failed => "失败",
+        }
+    }
+}
+
+impl fmt::Display for UpxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UpxStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "success" => Ok(Self::Success),
+            "skipped" => Ok(Self::Skipped),
+            "warning" => Ok(Self::Warning),
+            "failed" => Ok(Self::Failed),
+            _ => Err(format!(
+                "无效的状态 {}，可选 success、skipped、warning、failed",
+                s
+            )),
+        }
+    }
+}
+
+/// 单个文件的处理结果，GUI、命令行 JSON 输出与报告共用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpxResult {
+    pub status: UpxStatus,
+    pub input_file: String,
+    pub output_file: String,
+    pub original_size: u64,
+    pub output_size: u64,
+    pub ratio: i32,
+    pub exit_code: Option<i32>,
+    /// UPX 执行耗时（毫秒）
+    pub duration_ms: u64,
+    /// 跳过、警告或失败的原因
+    pub reason: Option<String>,
+    /// 用于日志显示的完整信息
+    pub message: String,
+    /// 处理前输入文件的 SHA-256，未能读取输入文件时为空
+    #[serde(default)]
+    pub input_sha256: Option<String>,
+    /// 处理后输出文件的 SHA-256，仅加壳、脱壳成功（含警告）时有值
+    #[serde(default)]
+    pub output_sha256: Option<String>,
+    /// 批次中内容相同、结果复制自的文件；为空表示调用了 UPX
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// 自动选择压缩方式时各候选策略的大小与测试结果
+    #[serde(default)]
+    pub strategies: Vec<StrategyTrial>,
+    /// 带体积预算的任务是否满足预算，以及能达到的最小大小
+    #[serde(default)]
+    pub budget: Option<BudgetCheck>,
+}
+
+impl UpxResult {
+    /// 未能执行 UPX（路径不存在、预检失败等）时的失败结果
+    pub fn failed(input_file: &str, output_file: &str, message: String) -> Self {
+        Self {
+            status: UpxStatus::Failed,
+            input_file: input_file.to_string(),
+            output_file: output_file.to_string(),
+            original_size: 0,
+            output_size: 0,
+            ratio: 100,
+            exit_code: None,
+            duration_ms: 0,
+            reason: Some(message.clone()),
+            message,
+            input_sha256: None,
+            output_sha256: None,
+            duplicate_of: None,
+            strategies: Vec::new(),
+            budget: None,
+        }
+    }
+
+    /// 未调用 UPX 就跳过的结果，例如输出文件已存在
+    pub fn skipped(input_file: &str, output_file: &str, reason: String) -> Self {
+        Self {
+            status: UpxStatus::Skipped,
+            message: format!("[跳过] {}: {}", input_file, reason),
+            reason: Some(reason),
+            ..Self::failed(input_file, output_file, String::new())
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! 文件夹扫描
+
+use crate::error::{CoreError, Result};
+use crate::options::ScanFolderOptions;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 批量处理时收集的文件扩展名
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["exe", "dll"];
+
+/// 扩展名是否受支持（不区分大小写）
+pub fn is_supported(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext_lower = ext.to_string_lossy().to_lowercase();
+        SUPPORTED_EXTENSIONS.contains(&ext_lower.as_str())
+    })
+}
+
+/// 列出文件夹中的所有文件
+pub fn list_files(folder_path: &Path, include_subfolders: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(folder_path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() && include_subfolders {
+                files.extend(list_files(&path, true));
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+/// 收集文件夹中扩展名受支持的文件
+pub fn scan_folder_recursive(folder_path: &Path, include_subfolders: bool) -> Vec<String> {
+    list_files(folder_path, include_subfolders)
+        .into_iter()
+        .filter(|path| is_supported(path))
+        .filter_map(|path| path.to_str().map(str::to_string))
+        .collect()
+}
+
+/// 检查路径后扫描文件夹
+pub fn scan_folder(options: &ScanFolderOptions) -> Result<Vec<String>> {
+    let path = Path::new(&options.folder_path);
+
+    if !path.exists() {
+        return Err(CoreError::PathNotFound(options.folder_path.clone()));
+    }
+
+    if !path.is_dir() {
+        return Err(CoreError::NotADirectory(options.folder_path.clone()));
+    }
+
+    Ok(scan_folder_recursive(path, options.include_subfolders))
+}
@@ -0,0 +1,199 @@
+//! 累计统计：由处理历史汇总节省的空间、压缩率、失败原因与处理速度
+
+use crate::batch::BatchSummary;
+use crate::history::HistoryRecord;
+use crate::options::UpxMode;
+use crate::output::failure_kind;
+use crate::report::method;
+use crate::result::UpxStatus;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const MB: f64 = 1024.0 * 1024.0;
+
+/// 统计范围，未设置的条件不过滤
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatisticsQuery {
+    /// 起始日期（本地时间，含当天）
+    pub since: Option<NaiveDate>,
+    /// 截止日期（本地时间，含当天）
+    pub until: Option<NaiveDate>,
+    /// 只统计此文件夹（含子文件夹）中的输入文件
+    pub folder: Option<String>,
+}
+
+impl StatisticsQuery {
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        let date = record.timestamp.date_naive();
+        self.since.is_none_or(|since| date >= since)
+            && self.until.is_none_or(|until| date <= until)
+            && self
+                .folder
+                .as_deref()
+                .is_none_or(|folder| in_folder(&record.input_file, folder))
+    }
+}
+
+/// 统一分隔符并忽略大小写，按整段目录名比较
+fn in_folder(path: &str, folder: &str) -> bool {
+    let normalize = |p: &str| p.replace('\\', "/").to_lowercase();
+    let path = normalize(path);
+    let folder = normalize(folder);
+    let folder = folder.trim_end_matches('/');
+    path.strip_prefix(folder)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 按文件格式（扩展名）汇总的压缩结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FormatStats {
+    /// 小写扩展名，没有扩展名时为空
+    pub format: String,
+    pub files: usize,
+    pub original_size: u64,
+    pub output_size: u64,
+    pub saved_bytes: u64,
+}
+
+/// 按实际使用的压缩方式（含压缩级别）汇总的压缩率
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MethodStats {
+    /// 与报告中一致，例如 `-9 --lzma`、`--ultra-brute`
+    pub method: String,
+    pub files: usize,
+    pub average_ratio: f64,
+}
+
+/// 按异常类型汇总的失败
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailureStats {
+    /// UPX 异常名，例如 `CantPackException`；见 [`failure_kind`]
+    pub kind: String,
+    pub count: usize,
+    /// 占统计范围内全部记录的比例（0~1）
+    pub rate: f64,
+}
+
+/// 累计统计结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Statistics {
+    /// 统计范围内的记录数
+    pub records: usize,
+    pub summary: BatchSummary,
+    /// 加壳成功（含警告）的文件合计
+    pub original_size: u64,
+    pub output_size: u64,
+    pub saved_bytes: u64,
+    /// 节省最多的格式在前
+    pub by_format: Vec<FormatStats>,
+    pub by_method: Vec<MethodStats>,
+    /// 失败记录占全部记录的比例（0~1）
+    pub failure_rate: f64,
+    /// 次数最多的在前
+    pub failures: Vec<FailureStats>,
+    /// 加壳与脱壳成功时处理的原文件大小与耗时合计
+    pub processed_bytes: u64,
+    pub processing_ms: u64,
+    /// 平均每 MB 的处理耗时（毫秒），没有可统计的记录时为空
+    pub ms_per_mb: Option<f64>,
+}
+
+/// 汇总符合条件的历史记录
+pub fn compute_statistics(records: &[HistoryRecord], query: &StatisticsQuery) -> Statistics {
+    let mut stats = Statistics::default();
+    let mut formats: BTreeMap<String, FormatStats> = BTreeMap::new();
+    let mut methods: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+    let mut failures: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+    for record in records.iter().filter(|r| query.matches(r)) {
+        stats.records += 1;
+        stats.summary.record(record.status);
+        let mode = record.options.mode;
+
+        match record.status {
+            UpxStatus::Failed => {
+                let kind = failure_kind(record.reason.as_deref().unwrap_or_default());
+                *failures.entry(kind).or_default() += 1;
+            }
+            UpxStatus::Success | UpxStatus::Warning if !mode.is_inspect() => {
+                stats.processed_bytes += record.original_size;
+                stats.processing_ms += record.duration_ms;
+            }
+            _ => {}
+        }
+
+        if mode != UpxMode::Compress
+            || !matches!(record.status, UpxStatus::Success | UpxStatus::Warning)
+        {
+            continue;
+        }
+
+        let saved = record.original_size.saturating_sub(record.output_size);
+        stats.original_size += record.original_size;
+        stats.output_size += record.output_size;
+        stats.saved_bytes += saved;
+
+        let format = file_format(&record.input_file);
+        let entry = formats
+            .entry(format.clone())
+            .or_insert_with(|| FormatStats {
+                format,
+                ..Default::default()
+            });
+        entry.files += 1;
+        entry.original_size += record.original_size;
+        entry.output_size += record.output_size;
+        entry.saved_bytes += saved;
+
+        // 自动选择与体积预算按最终保留的压缩方式归类，其余按提交的参数
+        let method = record
+            .chosen_strategy
+            .clone()
+            .unwrap_or_else(|| method(&record.options));
+        let (files, ratio_sum) = methods.entry(method).or_default();
+        *files += 1;
+        *ratio_sum += i64::from(record.ratio);
+    }
+
+    stats.by_format = formats.into_values().collect();
+    stats
+        .by_format
+        .sort_by_key(|f| std::cmp::Reverse(f.saved_bytes));
+    stats.by_method = methods
+        .into_iter()
+        .map(|(method, (files, ratio_sum))| MethodStats {
+            method,
+            files,
+            average_ratio: ratio_sum as f64 / files as f64,
+        })
+        .collect();
+
+    if stats.records > 0 {
+        stats.failure_rate = stats.summary.failed as f64 / stats.records as f64;
+    }
+    stats.failures = failures
+        .into_iter()
+        .map(|(kind, count)| FailureStats {
+            kind: kind.to_string(),
+            count,
+            rate: count as f64 / stats.records as f64,
+        })
+        .collect();
+    stats.failures.sort_by_key(|f| std::cmp::Reverse(f.count));
+
+    if stats.processed_bytes > 0 {
+        stats.ms_per_mb = Some(stats.processing_ms as f64 / (stats.processed_bytes as f64 / MB));
+    }
+    stats
+}
+
+/// 小写扩展名，Windows 与 Unix 路径均可
+fn file_format(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => ext.to_lowercase(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,593 @@
+//! 自动选择压缩方式：在输入文件的临时副本上并行尝试多个候选策略，
+//! 保留加壳成功、通过 `upx -t` 且最小的结果
+//!
+//! 压缩级别为 `auto` 的加壳任务由 [`AutoTuner`] 处理，其余任务原样交给内部后端。
+//! 带体积预算的任务则按顺序逐个尝试，采用第一个满足预算的结果（见 [`budget`](crate::budget)）。
+//! 每个策略的大小、耗时与测试结果记录在 [`UpxResult::strategies`](crate::UpxResult) 中。
+
+use crate::budget::{BudgetCheck, SizeBudget};
+use crate::capabilities::UpxCapabilities;
+use crate::error::{CoreError, Result};
+use crate::options::{NrvMethod, UpxMode, UpxOptions};
+use crate::output::{classify_upx_exit, format_bytes, UPX_EXIT_ERROR};
+use crate::packer::{Packer, PackerOutput};
+use crate::report::method;
+use crate::result::UpxStatus;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// 默认的候选策略，按耗时从短到长排列
+pub const DEFAULT_STRATEGIES: &[&str] = &[
+    "-9",
+    "--best",
+    "--best --nrv2d",
+    "--best --nrv2e",
+    "--best --lzma",
+];
+
+/// 一组压缩参数，例如 `--best --lzma`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Strategy {
+    /// `1`-`9`、`best` 或 `brute`
+    pub compression_level: String,
+    pub ultra_brute: bool,
+    pub lzma: bool,
+    pub nrv: Option<NrvMethod>,
+}
+
+impl Strategy {
+    /// 解析一组策略，空列表视为默认策略
+    pub fn parse_list<S: AsRef<str>>(items: &[S]) -> Result<Vec<Self>> {
+        if items.is_empty() {
+            return Ok(Self::defaults());
+        }
+        items.iter().map(|item| item.as_ref().parse()).collect()
+    }
+
+    /// 任务需要尝试候选方式时才解析列表，否则返回空列表，
+    /// 配置中无效的候选不会影响脱壳、测试与固定级别的加壳
+    pub fn parse_for<S: AsRef<str>>(options: &UpxOptions, items: &[S]) -> Result<Vec<Self>> {
+        if options.tries_strategies() {
+            Self::parse_list(items)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        DEFAULT_STRATEGIES
+            .iter()
+            .map(|s| s.parse().expect("默认策略格式正确"))
+            .collect()
+    }
+
+    /// 以本策略的压缩参数替换任务中的参数
+    pub fn apply(&self, options: &UpxOptions) -> UpxOptions {
+        UpxOptions {
+            compression_level: self.compression_level.clone(),
+            ultra_brute: self.ultra_brute,
+            lzma: self.lzma,
+            nrv: self.nrv,
+            ..options.clone()
+        }
+    }
+
+    /// 当前 UPX 不支持的参数
+    pub fn unsupported(&self, capabilities: &UpxCapabilities) -> Option<&'static str> {
+        [
+            ("--brute", self.compression_level == "brute"),
+            ("--ultra-brute", self.ultra_brute),
+            ("--lzma", self.lzma),
+        ]
+        .into_iter()
+        .find(|&(option, used)| used && !capabilities.supports(option))
+        .map(|(option, _)| option)
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 与 `report::method` 的写法一致
+        if self.ultra_brute {
+            f.write_str("--ultra-brute")?;
+        } else if let "best" | "brute" = self.compression_level.as_str() {
+            write!(f, "--{}", self.compression_level)?;
+        } else {
+            write!(f, "-{}", self.compression_level)?;
+        }
+        if self.lzma {
+            f.write_str(" --lzma")?;
+        } else if let Some(nrv) = self.nrv {
+            write!(f, " {}", nrv.as_arg())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = CoreError;
+
+    /// 由空格分隔的 UPX 参数解析；只给出算法时级别为 9
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || CoreError::InvalidStrategy(s.to_string());
+        let mut level: Option<String> = None;
+        let mut strategy = Self {
+            compression_level: "9".to_string(),
+            ultra_brute: false,
+            lzma: false,
+            nrv: None,
+        };
+
+        for arg in s.split_whitespace() {
+            let new_level = match arg {
+                "--best" => Some("best".to_string()),
+                "--brute" => Some("brute".to_string()),
+                "--ultra-brute" => {
+                    strategy.ultra_brute = true;
+                    Some("best".to_string())
+                }
+                "--lzma" if strategy.nrv.is_none() => {
+                    strategy.lzma = true;
+                    None
+                }
+                _ => match NrvMethod::ALL.into_iter().find(|m| m.as_arg() == arg) {
+                    Some(nrv) if !strategy.lzma && strategy.nrv.is_none() => {
+                        strategy.nrv = Some(nrv);
+                        None
+                    }
+                    Some(_) => return Err(invalid()),
+                    None => match arg.strip_prefix('-') {
+                        Some(n @ ("1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9")) => {
+                            Some(n.to_string())
+                        }
+                        _ => return Err(invalid()),
+                    },
+                },
+            };
+            if let Some(new_level) = new_level {
+                if level.replace(new_level).is_some() {
+                    return Err(invalid());
+                }
+            }
+        }
+
+        if level.is_none() && !strategy.lzma && strategy.nrv.is_none() {
+            return Err(invalid());
+        }
+        if let Some(level) = level {
+            strategy.compression_level = level;
+        }
+        Ok(strategy)
+    }
+}
+
+/// 一个候选策略的尝试结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategyTrial {
+    pub strategy: String,
+    /// 加壳后的大小，加壳失败时为 0
+    pub output_size: u64,
+    /// 加壳与测试的总耗时
+    pub duration_ms: u64,
+    /// 加壳成功且通过 `upx -t`
+    pub passed: bool,
+    /// 是否为最终保留的结果
+    pub chosen: bool,
+    pub reason: Option<String>,
+}
+
+impl StrategyTrial {
+    fn failed(strategy: &Strategy, duration_ms: u64, reason: String) -> Self {
+        Self {
+            strategy: strategy.to_string(),
+            output_size: 0,
+            duration_ms,
+            passed: false,
+            chosen: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// 各策略的大小，例如 `-9 120.00 KB, --best --lzma 98.00 KB（选用）`
+pub fn format_trials(trials: &[StrategyTrial]) -> String {
+    trials
+        .iter()
+        .map(|t| {
+            if !t.passed {
+                format!("{} 失败", t.strategy)
+            } else if t.chosen {
+                format!("{} {}（选用）", t.strategy, format_bytes(t.output_size))
+            } else {
+                format!("{} {}", t.strategy, format_bytes(t.output_size))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 任务的处理方式，自动选择或体积预算的任务还包括候选压缩方式
+///
+/// 用于判断两次处理是否等价：候选列表变化时选出的压缩方式也可能不同。
+pub fn method_with_candidates(options: &UpxOptions, strategies: &[Strategy]) -> String {
+    let method = method(options);
+    if !options.tries_strategies() {
+        return method;
+    }
+    let candidates: Vec<String> = strategies.iter().map(Strategy::to_string).collect();
+    format!("{} [{}]", method, candidates.join(", "))
+}
+
+// ============================================================================
+// 自动选择的后端
+// ============================================================================
+
+/// 一个策略在临时副本上的加壳结果
+struct Attempt {
+    trial: StrategyTrial,
+    /// 加壳成功（不论是否通过测试）
+    packed: bool,
+    /// 通过测试时的临时输出文件
+    file: Option<PathBuf>,
+    output: Result<PackerOutput>,
+}
+
+/// 压缩级别为 `auto` 或带体积预算时逐个尝试候选策略的后端，其余操作直接交给内部后端
+pub struct AutoTuner<P> {
+    inner: P,
+    strategies: Vec<Strategy>,
+}
+
+impl<P: Packer> AutoTuner<P> {
+    pub fn new(inner: P, strategies: Vec<Strategy>) -> Self {
+        Self { inner, strategies }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// 在 `dir` 中的输入副本上加壳并测试
+    fn attempt(
+        &self,
+        options: &UpxOptions,
+        strategy: &Strategy,
+        capabilities: &UpxCapabilities,
+        dir: &Path,
+    ) -> Attempt {
+        let started = Instant::now();
+        let elapsed = || started.elapsed().as_millis() as u64;
+        let failed = |reason: String, packed, output| Attempt {
+            trial: StrategyTrial::failed(strategy, elapsed(), reason),
+            packed,
+            file: None,
+            output,
+        };
+
+        if let Some(option) = strategy.unsupported(capabilities) {
+            let e =
+                CoreError::InvalidStrategy(format!("{}（当前 UPX 不支持 {}）", strategy, option));
+            return failed(format!("当前 UPX 不支持 {}", option), false, Err(e));
+        }
+
+        // 保留原文件名，UPX 按扩展名识别部分格式
+        let name = Path::new(&options.input_file)
+            .file_name()
+            .unwrap_or("input".as_ref());
+        let copy = dir.join(name);
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::copy(&options.input_file, &copy)) {
+            return failed(e.to_string(), false, Err(CoreError::AutoTune(e)));
+        }
+        let copy = copy.to_string_lossy().into_owned();
+        let trial_options = UpxOptions {
+            input_file: copy.clone(),
+            output_file: copy.clone(),
+            budget: None,
+            ..strategy.apply(options)
+        };
+
+        let output = match self.inner.compress(&trial_options, true) {
+            Ok(output) => output,
+            Err(e) => return failed(e.to_string(), false, Err(e)),
+        };
+        let (status, reason) = classify_upx_exit(
+            UpxMode::Compress,
+            output.exit_code,
+            &output.stdout,
+            &output.stderr,
+        );
+        if !matches!(status, UpxStatus::Success | UpxStatus::Warning) {
+            return failed(reason.unwrap_or_default(), false, Ok(output));
+        }
+
+        let tested = self.inner.test(&trial_options).map(|test| {
+            classify_upx_exit(UpxMode::Test, test.exit_code, &test.stdout, &test.stderr)
+        });
+        let test_reason = match tested {
+            Ok((UpxStatus::Success, _)) => None,
+            Ok((_, reason)) => Some(format!("未通过 upx -t: {}", reason.unwrap_or_default())),
+            Err(e) => Some(format!("未通过 upx -t: {}", e)),
+        };
+        if let Some(reason) = test_reason {
+            return failed(reason, true, Ok(output));
+        }
+
+        Attempt {
+            trial: StrategyTrial {
+                strategy: strategy.to_string(),
+                output_size: fs::metadata(&copy).map_or(0, |m| m.len()),
+                duration_ms: elapsed(),
+                passed: true,
+                chosen: false,
+                reason: None,
+            },
+            packed: true,
+            file: Some(PathBuf::from(copy)),
+            output: Ok(output),
+        }
+    }
+
+    fn tune(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        let capabilities = self.inner.capabilities_for(options)?;
+        let temp = tempfile::Builder::new()
+            .prefix("upx-tools-auto")
+            .tempdir()
+            .map_err(CoreError::AutoTune)?;
+
+        let attempts: Vec<Attempt> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .strategies
+                .iter()
+                .enumerate()
+                .map(|(i, strategy)| {
+                    let dir = temp.path().join(i.to_string());
+                    let capabilities = &capabilities;
+                    scope.spawn(move || self.attempt(options, strategy, capabilities, &dir))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("策略线程不会 panic"))
+                .collect()
+        });
+        let mut trials: Vec<StrategyTrial> = attempts.iter().map(|a| a.trial.clone()).collect();
+
+        let Some(best) = smallest_passing(&attempts) else {
+            return no_passing_strategy(attempts, trials);
+        };
+        trials[best].chosen = true;
+
+        let note = format!(
+            "[自动] 选用 {}：{}",
+            trials[best].strategy,
+            format_trials(&trials)
+        );
+        keep(options, is_overwrite, &attempts[best], note, trials, None)
+    }
+
+    /// 按顺序（从快到慢）逐个尝试，采用第一个通过测试且满足预算的结果
+    fn fit_budget(
+        &self,
+        options: &UpxOptions,
+        budget: SizeBudget,
+        is_overwrite: bool,
+    ) -> Result<PackerOutput> {
+        let capabilities = self.inner.capabilities_for(options)?;
+        let original_size = fs::metadata(&options.input_file)
+            .map_err(CoreError::AutoTune)?
+            .len();
+        let limit = budget.limit(original_size);
+        let temp = tempfile::Builder::new()
+            .prefix("upx-tools-budget")
+            .tempdir()
+            .map_err(CoreError::AutoTune)?;
+
+        let mut attempts = Vec::new();
+        let mut fit = None;
+        for (i, strategy) in self.strategies.iter().enumerate() {
+            let dir = temp.path().join(i.to_string());
+            let attempt = self.attempt(options, strategy, &capabilities, &dir);
+            let fits =
+                attempt.trial.passed && budget.fits(original_size, attempt.trial.output_size);
+            attempts.push(attempt);
+            if fits {
+                fit = Some(i);
+                break;
+            }
+        }
+        let mut trials: Vec<StrategyTrial> = attempts.iter().map(|a| a.trial.clone()).collect();
+
+        // 都没能加壳（例如已加壳）时照常按 UPX 的输出归类
+        let best = smallest_passing(&attempts);
+        if best.is_none() && !attempts.iter().any(|a| a.packed) {
+            return no_passing_strategy(attempts, trials);
+        }
+        let mut check = BudgetCheck {
+            budget,
+            limit_bytes: limit,
+            met: false,
+            best_size: best.map(|best| trials[best].output_size),
+            best_strategy: best.map(|best| trials[best].strategy.clone()),
+        };
+
+        let Some(fit) = fit else {
+            return Ok(PackerOutput {
+                exit_code: Some(UPX_EXIT_ERROR),
+                stdout: String::new(),
+                stderr: check.failure_reason(),
+                strategies: trials,
+                budget: Some(check),
+            });
+        };
+        check.met = true;
+        trials[fit].chosen = true;
+
+        let note = format!(
+            "[预算] 选用 {}（{}，预算 {}）：{}",
+            trials[fit].strategy,
+            format_bytes(trials[fit].output_size),
+            budget.describe(),
+            format_trials(&trials)
+        );
+        keep(
+            options,
+            is_overwrite,
+            &attempts[fit],
+            note,
+            trials,
+            Some(check),
+        )
+    }
+
+    /// 是否由本后端逐个尝试候选策略
+    fn tunes(&self, options: &UpxOptions) -> bool {
+        options.tries_strategies() && !self.strategies.is_empty()
+    }
+}
+
+/// 通过测试的最小结果；大小相同时取排在前面（更快）的策略
+fn smallest_passing(attempts: &[Attempt]) -> Option<usize> {
+    attempts
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.trial.passed)
+        .min_by_key(|(i, a)| (a.trial.output_size, *i))
+        .map(|(i, _)| i)
+}
+
+/// 把选用的临时输出复制到目标位置，并在输出开头附加选择说明
+fn keep(
+    options: &UpxOptions,
+    is_overwrite: bool,
+    attempt: &Attempt,
+    note: String,
+    trials: Vec<StrategyTrial>,
+    budget: Option<BudgetCheck>,
+) -> Result<PackerOutput> {
+    let target = if is_overwrite {
+        &options.input_file
+    } else {
+        &options.output_file
+    };
+    let file = attempt.file.as_ref().expect("通过测试的策略有输出文件");
+    fs::copy(file, target).map_err(CoreError::AutoTune)?;
+
+    let Ok(output) = &attempt.output else {
+        unreachable!("通过测试的策略加壳成功");
+    };
+    Ok(PackerOutput {
+        stdout: format!("{}\n{}", note, output.stdout),
+        strategies: trials,
+        budget,
+        ..output.clone()
+    })
+}
+
+/// 没有策略通过时：都没能加壳（例如已加壳）则返回 UPX 的输出，以便照常归类；
+/// 有加壳成功但未通过测试的则记为失败，列出各策略的原因
+fn no_passing_strategy(attempts: Vec<Attempt>, trials: Vec<StrategyTrial>) -> Result<PackerOutput> {
+    if !attempts.iter().any(|a| a.packed) {
+        let mut first_error = None;
+        for attempt in attempts {
+            match attempt.output {
+                Ok(output) => {
+                    return Ok(PackerOutput {
+                        strategies: trials,
+                        ..output
+                    })
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+    }
+
+    let reasons: Vec<String> = trials
+        .iter()
+        .map(|t| {
+            format!(
+                "{}: {}",
+                t.strategy,
+                t.reason.as_deref().unwrap_or_default()
+            )
+        })
+        .collect();
+    Ok(PackerOutput {
+        exit_code: Some(UPX_EXIT_ERROR),
+        stdout: String::new(),
+        stderr: format!("所有候选压缩方式都未通过\n{}", reasons.join("\n")),
+        strategies: trials,
+        budget: None,
+    })
+}
+
+impl<P: Packer> Packer for AutoTuner<P> {
+    fn compress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        if !self.tunes(options) {
+            self.inner.compress(options, is_overwrite)
+        } else if let Some(budget) = options.compress_budget() {
+            self.fit_budget(options, budget, is_overwrite)
+        } else {
+            self.tune(options, is_overwrite)
+        }
+    }
+
+    fn decompress(&self, options: &UpxOptions, is_overwrite: bool) -> Result<PackerOutput> {
+        self.inner.decompress(options, is_overwrite)
+    }
+
+    fn test(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.test(options)
+    }
+
+    fn list(&self, options: &UpxOptions) -> Result<PackerOutput> {
+        self.inner.list(options)
+    }
+
+    fn version(&self) -> Result<String> {
+        self.inner.version()
+    }
+
+    fn capabilities(&self) -> Result<UpxCapabilities> {
+        self.inner.capabilities()
+    }
+
+    fn capabilities_for(&self, options: &UpxOptions) -> Result<UpxCapabilities> {
+        self.inner.capabilities_for(options)
+    }
+
+    /// 自动选择或带预算时为各候选策略的命令，以 `|` 分隔
+    fn command_line(&self, options: &UpxOptions, is_overwrite: bool) -> Result<Vec<String>> {
+        if !self.tunes(options) {
+            return self.inner.command_line(options, is_overwrite);
+        }
+        let mut command = Vec::new();
+        for strategy in &self.strategies {
+            if !command.is_empty() {
+                command.push("|".to_string());
+            }
+            command.extend(
+                self.inner
+                    .command_line(&strategy.apply(options), is_overwrite)?,
+            );
+        }
+        Ok(command)
+    }
+
+    fn is_packed(&self, path: &Path) -> io::Result<bool> {
+        self.inner.is_packed(path)
+    }
+
+    fn strategies(&self) -> &[Strategy] {
+        &self.strategies
+    }
+}
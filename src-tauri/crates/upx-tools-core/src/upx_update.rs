@@ -0,0 +1,248 @@
+//! 从 UPX 官方发行版更新 UPX
+//!
+//! 从可配置的发行信息接口获取最新版本、选择当前平台的发行包、下载并校验摘要，
+//! 再解压出 `upx` 可执行文件。接口地址可以指向本地的模拟服务，便于测试。
+
+use crate::error::{CoreError, Result};
+use crate::hash;
+use crate::http;
+use crate::locate::UpxVersion;
+use crate::registry::{self, RegisteredUpx};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// UPX 发行信息接口的默认地址，可通过 [`crate::AppConfig::upx_release_api`] 修改
+pub const DEFAULT_UPX_RELEASE_API: &str = "https://api.github.com/repos/upx/upx";
+
+/// 发行包中 UPX 可执行文件的名称
+const UPX_FILE_NAME: &str = if cfg!(windows) { "upx.exe" } else { "upx" };
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpxReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    pub size: u64,
+    /// GitHub 提供的摘要，格式为 `sha256:<十六进制>`
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpxRelease {
+    pub tag_name: String,
+    pub html_url: String,
+    pub assets: Vec<UpxReleaseAsset>,
+}
+
+impl UpxRelease {
+    /// 由标签解析版本号，例如 `v4.2.4`
+    pub fn version(&self) -> Option<UpxVersion> {
+        UpxVersion::parse(self.tag_name.trim_start_matches('v'))
+    }
+}
+
+impl UpxReleaseAsset {
+    /// 发行包的 SHA-256，没有摘要时无法校验，不允许安装
+    pub fn sha256(&self) -> Result<&str> {
+        self.digest
+            .as_deref()
+            .and_then(|d| d.strip_prefix("sha256:"))
+            .ok_or_else(|| CoreError::UpxUpdateUnverified(self.name.clone()))
+    }
+}
+
+/// 最新发行版的接口地址
+pub fn latest_release_url(api_base: &str) -> String {
+    format!("{}/releases/latest", api_base.trim_end_matches('/'))
+}
+
+// ============================================================================
+// 网络请求
+// ============================================================================
+
+/// 请求最新发行信息，`api_base` 即 [`crate::AppConfig::upx_release_api`]
+pub fn fetch_latest_release(api_base: &str) -> Result<UpxRelease> {
+    http::get_github_json(&latest_release_url(api_base)).map_err(CoreError::UpxUpdateRequest)
+}
+
+/// 下载发行包
+pub fn download_asset(asset: &UpxReleaseAsset) -> Result<Vec<u8>> {
+    http::download(&asset.browser_download_url, asset.size).map_err(CoreError::UpxUpdateDownload)
+}
+
+/// 下载最新发行版，校验、安装到 `install_root` 并注册，返回注册的条目
+pub fn install_latest_release(
+    api_base: &str,
+    install_root: &Path,
+    binaries: &mut Vec<RegisteredUpx>,
+) -> Result<RegisteredUpx> {
+    let release = fetch_latest_release(api_base)?;
+    let version = release.version().ok_or_else(|| {
+        CoreError::UpxUpdateRequest(format!("无法识别的 UPX 版本: {}", release.tag_name))
+    })?;
+    let asset = select_asset(&release)?;
+    let archive = download_asset(asset)?;
+    let entry = install_upx_archive(&archive, asset, version, install_root)?;
+    registry::add_registered(binaries, entry.clone());
+    Ok(entry)
+}
+
+// ============================================================================
+// 选择、校验与安装
+// ============================================================================
+
+/// 当前平台发行包的文件名后缀；UPX 不提供其他平台的预编译版本
+pub fn platform_asset_suffix() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("windows", "x86_64") => Some("-win64.zip"),
+        ("windows", "x86") => Some("-win32.zip"),
+        ("linux", "x86_64") => Some("-amd64_linux.tar.xz"),
+        ("linux", "aarch64") => Some("-arm64_linux.tar.xz"),
+        ("linux", "x86") => Some("-i386_linux.tar.xz"),
+        _ => None,
+    }
+}
+
+/// 选出当前平台的发行包
+pub fn select_asset(release: &UpxRelease) -> Result<&UpxReleaseAsset> {
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let suffix =
+        platform_asset_suffix().ok_or_else(|| CoreError::UpxUpdateNoAsset(platform.clone()))?;
+
+    release
+        .assets
+        .iter()
+        .find(|a| a.name.ends_with(suffix))
+        .ok_or(CoreError::UpxUpdateNoAsset(platform))
+}
+
+/// 下载的 UPX 安装在 `<本地数据目录>/UPX-Tools/upx/<版本号>/`
+pub fn upx_install_root() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("UPX-Tools").join("upx"))
+}
+
+/// 校验发行包并解压出 UPX，确认版本后安装，返回待注册的条目
+///
+/// 摘要不符时不写入任何文件；可执行文件先写入临时文件，在临时文件上确认
+/// `--version` 正是发行版的版本后，才原子替换到目标位置。版本不符时目标位置
+/// 原有的文件（可能是已注册的 UPX）保持不变。
+pub fn install_upx_archive(
+    archive: &[u8],
+    asset: &UpxReleaseAsset,
+    version: UpxVersion,
+    install_root: &Path,
+) -> Result<RegisteredUpx> {
+    let expected = asset.sha256()?;
+    let actual = hash::sha256_hex(archive);
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(CoreError::UpxUpdateChecksum {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+
+    let binary = if asset.name.ends_with(".zip") {
+        extract_from_zip(archive)?
+    } else if asset.name.ends_with(".tar.xz") {
+        extract_from_tar_xz(archive)?
+    } else {
+        return Err(CoreError::UpxUpdateArchive(format!(
+            "不支持的格式: {}",
+            asset.name
+        )));
+    };
+
+    let dir = install_root.join(version.to_string());
+    fs::create_dir_all(&dir).map_err(CoreError::UpxUpdateInstall)?;
+
+    let mut temp = NamedTempFile::new_in(&dir).map_err(CoreError::UpxUpdateInstall)?;
+    temp.write_all(&binary)
+        .and_then(|_| temp.as_file().sync_all())
+        .map_err(CoreError::UpxUpdateInstall)?;
+    // 关闭写入句柄后才能执行，否则 Linux 会报 ETXTBSY；临时路径仍在丢弃时删除
+    let temp = temp.into_temp_path();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temp, fs::Permissions::from_mode(0o755))
+            .map_err(CoreError::UpxUpdateInstall)?;
+    }
+
+    let probed = registry::probe_upx(&temp)?;
+    if probed.version != version.to_string() {
+        return Err(CoreError::UpxUpdateVersionMismatch {
+            expected: version.to_string(),
+            actual: probed.version,
+        });
+    }
+
+    let upx_path = dir.join(UPX_FILE_NAME);
+    temp.persist(&upx_path)
+        .map_err(|e| CoreError::UpxUpdateInstall(e.error))?;
+
+    Ok(RegisteredUpx {
+        path: upx_path.to_string_lossy().into_owned(),
+        version: probed.version,
+    })
+}
+
+/// 发行包内的路径形如 `upx-4.2.4-win64/upx.exe`
+fn is_upx_entry(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == UPX_FILE_NAME)
+}
+
+fn extract_from_zip(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(archive))
+        .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+        let is_upx = entry.enclosed_name().is_some_and(|p| is_upx_entry(&p));
+        if entry.is_file() && is_upx {
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+            return Ok(data);
+        }
+    }
+
+    Err(CoreError::UpxUpdateArchive(format!(
+        "发行包中没有 {}",
+        UPX_FILE_NAME
+    )))
+}
+
+fn extract_from_tar_xz(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut tar_data = Vec::new();
+    lzma_rs::xz_decompress(&mut Cursor::new(archive), &mut tar_data)
+        .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+
+    let mut tar = tar::Archive::new(Cursor::new(tar_data));
+    let entries = tar
+        .entries()
+        .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+        let is_upx = entry.path().is_ok_and(|p| is_upx_entry(&p));
+        if entry.header().entry_type().is_file() && is_upx {
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(|e| CoreError::UpxUpdateArchive(e.to_string()))?;
+            return Ok(data);
+        }
+    }
+
+    Err(CoreError::UpxUpdateArchive(format!(
+        "发行包中没有 {}",
+        UPX_FILE_NAME
+    )))
+}
@@ -0,0 +1,224 @@
+//! 基准测试：在临时副本上跑遍文件 × 压缩方式 × UPX 版本的组合，原文件保持不变
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use upx_tools_core::bench::{
+    bench_cases, export_benchmark, render_csv, render_table, run_benchmark, summarize, BenchFormat,
+    BenchRecord,
+};
+use upx_tools_core::fake::{fake_pack, FakeFailure, FakePacker};
+use upx_tools_core::registry::UpxRegistry;
+use upx_tools_core::tune::Strategy;
+use upx_tools_core::{UpxMode, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+/// 基准测试按字符串接收文件路径
+fn bench_file(dir: &TempDir, name: &str, data: &[u8]) -> String {
+    write_file(dir.path(), name, data)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn strategies(list: &[&str]) -> Vec<Strategy> {
+    Strategy::parse_list(list).unwrap()
+}
+
+fn base() -> u64 {
+    fake_pack(COMPRESSIBLE).len() as u64
+}
+
+#[test]
+fn cases_cover_every_combination_in_order() {
+    let files = ["a.exe".to_string(), "b.dll".to_string()];
+    let versions = [Some("4.2.4".to_string()), Some("3.96".to_string())];
+
+    let cases = bench_cases(&files, &strategies(&["-9", "--best"]), &versions);
+
+    let combos: Vec<_> = cases
+        .iter()
+        .map(|c| {
+            (
+                c.upx_version.as_deref().unwrap(),
+                c.strategy.to_string(),
+                c.file.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(combos.len(), 8);
+    assert_eq!(combos[0], ("4.2.4", "-9".to_string(), "a.exe"));
+    assert_eq!(combos[1], ("4.2.4", "-9".to_string(), "b.dll"));
+    assert_eq!(combos[2], ("4.2.4", "--best".to_string(), "a.exe"));
+    assert_eq!(combos[7], ("3.96", "--best".to_string(), "b.dll"));
+
+    let default_only = bench_cases(&files, &strategies(&["-9"]), &[]);
+    assert_eq!(default_only.len(), 2);
+    assert!(default_only.iter().all(|c| c.upx_version.is_none()));
+}
+
+#[test]
+fn each_case_is_packed_tested_and_unpacked_on_a_copy() {
+    let dir = TempDir::new().unwrap();
+    let files = vec![
+        bench_file(&dir, "app.exe", COMPRESSIBLE),
+        bench_file(&dir, "lib.dll", COMPRESSIBLE),
+    ];
+    let packer = FakePacker::new().pad_method("-9", 100);
+    let cases = bench_cases(&files, &strategies(&["-9", "--best"]), &[]);
+
+    let mut seen = 0;
+    let records = run_benchmark(&packer, &cases, 2, |_| seen += 1).unwrap();
+
+    assert_eq!(seen, 4);
+    let sizes: Vec<_> = records
+        .iter()
+        .map(|r| (r.strategy.as_str(), r.output_size))
+        .collect();
+    assert_eq!(
+        sizes,
+        [
+            ("-9", base() + 100),
+            ("-9", base() + 100),
+            ("--best", base()),
+            ("--best", base()),
+        ]
+    );
+    for record in &records {
+        assert!(record.is_ok(), "{:?}", record);
+        assert_eq!(record.original_size, COMPRESSIBLE.len() as u64);
+        assert!(record.unpack_ms.is_some());
+    }
+
+    // 每个组合加壳、测试、脱壳各一次，且都不在原文件上
+    let calls = packer.calls();
+    assert_eq!(calls.len(), 12);
+    for mode in [UpxMode::Compress, UpxMode::Test, UpxMode::Decompress] {
+        assert_eq!(calls.iter().filter(|c| c.mode == mode).count(), 4);
+    }
+    assert!(calls.iter().all(|c| !files.contains(&c.input_file)));
+    for file in &files {
+        assert_eq!(fs::read(file).unwrap(), COMPRESSIBLE);
+    }
+}
+
+#[test]
+fn failures_are_recorded_per_case() {
+    let dir = TempDir::new().unwrap();
+    let files = vec![
+        bench_file(&dir, "protected.exe", COMPRESSIBLE),
+        bench_file(&dir, "packed.exe", &fake_pack(COMPRESSIBLE)),
+        bench_file(&dir, "app.exe", COMPRESSIBLE),
+    ];
+    let packer = FakePacker::new()
+        .fail_on("protected", FakeFailure::CantPack)
+        .corrupt_method("--best");
+    let cases = bench_cases(&files, &strategies(&["--best"]), &[]);
+
+    let records = run_benchmark(&packer, &cases, 1, |_| {}).unwrap();
+
+    let protected = &records[0];
+    assert_eq!(protected.status, UpxStatus::Failed);
+    assert_eq!(protected.test_passed, None);
+    assert!(protected
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("无法压缩此文件"));
+
+    assert_eq!(records[1].status, UpxStatus::Skipped);
+    assert_eq!(records[1].output_size, 0);
+
+    let corrupt = &records[2];
+    assert_eq!(corrupt.status, UpxStatus::Success);
+    assert_eq!(corrupt.test_passed, Some(false));
+    assert!(corrupt.error.as_deref().unwrap().contains("upx -t"));
+    assert!(!corrupt.is_ok());
+
+    let totals = summarize(&records);
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].files, 3);
+    // 已加壳的文件只是跳过，不计为失败
+    assert_eq!(totals[0].failed, 2);
+}
+
+#[test]
+fn versions_are_selected_through_the_registry() {
+    let dir = TempDir::new().unwrap();
+    let files = vec![bench_file(&dir, "app.exe", COMPRESSIBLE)];
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new())
+        .with_version("3.96", FakePacker::new().pad_method("-9", 40));
+    let versions: Vec<_> = registry
+        .labels()
+        .into_iter()
+        .map(|label| Some(label.to_string()))
+        .collect();
+    let cases = bench_cases(&files, &strategies(&["-9"]), &versions);
+
+    let records = run_benchmark(&registry, &cases, 1, |_| {}).unwrap();
+
+    let sizes: Vec<_> = records
+        .iter()
+        .map(|r| (r.upx_version.as_deref().unwrap(), r.output_size))
+        .collect();
+    assert_eq!(sizes, [("4.2.4", base()), ("3.96", base() + 40)]);
+    assert!(records.iter().all(BenchRecord::is_ok));
+}
+
+#[test]
+fn results_export_as_csv_table_and_json() {
+    let dir = TempDir::new().unwrap();
+    let files = vec![
+        bench_file(&dir, "app.exe", COMPRESSIBLE),
+        bench_file(&dir, "lib.dll", COMPRESSIBLE),
+    ];
+    let packer = FakePacker::new().pad_method("-9", 100);
+    let cases = bench_cases(&files, &strategies(&["-9", "--best"]), &[]);
+    let records = run_benchmark(&packer, &cases, 1, |_| {}).unwrap();
+
+    let totals = summarize(&records);
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].strategy, "-9");
+    assert_eq!(totals[0].files, 2);
+    assert_eq!(totals[0].failed, 0);
+    assert_eq!(totals[0].output_size, 2 * (base() + 100));
+    assert_eq!(totals[1].output_size, 2 * base());
+    assert!(totals[1].ratio < totals[0].ratio);
+
+    let csv = render_csv(&records, "4.2.4");
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "\u{feff}file,upx_version,strategy,status,original_size,output_size,ratio,\
+pack_ms,unpack_ms,test_passed,error"
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with(&format!(
+        "{},4.2.4,-9,success,4096,{},",
+        files[0],
+        base() + 100
+    )));
+    assert!(row.ends_with(",true,"));
+    assert_eq!(lines.count(), 3);
+
+    let table = render_table(&records, "4.2.4");
+    assert!(table.contains("--best"));
+    assert!(table.contains("lib.dll"));
+
+    for (name, format) in [
+        ("bench.csv", BenchFormat::Csv),
+        ("bench.json", BenchFormat::Json),
+        ("bench.txt", BenchFormat::Table),
+    ] {
+        let path: PathBuf = dir.path().join(name);
+        assert_eq!(BenchFormat::from_path(&path), format);
+        export_benchmark(&records, "4.2.4", &path, format).unwrap();
+        assert!(fs::metadata(&path).unwrap().len() > 0);
+    }
+    let json = fs::read_to_string(dir.path().join("bench.json")).unwrap();
+    let parsed: Vec<BenchRecord> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, records);
+}
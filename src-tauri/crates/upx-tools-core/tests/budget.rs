@@ -0,0 +1,202 @@
+//! 体积预算：从快到慢逐个尝试，采用第一个满足预算的结果，都不满足时失败
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::args::build_compress_args;
+use upx_tools_core::budget::BUDGET_NOT_MET;
+use upx_tools_core::fake::{fake_pack, FakePacker};
+use upx_tools_core::manifest::{Manifest, SIDECAR_SUFFIX};
+use upx_tools_core::output::failure_kind;
+use upx_tools_core::process::run_upx;
+use upx_tools_core::report::{render_csv, BatchReport, ReportEntry};
+use upx_tools_core::tune::{AutoTuner, Strategy};
+use upx_tools_core::{CoreError, ManifestMode, SizeBudget, UpxMode, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(input: &Path, budget: SizeBudget) -> UpxOptions {
+    UpxOptions {
+        budget: Some(budget),
+        ..common::options(input, input)
+    }
+}
+
+/// `-9` 最大、`--best` 其次、`--best --lzma` 最小
+fn tuner() -> AutoTuner<FakePacker> {
+    let packer = FakePacker::new()
+        .pad_method("-9", 200)
+        .pad_method("--best", 100);
+    let strategies = Strategy::parse_list(&["-9", "--best", "--best --lzma"]).unwrap();
+    AutoTuner::new(packer, strategies)
+}
+
+fn base() -> u64 {
+    fake_pack(COMPRESSIBLE).len() as u64
+}
+
+#[test]
+fn budgets_parse_and_display() {
+    for (text, budget, display) in [
+        ("512KB", SizeBudget::MaxBytes(512 * 1024), "512KB"),
+        ("1.5 mb", SizeBudget::MaxBytes(1536 * 1024), "1536KB"),
+        ("2G", SizeBudget::MaxBytes(2 << 30), "2GB"),
+        ("1000", SizeBudget::MaxBytes(1000), "1000"),
+        ("60%", SizeBudget::MaxRatio(60), "60%"),
+    ] {
+        let parsed: SizeBudget = text.parse().unwrap();
+        assert_eq!(parsed, budget, "{}", text);
+        assert_eq!(parsed.to_string(), display);
+    }
+
+    for text in ["", "0", "0%", "101%", "abc", "5 TB", "-1", "1.5%"] {
+        let err = text.parse::<SizeBudget>().unwrap_err();
+        assert!(matches!(err, CoreError::InvalidBudget(_)), "{}", text);
+    }
+
+    assert_eq!(
+        serde_json::from_str::<SizeBudget>("1024").unwrap(),
+        SizeBudget::MaxBytes(1024)
+    );
+    assert_eq!(
+        serde_json::to_string(&SizeBudget::MaxBytes(1024)).unwrap(),
+        "\"1KB\""
+    );
+    assert_eq!(SizeBudget::MaxRatio(60).limit(1000), 600);
+}
+
+#[test]
+fn escalation_stops_at_the_first_strategy_that_fits() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let tuner = tuner();
+    let job = options(&input, SizeBudget::MaxBytes(base() + 150));
+
+    let result = run_upx(&tuner, &job).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(result.output_size, base() + 100);
+    let tried: Vec<_> = result
+        .strategies
+        .iter()
+        .map(|t| (t.strategy.as_str(), t.chosen))
+        .collect();
+    assert_eq!(tried, [("-9", false), ("--best", true)]);
+    // 更慢的 LZMA 没有运行
+    assert_eq!(tuner.inner().calls().len(), 4);
+
+    let check = result.budget.as_ref().unwrap();
+    assert!(check.met);
+    assert_eq!(check.best_size, Some(base() + 100));
+    assert!(result.message.contains("[预算] 选用 --best"));
+
+    let entry = ReportEntry::from_result(Some(&job), &result);
+    assert_eq!(
+        entry.method,
+        format!("budget {}: --best", job.budget.unwrap().describe())
+    );
+    let csv = render_csv(&BatchReport::new(UpxMode::Compress, vec![entry]));
+    let row = csv.lines().nth(1).unwrap();
+    assert!(row.ends_with(&format!(",{},true", base() + 150)));
+}
+
+#[test]
+fn nothing_fitting_fails_with_the_best_size_reached() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let tuner = tuner();
+
+    let result = run_upx(&tuner, &options(&input, SizeBudget::MaxBytes(base() - 1))).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Failed);
+    let reason = result.reason.as_deref().unwrap();
+    assert!(reason.starts_with(BUDGET_NOT_MET));
+    assert_eq!(failure_kind(reason), "BudgetNotMet");
+
+    let check = result.budget.as_ref().unwrap();
+    assert!(!check.met);
+    assert_eq!(check.limit_bytes, base() - 1);
+    assert_eq!(check.best_size, Some(base()));
+    assert_eq!(check.best_strategy.as_deref(), Some("--best --lzma"));
+    assert_eq!(result.strategies.len(), 3);
+    assert!(result.strategies.iter().all(|t| t.passed && !t.chosen));
+    assert_eq!(fs::read(&input).unwrap(), COMPRESSIBLE);
+}
+
+#[test]
+fn ratio_budgets_are_relative_to_the_input() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let tuner = tuner();
+
+    let result = run_upx(&tuner, &options(&input, SizeBudget::MaxRatio(50))).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(result.strategies.len(), 1);
+    let check = result.budget.unwrap();
+    assert_eq!(check.limit_bytes, COMPRESSIBLE.len() as u64 / 2);
+}
+
+#[test]
+fn packed_inputs_are_still_skipped() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "packed.exe", &fake_pack(COMPRESSIBLE));
+
+    let result = run_upx(&tuner(), &options(&input, SizeBudget::MaxRatio(50))).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert!(result.budget.is_none());
+}
+
+#[test]
+fn manifest_records_the_strategy_that_met_the_budget() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let job = UpxOptions {
+        manifest: ManifestMode::Sidecar,
+        ..options(&input, SizeBudget::MaxBytes(base() + 150))
+    };
+
+    let result = run_upx(&tuner(), &job).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+
+    let sidecar = dir.path().join(format!("app.exe{}", SIDECAR_SUFFIX));
+    let entry = &Manifest::load(&sidecar).unwrap().entries[0];
+    let met: Strategy = "--best".parse().unwrap();
+    // 记录升级后的 --best，而不是任务中配置的 -9
+    assert_eq!(
+        entry.args,
+        build_compress_args(&met.apply(&job), true).collect::<Vec<_>>()
+    );
+    assert!(!entry.args.contains(&"-9".to_string()));
+}
+
+#[test]
+fn nothing_passing_test_is_still_a_budget_failure() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new()
+        .corrupt_method("-9")
+        .corrupt_method("--best");
+    let tuner = AutoTuner::new(packer, Strategy::parse_list(&["-9", "--best"]).unwrap());
+
+    let result = run_upx(&tuner, &options(&input, SizeBudget::MaxRatio(50))).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Failed);
+    let reason = result.reason.as_deref().unwrap();
+    assert_eq!(failure_kind(reason), "BudgetNotMet");
+    assert!(reason.contains("没有压缩方式通过测试"));
+    assert!(result
+        .strategies
+        .iter()
+        .all(|t| !t.passed && t.reason.as_deref().unwrap().contains("upx -t")));
+
+    let check = result.budget.as_ref().unwrap();
+    assert!(!check.met);
+    assert_eq!(check.best_size, None);
+    assert_eq!(check.best_strategy, None);
+    assert_eq!(fs::read(&input).unwrap(), COMPRESSIBLE);
+}
@@ -0,0 +1,233 @@
+//! 加壳缓存：命中时不运行 UPX、缓存键、按最近使用淘汰与清空
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+use upx_tools_core::cache::{cache_key, CachedPacker, PackCache};
+use upx_tools_core::fake::{FakeFailure, FakePacker};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::{ConflictPolicy, SizeBudget, UpxMode, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(input: &Path) -> UpxOptions {
+    UpxOptions {
+        compression_level: "best".to_string(),
+        lzma: true,
+        ..common::options(input, input)
+    }
+}
+
+fn compress_calls(packer: &CachedPacker<FakePacker>) -> usize {
+    packer
+        .inner()
+        .calls()
+        .iter()
+        .filter(|call| call.mode == UpxMode::Compress)
+        .count()
+}
+
+#[test]
+fn same_content_is_served_from_cache() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let packer = CachedPacker::new(FakePacker::new(), Some(cache.clone()));
+    let first = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let second = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+
+    let result = run_upx(&packer, &options(&first)).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+    let result = run_upx(&packer, &options(&second)).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+
+    assert_eq!(compress_calls(&packer), 1);
+    assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+    assert_eq!(result.output_size, fs::metadata(&second).unwrap().len());
+    assert_eq!(cache.info().entries, 1);
+}
+
+#[test]
+fn other_options_or_versions_miss() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("app_packed.exe");
+    let job = UpxOptions {
+        output_file: output.to_string_lossy().into_owned(),
+        ..options(&input)
+    };
+
+    let packer = CachedPacker::new(FakePacker::new(), Some(cache.clone()));
+    run_upx(&packer, &job).unwrap();
+    run_upx(
+        &packer,
+        &UpxOptions {
+            lzma: false,
+            ..job.clone()
+        },
+    )
+    .unwrap();
+    assert_eq!(compress_calls(&packer), 2);
+
+    let newer = CachedPacker::new(
+        FakePacker::new().with_version("upx 5.0.0"),
+        Some(cache.clone()),
+    );
+    run_upx(&newer, &job).unwrap();
+    assert_eq!(compress_calls(&newer), 1);
+    assert_eq!(cache.info().entries, 3);
+}
+
+#[test]
+fn key_ignores_paths_and_backup() {
+    let a = options(Path::new("a.exe"));
+    let b = UpxOptions {
+        backup: true,
+        on_conflict: ConflictPolicy::Rename,
+        ..options(Path::new("other/b.exe"))
+    };
+    assert_eq!(cache_key("00", &a, "4.2.4"), cache_key("00", &b, "4.2.4"));
+    assert_ne!(cache_key("00", &a, "4.2.4"), cache_key("01", &a, "4.2.4"));
+    assert_ne!(cache_key("00", &a, "4.2.4"), cache_key("00", &a, "3.96.0"));
+
+    let brute = UpxOptions {
+        ultra_brute: true,
+        ..a.clone()
+    };
+    assert_ne!(
+        cache_key("00", &a, "4.2.4"),
+        cache_key("00", &brute, "4.2.4")
+    );
+}
+
+#[test]
+fn key_uses_the_level_rather_than_the_budget() {
+    let budget = Some(SizeBudget::MaxRatio(60));
+    let level = |level: &str, budget| UpxOptions {
+        compression_level: level.to_string(),
+        budget,
+        ..options(Path::new("a.exe"))
+    };
+
+    // 预算相同、压缩参数不同时输出不同
+    assert_ne!(
+        cache_key("00", &level("9", budget), "4.2.4"),
+        cache_key("00", &level("best", budget), "4.2.4")
+    );
+    assert_eq!(
+        cache_key("00", &level("9", budget), "4.2.4"),
+        cache_key("00", &level("9", None), "4.2.4")
+    );
+}
+
+#[test]
+fn failed_packs_are_not_cached() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let packer = CachedPacker::new(
+        FakePacker::new().fail_on("setup", FakeFailure::CantPack),
+        Some(cache.clone()),
+    );
+    let input = write_file(dir.path(), "setup.exe", COMPRESSIBLE);
+
+    let result = run_upx(&packer, &options(&input)).unwrap();
+    assert_eq!(result.status, UpxStatus::Failed);
+    assert_eq!(cache.info().entries, 0);
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), 10);
+    let source = write_file(dir.path(), "object", b"1234");
+    let dest = dir.path().join("restored");
+
+    cache.put("first", &source).unwrap();
+    cache.put("second", &source).unwrap();
+    // 读取一次后 first 比 second 更新
+    assert!(cache.get("first", &dest).unwrap());
+    cache.put("third", &source).unwrap();
+
+    assert!(cache.get("first", &dest).unwrap());
+    assert!(!cache.get("second", &dest).unwrap());
+    assert!(cache.get("third", &dest).unwrap());
+    assert_eq!(cache.info().total_bytes, 8);
+
+    // 比上限还大的文件不缓存
+    let large = write_file(dir.path(), "large", &[0u8; 11]);
+    cache.put("large", &large).unwrap();
+    assert!(!cache.get("large", &dest).unwrap());
+}
+
+#[test]
+fn index_updates_wait_for_other_processes() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let source = write_file(dir.path(), "object", b"1234");
+
+    // 模拟另一个进程持有缓存的锁文件
+    let other = fs::File::create(dir.path().join("cache.lock")).unwrap();
+    other.lock().unwrap();
+    let writer = {
+        let cache = cache.clone();
+        thread::spawn(move || cache.put("key", &source).unwrap())
+    };
+    thread::sleep(Duration::from_millis(200));
+    assert!(!writer.is_finished());
+
+    other.unlock().unwrap();
+    writer.join().unwrap();
+    assert_eq!(cache.info().entries, 1);
+    // 索引改名替换，不留下临时文件
+    let mut names: Vec<_> = fs::read_dir(cache.dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["index.json", "objects"]);
+}
+
+#[test]
+fn info_waits_for_writers_but_not_for_readers() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let other = fs::File::create(dir.path().join("cache.lock")).unwrap();
+
+    // 其他进程只读时可以同时读取
+    other.lock_shared().unwrap();
+    assert_eq!(cache.info().entries, 0);
+    other.unlock().unwrap();
+
+    // 其他进程正在写入时等待
+    other.lock().unwrap();
+    let reader = {
+        let cache = cache.clone();
+        thread::spawn(move || cache.info())
+    };
+    thread::sleep(Duration::from_millis(200));
+    assert!(!reader.is_finished());
+
+    other.unlock().unwrap();
+    assert_eq!(reader.join().unwrap().entries, 0);
+}
+
+#[test]
+fn clear_removes_everything() {
+    let dir = TempDir::new().unwrap();
+    let cache = PackCache::new(dir.path().join("cache"), u64::MAX);
+    let source = write_file(dir.path(), "object", b"1234");
+    cache.put("first", &source).unwrap();
+    cache.put("second", &source).unwrap();
+
+    let cleared = cache.clear().unwrap();
+    assert_eq!((cleared.entries, cleared.total_bytes), (2, 8));
+    assert!(!cache.dir().exists());
+    assert_eq!(cache.info().entries, 0);
+    // 没有缓存目录时也可以清空
+    assert!(cache.clear().is_ok());
+}
@@ -0,0 +1,138 @@
+//! `upx --version`/`--help` 输出的解析与探测缓存
+
+mod common;
+
+use std::path::Path;
+use upx_tools_core::capabilities::UpxCapabilities;
+use upx_tools_core::locate::UpxVersion;
+use upx_tools_core::process::supported_options;
+use upx_tools_core::UpxOptions;
+
+const VERSION_OUTPUT: &str = "upx 4.2.4
+UCL data compression library 1.03
+zlib data compression library 1.3.1.1-motley
+LZMA SDK version 4.43
+doctest C++ testing framework version 2.4.11
+Copyright (C) 1996-2024 Markus Franz Xaver Johannes Oberhumer
+";
+
+const HELP_OUTPUT: &str = "Usage: upx [-123456789dlthVL] [-qvfk] [-o file] file..
+
+Commands:
+  -1     compress faster                   -9    compress better
+  --best compress best (can be slow for big files)
+  -d     decompress                        -l    list compressed file
+
+Options:
+  -q     be quiet                          -v    be verbose
+  -oFILE write output to 'FILE'
+  -f     force compression of suspicious files
+  --no-color, --mono, --color, --no-progress   change look
+
+Compression tuning options:
+  --lzma              try LZMA [slower but tighter than NRV]
+  --brute             try all available compression methods & filters [slow]
+  --ultra-brute       try even more compression variants [very slow]
+
+Backup options:
+  -k, --backup        keep backup files
+  --no-backup         no backup files [default]
+
+Overlay options:
+  --overlay=copy      copy any extra data attached to the file [default]
+
+This version supports:
+    amd64-darwin.dylib                   dylib/amd64
+    i386-win32.pe                        win32/pe
+    amd64-win64.pe                       win64/pe
+
+UPX comes with ABSOLUTELY NO WARRANTY; for details visit https://upx.github.io
+";
+
+fn options() -> UpxOptions {
+    let file = Path::new("a.exe");
+    UpxOptions {
+        lzma: true,
+        ultra_brute: true,
+        force: true,
+        ..common::options(file, file)
+    }
+}
+
+#[test]
+fn parses_version_and_libraries() {
+    let caps = UpxCapabilities::parse(VERSION_OUTPUT, HELP_OUTPUT);
+
+    assert_eq!(caps.version_line, "upx 4.2.4");
+    assert_eq!(caps.version, Some(UpxVersion::new(4, 2, 4)));
+    assert_eq!(caps.ucl.as_deref(), Some("1.03"));
+    assert_eq!(caps.zlib.as_deref(), Some("1.3.1.1-motley"));
+    assert_eq!(caps.lzma.as_deref(), Some("4.43"));
+}
+
+#[test]
+fn parses_formats_and_options() {
+    let caps = UpxCapabilities::parse(VERSION_OUTPUT, HELP_OUTPUT);
+
+    assert_eq!(caps.formats, ["dylib/amd64", "win32/pe", "win64/pe"]);
+    assert!(caps.supports("--lzma"));
+    assert!(caps.supports("--ultra-brute"));
+    assert!(caps.supports("--no-backup"));
+    assert!(caps.supports("--overlay"));
+    assert!(!caps.supports("--force"));
+}
+
+#[test]
+fn missing_help_does_not_restrict_options() {
+    let caps = UpxCapabilities::parse("upx 3.96\nUCL data compression library 1.03\n", "");
+
+    assert_eq!(caps.version, Some(UpxVersion::new(3, 96, 0)));
+    assert!(caps.lzma.is_none());
+    assert!(caps.formats.is_empty());
+    assert!(caps.supports("--lzma"));
+}
+
+#[test]
+fn unsupported_options_are_dropped() {
+    let caps = UpxCapabilities::parse(VERSION_OUTPUT, "  --best\n  --brute\n  --force\n");
+    let adjusted = supported_options(&options(), &caps);
+
+    assert!(!adjusted.lzma);
+    assert!(!adjusted.ultra_brute);
+    assert!(adjusted.force);
+    assert_eq!(adjusted.compression_level, "9");
+}
+
+#[cfg(unix)]
+#[test]
+fn probe_is_cached_until_binary_changes() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use upx_tools_core::capabilities::probe_capabilities;
+
+    let dir = tempfile::tempdir().unwrap();
+    let upx = dir.path().join("upx");
+    let counter = dir.path().join("count");
+    let script = |version: &str| {
+        format!(
+            "#!/bin/sh\necho x >> '{}'\n[ \"$1\" = --version ] && echo 'upx {}'\n[ \"$1\" = --help ] && echo '  --lzma'\nexit 0\n",
+            counter.display(),
+            version
+        )
+    };
+    fs::write(&upx, script("4.2.4")).unwrap();
+    fs::set_permissions(&upx, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let first = probe_capabilities(&upx).unwrap();
+    let second = probe_capabilities(&upx).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first.version, Some(UpxVersion::new(4, 2, 4)));
+    assert!(!first.supports("--ultra-brute"));
+    // --version 与 --help 各运行一次
+    assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 2);
+
+    // 文件大小变化后重新探测
+    fs::write(&upx, script("5.0.10")).unwrap();
+    let updated = probe_capabilities(&upx).unwrap();
+    assert_eq!(updated.version, Some(UpxVersion::new(5, 0, 10)));
+}
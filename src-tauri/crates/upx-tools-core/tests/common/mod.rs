@@ -0,0 +1,35 @@
+//! 集成测试共用的辅助函数
+
+// 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use upx_tools_core::{ConflictPolicy, ManifestMode, UpxMode, UpxOptions};
+
+pub fn write_file(dir: &Path, name: &str, data: &[u8]) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, data).unwrap();
+    path
+}
+
+/// 以 `-9` 把 `input` 加壳到 `output` 的任务，输出已存在时覆盖
+///
+/// 测试需要的其他选项用结构体更新语法在此基础上修改。
+pub fn options(input: &Path, output: &Path) -> UpxOptions {
+    UpxOptions {
+        mode: UpxMode::Compress,
+        input_file: input.to_string_lossy().into_owned(),
+        output_file: output.to_string_lossy().into_owned(),
+        compression_level: "9".to_string(),
+        backup: false,
+        lzma: false,
+        nrv: None,
+        ultra_brute: false,
+        force: false,
+        upx_version: None,
+        on_conflict: ConflictPolicy::Overwrite,
+        manifest: ManifestMode::Off,
+        budget: None,
+    }
+}
@@ -0,0 +1,195 @@
+//! 批次内去重：内容相同的文件只运行一次 UPX，其余复制结果
+
+mod common;
+
+use common::{options, write_file};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::batch;
+use upx_tools_core::dedup::{self, DedupSavings};
+use upx_tools_core::fake::{FakeFailure, FakePacker};
+use upx_tools_core::report::{render_csv, BatchReport, ReportEntry};
+use upx_tools_core::tune::{method_with_candidates, AutoTuner, Strategy};
+use upx_tools_core::{ConflictPolicy, UpxMode, UpxOptions, UpxResult, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+/// 处理全部任务，按输入文件名返回结果
+fn run(packer: &FakePacker, jobs: &[UpxOptions]) -> Vec<UpxResult> {
+    let mut results = Vec::new();
+    batch::run_batch(packer, jobs, 2, |_, result| results.push(result));
+    results.sort_by(|a, b| a.input_file.cmp(&b.input_file));
+    results
+}
+
+#[test]
+fn identical_files_are_found_by_size_then_hash() {
+    let dir = TempDir::new().unwrap();
+    let files = [
+        write_file(dir.path(), "a.dll", COMPRESSIBLE),
+        write_file(dir.path(), "b.dll", &[1u8; 4096]),
+        write_file(dir.path(), "c.dll", COMPRESSIBLE),
+        write_file(dir.path(), "d.dll", &[0u8; 8192]),
+        dir.path().join("missing.dll"),
+    ];
+    let files: Vec<String> = files
+        .iter()
+        .map(|f| f.to_string_lossy().into_owned())
+        .collect();
+
+    let identical = dedup::identical_files(&files);
+    assert_eq!(identical.into_iter().collect::<Vec<_>>(), [(2, 0)]);
+}
+
+#[test]
+fn duplicates_are_copied_without_running_upx() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+    let c = write_file(dir.path(), "c.dll", COMPRESSIBLE);
+    let other = write_file(dir.path(), "other.dll", &[0u8; 8192]);
+    let jobs = vec![
+        options(&a, &a),
+        options(&b, &b),
+        options(&c, &dir.path().join("c_packed.dll")),
+        options(&other, &other),
+    ];
+    let packer = FakePacker::new();
+
+    let results = run(&packer, &jobs);
+
+    assert_eq!(packer.calls().len(), 2);
+    assert!(results
+        .iter()
+        .all(|r| r.status == UpxStatus::Success && r.output_sha256.is_some()));
+    let packed = fs::read(&a).unwrap();
+    assert_eq!(fs::read(&b).unwrap(), packed);
+    assert_eq!(fs::read(dir.path().join("c_packed.dll")).unwrap(), packed);
+    assert_eq!(fs::read(&c).unwrap(), COMPRESSIBLE);
+
+    let a_str = a.to_string_lossy();
+    assert_eq!(results[0].duplicate_of, None);
+    assert_eq!(results[1].duplicate_of.as_deref(), Some(&*a_str));
+    assert_eq!(results[2].duplicate_of.as_deref(), Some(&*a_str));
+    assert_eq!(results[3].duplicate_of, None);
+    assert!(results[1].message.contains("[去重]"));
+
+    let mut savings = DedupSavings::default();
+    results
+        .iter()
+        .for_each(|r| savings.record(r.duplicate_of.as_deref(), r.original_size));
+    assert_eq!(
+        savings,
+        DedupSavings {
+            files: 2,
+            bytes: 2 * COMPRESSIBLE.len() as u64,
+        }
+    );
+}
+
+#[test]
+fn different_options_are_not_deduplicated() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+    let jobs = vec![
+        options(&a, &a),
+        UpxOptions {
+            lzma: true,
+            ..options(&b, &b)
+        },
+    ];
+
+    assert!(dedup::duplicate_inputs(&jobs, &[]).is_empty());
+    // 冲突处理与备份不同时也分别处理
+    let c = write_file(dir.path(), "c.dll", COMPRESSIBLE);
+    let d = write_file(dir.path(), "d.dll", COMPRESSIBLE);
+    let others = vec![
+        options(&a, &a),
+        UpxOptions {
+            on_conflict: ConflictPolicy::Rename,
+            ..options(&c, &c)
+        },
+        UpxOptions {
+            backup: true,
+            ..options(&d, &d)
+        },
+    ];
+    assert!(dedup::duplicate_inputs(&others, &[]).is_empty());
+
+    let packer = FakePacker::new();
+    let results = run(&packer, &jobs);
+    assert_eq!(packer.calls().len(), 2);
+    assert!(results.iter().all(|r| r.duplicate_of.is_none()));
+}
+
+#[test]
+fn tuned_jobs_are_grouped_by_their_candidates() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+    let auto = |file: &Path| UpxOptions {
+        compression_level: "auto".to_string(),
+        ..options(file, file)
+    };
+    let jobs = vec![auto(&a), auto(&b)];
+    let strategies = Strategy::parse_list(&["-9", "--best"]).unwrap();
+
+    assert_eq!(dedup::duplicate_inputs(&jobs, &strategies).len(), 1);
+    // 同为 auto，候选不同时选出的压缩方式可能不同
+    assert_ne!(
+        method_with_candidates(&jobs[0], &strategies),
+        method_with_candidates(&jobs[0], &strategies[..1])
+    );
+    assert_eq!(
+        method_with_candidates(&options(&a, &a), &strategies),
+        method_with_candidates(&options(&a, &a), &[])
+    );
+
+    let mut results = Vec::new();
+    let packer = AutoTuner::new(FakePacker::new(), strategies);
+    batch::run_batch(&packer, &jobs, 2, |_, result| results.push(result));
+    assert_eq!(packer.inner().calls().len(), 4);
+    assert_eq!(
+        results.iter().filter(|r| r.duplicate_of.is_some()).count(),
+        1
+    );
+    assert!(results.iter().all(|r| r.strategies.len() == 2));
+}
+
+#[test]
+fn failed_representative_lets_duplicates_run() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+    let packer = FakePacker::new().fail_on("a.dll", FakeFailure::CantPack);
+
+    let results = run(&packer, &[options(&a, &a), options(&b, &b)]);
+
+    assert_eq!(packer.calls().len(), 2);
+    assert_eq!(results[0].status, UpxStatus::Failed);
+    assert_eq!(results[1].status, UpxStatus::Success);
+    assert_eq!(results[1].duplicate_of, None);
+}
+
+#[test]
+fn report_counts_deduplicated_files() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.dll", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.dll", COMPRESSIBLE);
+    let jobs = vec![options(&a, &a), options(&b, &b)];
+
+    let mut entries = Vec::new();
+    batch::run_batch(&FakePacker::new(), &jobs, 1, |job, result| {
+        entries.push(ReportEntry::from_result(Some(job), &result));
+    });
+    let report = BatchReport::new(UpxMode::Compress, entries);
+
+    assert_eq!(report.deduplicated.files, 1);
+    assert_eq!(report.deduplicated.bytes, COMPRESSIBLE.len() as u64);
+    let csv = render_csv(&report);
+    let header = csv.lines().next().unwrap();
+    assert!(header.split(',').any(|column| column == "duplicate_of"));
+    assert!(csv.contains(&*a.to_string_lossy()));
+}
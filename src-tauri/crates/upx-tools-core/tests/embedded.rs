@@ -0,0 +1,89 @@
+//! 内嵌 UPX 的校验与释放
+//!
+//! 释放结果在进程内缓存，因此整个流程放在一个测试中按顺序验证。
+
+#![cfg(target_os = "linux")]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+use upx_tools_core::hash::sha256_hex;
+use upx_tools_core::locate::{extract_embedded_upx, EmbeddedUpx};
+use upx_tools_core::CoreError;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn extraction_is_verified_and_scoped_by_hash() {
+    let cache = TempDir::new().unwrap();
+    std::env::set_var("XDG_CACHE_HOME", cache.path());
+
+    let data = b"MZ fake upx payload".repeat(64);
+    let sha256 = sha256_hex(&data);
+    let compressed = gzip(&data);
+    assert!(compressed.len() < data.len());
+
+    // 摘要不符或数据无法解压时拒绝释放
+    let wrong = "0".repeat(64);
+    for (compressed, sha256) in [
+        (compressed.as_slice(), wrong.as_str()),
+        (&data[..], &sha256),
+    ] {
+        let err = extract_embedded_upx(&EmbeddedUpx { compressed, sha256 }).unwrap_err();
+        assert!(matches!(err, CoreError::EmbeddedUpxCorrupt));
+    }
+
+    // 预先放一个大小相同但内容被篡改的文件
+    let dir = cache.path().join("UPX-Tools/upx").join(&sha256);
+    fs::create_dir_all(&dir).unwrap();
+    let tampered = vec![b'X'; data.len()];
+    fs::write(dir.join("upx"), &tampered).unwrap();
+
+    let embedded = EmbeddedUpx {
+        compressed: &compressed,
+        sha256: &sha256,
+    };
+    let path = extract_embedded_upx(&embedded).unwrap();
+
+    assert_eq!(path, dir.join("upx"));
+    assert_eq!(fs::read(&path).unwrap(), data);
+    assert_eq!(
+        fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+        0o700
+    );
+    assert_eq!(
+        fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o700
+    );
+
+    // 目录中不残留临时文件
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    assert_eq!(extract_embedded_upx(&embedded).unwrap(), path);
+
+    // 换一份内嵌数据时按其摘要另行释放，不沿用之前的路径
+    let other_data = b"MZ another upx payload".repeat(64);
+    let other_sha256 = sha256_hex(&other_data);
+    let other_compressed = gzip(&other_data);
+    let other = extract_embedded_upx(&EmbeddedUpx {
+        compressed: &other_compressed,
+        sha256: &other_sha256,
+    })
+    .unwrap();
+    assert_eq!(
+        other,
+        cache
+            .path()
+            .join("UPX-Tools/upx")
+            .join(&other_sha256)
+            .join("upx")
+    );
+    assert_eq!(fs::read(&other).unwrap(), other_data);
+    assert_eq!(extract_embedded_upx(&embedded).unwrap(), path);
+}
@@ -0,0 +1,185 @@
+//! 处理历史：追加、查询条件与导出
+
+mod common;
+
+use chrono::{Duration, Local};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::history::{
+    export_history, parse_date, ExportFormat, HistoryQuery, HistoryRecord, HistoryStore,
+};
+use upx_tools_core::{ConflictPolicy, CoreError, UpxOptions, UpxResult, UpxStatus};
+
+fn options(input: &str) -> UpxOptions {
+    UpxOptions {
+        lzma: true,
+        on_conflict: ConflictPolicy::default(),
+        ..common::options(Path::new(input), Path::new(&format!("{}.packed", input)))
+    }
+}
+
+fn record(input: &str, status: UpxStatus, days_ago: i64) -> HistoryRecord {
+    let options = options(input);
+    let result = UpxResult {
+        status,
+        original_size: 4096,
+        output_size: 1024,
+        ratio: 25,
+        ..UpxResult::failed(&options.input_file, &options.output_file, String::new())
+    };
+    let mut record = HistoryRecord::new(&options, Some("4.2.4".to_string()), &result);
+    record.timestamp -= Duration::days(days_ago);
+    record
+}
+
+fn store(dir: &TempDir) -> HistoryStore {
+    HistoryStore::new(dir.path().join("data").join("history.jsonl"))
+}
+
+#[test]
+fn missing_file_is_empty_history() {
+    let dir = TempDir::new().unwrap();
+    assert!(store(&dir).load().unwrap().is_empty());
+}
+
+#[test]
+fn appended_records_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let store = store(&dir);
+
+    store
+        .append(&record("C:/build/app.exe", UpxStatus::Success, 0))
+        .unwrap();
+    let error = CoreError::InputNotFound("C:/build/gone.exe".to_string());
+    store
+        .append(&HistoryRecord::failed(
+            &options("C:/build/gone.exe"),
+            None,
+            &error,
+        ))
+        .unwrap();
+
+    let records = store.load().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].upx_version.as_deref(), Some("4.2.4"));
+    assert_eq!(records[0].output_size, 1024);
+    assert!(records[0].options.lzma);
+    assert_eq!(records[1].status, UpxStatus::Failed);
+    assert!(records[1].reason.as_deref().unwrap().contains("gone.exe"));
+}
+
+#[test]
+fn broken_lines_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let store = store(&dir);
+    store
+        .append(&record("a.exe", UpxStatus::Success, 0))
+        .unwrap();
+    fs::OpenOptions::new()
+        .append(true)
+        .open(store.path())
+        .unwrap()
+        .write_all(b"{\"timestamp\":\n")
+        .unwrap();
+    store
+        .append(&record("b.exe", UpxStatus::Success, 0))
+        .unwrap();
+
+    assert_eq!(store.load().unwrap().len(), 2);
+}
+
+#[test]
+fn query_filters_by_path_date_and_status() {
+    let dir = TempDir::new().unwrap();
+    let store = store(&dir);
+    for record in [
+        record("D:/Release/app.exe", UpxStatus::Success, 10),
+        record("D:/Release/lib.dll", UpxStatus::Failed, 3),
+        record("E:/other/tool.exe", UpxStatus::Success, 1),
+        record("D:/release/new.exe", UpxStatus::Skipped, 0),
+    ] {
+        store.append(&record).unwrap();
+    }
+    let inputs = |query: HistoryQuery| -> Vec<String> {
+        store
+            .query(&query)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.input_file)
+            .collect()
+    };
+    let today = Local::now().date_naive();
+
+    // 最新的在前，路径不区分大小写
+    assert_eq!(
+        inputs(HistoryQuery {
+            path: Some("release".to_string()),
+            ..Default::default()
+        }),
+        [
+            "D:/release/new.exe",
+            "D:/Release/lib.dll",
+            "D:/Release/app.exe"
+        ]
+    );
+    assert_eq!(
+        inputs(HistoryQuery {
+            status: Some(UpxStatus::Success),
+            ..Default::default()
+        }),
+        ["E:/other/tool.exe", "D:/Release/app.exe"]
+    );
+    assert_eq!(
+        inputs(HistoryQuery {
+            since: Some(today - Duration::days(3)),
+            until: Some(today - Duration::days(1)),
+            ..Default::default()
+        }),
+        ["E:/other/tool.exe", "D:/Release/lib.dll"]
+    );
+    assert_eq!(
+        inputs(HistoryQuery {
+            limit: Some(1),
+            ..Default::default()
+        }),
+        ["D:/release/new.exe"]
+    );
+}
+
+#[test]
+fn dates_must_be_iso() {
+    assert!(parse_date("2024-05-01").is_ok());
+    assert!(matches!(
+        parse_date("05/01/2024"),
+        Err(CoreError::InvalidDate(_))
+    ));
+}
+
+#[test]
+fn exports_csv_and_json() {
+    let dir = TempDir::new().unwrap();
+    let mut tricky = record("C:/a,b/\"app\".exe", UpxStatus::Failed, 0);
+    tricky.reason = Some("第一行\n第二行".to_string());
+    let records = [record("C:/app.exe", UpxStatus::Success, 0), tricky];
+
+    let csv_path = dir.path().join("history.CSV");
+    let format = ExportFormat::from_path(&csv_path);
+    assert_eq!(format, ExportFormat::Csv);
+    export_history(&records, &csv_path, format).unwrap();
+    let csv = fs::read_to_string(&csv_path).unwrap();
+    let csv = csv.strip_prefix('\u{feff}').expect("CSV 以 BOM 开头");
+    assert!(csv.starts_with("timestamp,mode,status,input_file,"));
+    assert!(csv.contains(",compress,success,C:/app.exe,"));
+    assert!(csv.contains("\"C:/a,b/\"\"app\"\".exe\""));
+    assert!(csv.contains("\"第一行\n第二行\""));
+
+    let json_path = dir.path().join("history.json");
+    assert_eq!(ExportFormat::from_path(&json_path), ExportFormat::Json);
+    export_history(&records, &json_path, ExportFormat::Json).unwrap();
+    let parsed: Vec<HistoryRecord> =
+        serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[1].status, UpxStatus::Failed);
+}
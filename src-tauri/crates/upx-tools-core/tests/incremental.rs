@@ -0,0 +1,198 @@
+//! 增量处理：未变化的文件被跳过，输入、输出、参数或版本变化时重新处理
+
+mod common;
+
+use common::{options, write_file};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use upx_tools_core::batch;
+use upx_tools_core::fake::{FakeFailure, FakePacker};
+use upx_tools_core::incremental::IncrementalIndex;
+use upx_tools_core::packer::Packer;
+use upx_tools_core::tune::{AutoTuner, Strategy};
+use upx_tools_core::{UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+const VERSION: &str = "4.2.4";
+
+struct Fixture {
+    dir: TempDir,
+    root: PathBuf,
+    index_dir: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("release");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        let index_dir = dir.path().join("index");
+        Self {
+            dir,
+            root,
+            index_dir,
+        }
+    }
+
+    fn open(&self) -> IncrementalIndex {
+        IncrementalIndex::open(&self.index_dir, [self.root.clone()])
+    }
+
+    /// 处理全部任务并保存索引，返回被跳过的文件数
+    fn run(&self, packer: &FakePacker, jobs: Vec<UpxOptions>) -> usize {
+        let mut index = self.open();
+        let (pending, unchanged) = index.partition(jobs, &[], VERSION);
+        batch::run_batch(packer, &pending, 2, |job, result| {
+            index.record(job, &[], VERSION, &result)
+        });
+        index.save().unwrap();
+        unchanged.len()
+    }
+}
+
+#[test]
+fn unchanged_files_are_skipped() {
+    let fx = Fixture::new();
+    let app = write_file(&fx.root, "app.exe", COMPRESSIBLE);
+    let core = write_file(&fx.root.join("sub"), "core.dll", &[0u8; 8192]);
+    let jobs = vec![options(&app, &app), options(&core, &core)];
+    let packer = FakePacker::new();
+
+    assert_eq!(fx.run(&packer, jobs.clone()), 0);
+    assert_eq!(fx.run(&packer, jobs.clone()), 2);
+    assert_eq!(packer.calls().len(), 2);
+
+    let skipped = fx.open().unchanged(&jobs[0], &[], VERSION).unwrap();
+    assert_eq!(skipped.status, UpxStatus::Skipped);
+    assert!(skipped.message.contains("未变化"));
+    assert_eq!(skipped.output_size, fs::metadata(&app).unwrap().len());
+}
+
+#[test]
+fn changed_inputs_and_outputs_are_reprocessed() {
+    let fx = Fixture::new();
+    let app = write_file(&fx.root, "app.exe", COMPRESSIBLE);
+    let core = write_file(&fx.root, "core.dll", COMPRESSIBLE);
+    let out = fx.dir.path().join("dist");
+    fs::create_dir_all(&out).unwrap();
+    let jobs = vec![
+        options(&app, &out.join("app.exe")),
+        options(&core, &out.join("core.dll")),
+    ];
+    let packer = FakePacker::new();
+    fx.run(&packer, jobs.clone());
+
+    write_file(&fx.root, "app.exe", b"rebuilt");
+    fs::write(out.join("core.dll"), b"patched").unwrap();
+
+    let index = fx.open();
+    assert!(index.unchanged(&jobs[0], &[], VERSION).is_none());
+    assert!(index.unchanged(&jobs[1], &[], VERSION).is_none());
+}
+
+#[test]
+fn option_or_version_changes_are_reprocessed() {
+    let fx = Fixture::new();
+    let app = write_file(&fx.root, "app.exe", COMPRESSIBLE);
+    let out = fx.dir.path().join("app_packed.exe");
+    let job = options(&app, &out);
+    fx.run(&FakePacker::new(), vec![job.clone()]);
+
+    let index = fx.open();
+    assert!(index.unchanged(&job, &[], VERSION).is_some());
+    assert!(index.unchanged(&job, &[], "5.0.0").is_none());
+    let lzma = UpxOptions {
+        lzma: true,
+        ..job.clone()
+    };
+    assert!(index.unchanged(&lzma, &[], VERSION).is_none());
+    let elsewhere = options(&app, &fx.dir.path().join("other.exe"));
+    assert!(index.unchanged(&elsewhere, &[], VERSION).is_none());
+}
+
+#[test]
+fn candidate_or_budget_changes_are_reprocessed() {
+    let fx = Fixture::new();
+    let app = write_file(&fx.root, "app.exe", COMPRESSIBLE);
+    let job = UpxOptions {
+        compression_level: "auto".to_string(),
+        ..options(&app, &fx.dir.path().join("app_packed.exe"))
+    };
+    let strategies = Strategy::parse_list(&["-9", "--best"]).unwrap();
+    let packer = AutoTuner::new(FakePacker::new(), strategies.clone());
+
+    let mut index = fx.open();
+    batch::run_batch(&packer, std::slice::from_ref(&job), 1, |job, result| {
+        index.record(job, packer.strategies(), VERSION, &result)
+    });
+    index.save().unwrap();
+
+    let index = fx.open();
+    assert!(index.unchanged(&job, &strategies, VERSION).is_some());
+    assert!(index.unchanged(&job, &strategies[..1], VERSION).is_none());
+    let budget = UpxOptions {
+        budget: Some("60%".parse().unwrap()),
+        ..job.clone()
+    };
+    assert!(index.unchanged(&budget, &strategies, VERSION).is_none());
+}
+
+#[test]
+fn failures_are_not_recorded() {
+    let fx = Fixture::new();
+    let setup = write_file(&fx.root, "setup.exe", COMPRESSIBLE);
+    let packer = FakePacker::new().fail_on("setup", FakeFailure::CantPack);
+    let job = options(&setup, &setup);
+
+    fx.run(&packer, vec![job.clone()]);
+    assert_eq!(fx.run(&packer, vec![job]), 0);
+}
+
+#[test]
+fn saves_from_separate_runs_are_merged() {
+    let fx = Fixture::new();
+    let app = write_file(&fx.root, "app.exe", COMPRESSIBLE);
+    let core = write_file(&fx.root, "core.dll", COMPRESSIBLE);
+    let packer = FakePacker::new();
+
+    // 图形界面逐个文件处理时，每个文件各自打开、记录并保存索引
+    let mut first = fx.open();
+    let mut second = fx.open();
+    for (index, file) in [(&mut first, &app), (&mut second, &core)] {
+        let job = options(file, file);
+        batch::run_batch(&packer, std::slice::from_ref(&job), 1, |job, result| {
+            index.record(job, &[], VERSION, &result)
+        });
+    }
+    first.save().unwrap();
+    second.save().unwrap();
+
+    // 原子替换不留下临时文件，锁文件与索引文件并排
+    let mut names: Vec<String> = fs::read_dir(&fx.index_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].ends_with(".json") && names[1].ends_with(".lock"));
+
+    let index = fx.open();
+    assert!(index
+        .unchanged(&options(&app, &app), &[], VERSION)
+        .is_some());
+    assert!(index
+        .unchanged(&options(&core, &core), &[], VERSION)
+        .is_some());
+}
+
+#[test]
+fn files_outside_roots_are_not_tracked() {
+    let fx = Fixture::new();
+    let stray = write_file(fx.dir.path(), "stray.exe", COMPRESSIBLE);
+    let job = options(&stray, &stray);
+
+    fx.run(&FakePacker::new(), vec![job.clone()]);
+    assert!(fx.open().unchanged(&job, &[], VERSION).is_none());
+    assert!(!fx.index_dir.exists());
+}
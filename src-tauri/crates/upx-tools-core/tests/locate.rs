@@ -0,0 +1,110 @@
+//! UPX 查找顺序与版本检查
+
+use upx_tools_core::locate::{UpxVersion, MIN_UPX_VERSION};
+
+#[test]
+fn parses_release_and_git_versions() {
+    assert_eq!(
+        UpxVersion::parse("upx 4.2.4"),
+        Some(UpxVersion::new(4, 2, 4))
+    );
+    assert_eq!(
+        UpxVersion::parse("upx 3.96-git-d7ba31cab8ce"),
+        Some(UpxVersion::new(3, 96, 0))
+    );
+    assert_eq!(UpxVersion::parse("upx 5"), Some(UpxVersion::new(5, 0, 0)));
+    assert_eq!(UpxVersion::parse("Ultimate Packer"), None);
+}
+
+#[test]
+fn versions_compare_numerically() {
+    assert!(UpxVersion::new(3, 100, 0) > UpxVersion::new(3, 96, 0));
+    assert!(UpxVersion::new(3, 94, 9) < MIN_UPX_VERSION);
+    assert_eq!(MIN_UPX_VERSION.to_string(), "3.95.0");
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+    use upx_tools_core::locate::{find_upx, UpxSource};
+    use upx_tools_core::CoreError;
+
+    /// 写一个只会回答 `--version` 的脚本
+    fn fake_upx(dir: &Path, name: &str, version_line: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\necho \"{}\"\n", version_line)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn configured_path_is_preferred() {
+        let dir = TempDir::new().unwrap();
+        let upx = fake_upx(dir.path(), "my-upx", "upx 4.2.4");
+
+        let location = find_upx(Some(&upx), None).unwrap();
+
+        assert_eq!(location.path, upx);
+        assert_eq!(location.source, UpxSource::Config);
+        assert_eq!(location.version, "upx 4.2.4");
+        assert!(location.reason.contains("配置文件指定的路径"));
+    }
+
+    #[test]
+    fn outdated_configured_upx_is_skipped_with_reason() {
+        let dir = TempDir::new().unwrap();
+        let upx = fake_upx(dir.path(), "old-upx", "upx 3.91");
+
+        // 其他位置是否有可用的 UPX 取决于运行环境，两种结果都要说明跳过的原因
+        match find_upx(Some(&upx), None) {
+            Ok(location) => {
+                assert_ne!(location.source, UpxSource::Config);
+                assert!(location.reason.contains("低于最低要求 3.95.0"));
+            }
+            Err(CoreError::UpxUnusable(reason)) => {
+                assert!(reason.contains("版本 3.91.0 低于最低要求 3.95.0"));
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn outdated_upx_on_path_does_not_hide_later_entries() {
+        let old = TempDir::new().unwrap();
+        let new = TempDir::new().unwrap();
+        fake_upx(old.path(), "upx", "upx 3.91");
+        let upx = fake_upx(new.path(), "upx", "upx 4.2.4");
+
+        let mut dirs = vec![old.path().to_path_buf(), new.path().to_path_buf()];
+        dirs.extend(std::env::split_paths(
+            &std::env::var_os("PATH").unwrap_or_default(),
+        ));
+        std::env::set_var("PATH", std::env::join_paths(dirs).unwrap());
+
+        let location = find_upx(None, None).unwrap();
+
+        assert_eq!(location.path, upx);
+        assert_eq!(location.source, UpxSource::Path);
+        assert!(
+            location.reason.contains("版本 3.91.0 低于最低要求 3.95.0"),
+            "{}",
+            location.reason
+        );
+    }
+
+    #[test]
+    fn missing_configured_path_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("nope");
+
+        let message = match find_upx(Some(&missing), None) {
+            Ok(location) => location.reason,
+            Err(e) => e.to_string(),
+        };
+
+        assert!(message.contains("配置的路径不存在"), "{}", message);
+    }
+}
@@ -0,0 +1,209 @@
+//! 加壳清单：单文件与文件夹清单的内容、按清单校验与写入失败
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use upx_tools_core::args::build_compress_args;
+use upx_tools_core::batch;
+use upx_tools_core::fake::FakePacker;
+use upx_tools_core::hash::sha256_hex;
+use upx_tools_core::manifest::{
+    verify_manifest, DriftStatus, Manifest, FOLDER_MANIFEST_NAME, SIDECAR_SUFFIX,
+};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::{CoreError, ManifestMode, UpxMode, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(input: &Path, output: &Path, manifest: ManifestMode) -> UpxOptions {
+    UpxOptions {
+        compression_level: "best".to_string(),
+        lzma: true,
+        manifest,
+        ..common::options(input, output)
+    }
+}
+
+/// 源文件在 `src`，两个加壳结果写入 `dist` 并生成文件夹清单
+fn pack_folder(dir: &TempDir) -> PathBuf {
+    let src = dir.path().join("src");
+    let dist = dir.path().join("dist");
+    fs::create_dir_all(&src).unwrap();
+    let jobs: Vec<_> = ["a.exe", "b.dll"]
+        .iter()
+        .map(|name| {
+            let input = write_file(&src, name, COMPRESSIBLE);
+            options(&input, &dist.join(name), ManifestMode::Folder)
+        })
+        .collect();
+    let summary = batch::run_batch(&FakePacker::new(), &jobs, 2, |_, _| {});
+    assert_eq!(summary.success, 2);
+    dist
+}
+
+#[test]
+fn sidecar_records_hashes_version_and_args() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("app_packed.exe");
+    let job = options(&input, &output, ManifestMode::Sidecar);
+
+    let packer = FakePacker::new().with_version("upx 4.2.4");
+    let result = run_upx(&packer, &job).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+
+    let sidecar = dir.path().join(format!("app_packed.exe{}", SIDECAR_SUFFIX));
+    let manifest = Manifest::load(&sidecar).unwrap();
+    let entry = &manifest.entries[0];
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(entry.file, "app_packed.exe");
+    assert_eq!(entry.original_sha256, sha256_hex(COMPRESSIBLE));
+    assert_eq!(entry.packed_sha256, sha256_hex(&fs::read(&output).unwrap()));
+    assert_eq!(entry.upx_version, "upx 4.2.4");
+    assert_eq!(
+        entry.args,
+        build_compress_args(&job, false).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn folder_manifest_collects_and_replaces_entries() {
+    let dir = TempDir::new().unwrap();
+    let dist = pack_folder(&dir);
+    pack_folder(&dir);
+
+    let manifest = Manifest::load(&dist.join(FOLDER_MANIFEST_NAME)).unwrap();
+    let files: Vec<_> = manifest.entries.iter().map(|e| e.file.as_str()).collect();
+    assert_eq!(files, ["a.exe", "b.dll"]);
+}
+
+#[test]
+fn nothing_is_written_when_off_or_not_packing() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new();
+
+    run_upx(&packer, &options(&input, &input, ManifestMode::Off)).unwrap();
+    let unpack = UpxOptions {
+        mode: UpxMode::Decompress,
+        ..options(&input, &input, ManifestMode::Folder)
+    };
+    assert_eq!(
+        run_upx(&packer, &unpack).unwrap().status,
+        UpxStatus::Success
+    );
+
+    let names: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["app.exe"]);
+}
+
+#[test]
+fn verify_reports_drift() {
+    let dir = TempDir::new().unwrap();
+    let dist = pack_folder(&dir);
+
+    let report = verify_manifest(&dist, false).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries.len(), 2);
+
+    fs::write(dist.join("a.exe"), b"patched").unwrap();
+    fs::remove_file(dist.join("b.dll")).unwrap();
+    write_file(&dist, "new.exe", COMPRESSIBLE);
+
+    let report = verify_manifest(&dist, false).unwrap();
+    let statuses: Vec<_> = report
+        .entries
+        .iter()
+        .map(|e| (Path::new(&e.file).file_name().unwrap().to_owned(), e.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("a.exe".into(), DriftStatus::Modified),
+            ("b.dll".into(), DriftStatus::Missing),
+            ("new.exe".into(), DriftStatus::Untracked),
+        ]
+    );
+    assert_eq!(report.drift().count(), 3);
+}
+
+#[test]
+fn sources_next_to_outputs_are_not_untracked() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let job = options(
+        &input,
+        &dir.path().join("app_packed.exe"),
+        ManifestMode::Sidecar,
+    );
+    run_upx(&FakePacker::new(), &job).unwrap();
+
+    let report = verify_manifest(dir.path(), false).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn paths_are_compared_after_normalizing() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("sub")).unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let job = options(
+        &input,
+        &dir.path().join("app_packed.exe"),
+        ManifestMode::Sidecar,
+    );
+    run_upx(&FakePacker::new(), &job).unwrap();
+
+    // 扫描得到的路径带 `..`，与清单中记录的源文件路径写法不同
+    let folder = dir.path().join("sub").join("..");
+    let report = verify_manifest(&folder, false).unwrap();
+    assert!(report.is_clean(), "{:?}", report.entries);
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn folder_without_manifest_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    write_file(dir.path(), "app.exe", COMPRESSIBLE);
+
+    assert!(matches!(
+        verify_manifest(dir.path(), true),
+        Err(CoreError::ManifestNotFound(_))
+    ));
+}
+
+#[test]
+fn manifest_write_failure_is_a_warning() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    // 清单路径被同名文件夹占用
+    fs::create_dir(dir.path().join(FOLDER_MANIFEST_NAME)).unwrap();
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(&input, &input, ManifestMode::Folder),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Warning);
+    assert!(result.reason.unwrap().contains("清单"));
+}
+
+#[test]
+fn modes_parse_from_names() {
+    assert_eq!("sidecar".parse(), Ok(ManifestMode::Sidecar));
+    assert_eq!("folder".parse(), Ok(ManifestMode::Folder));
+    assert!("both".parse::<ManifestMode>().is_err());
+    assert_eq!(
+        ManifestMode::Sidecar.manifest_path(Path::new("out/app.exe")),
+        Some(PathBuf::from("out/app.exe.upx.json"))
+    );
+    assert_eq!(ManifestMode::Off.manifest_path(Path::new("app.exe")), None);
+}
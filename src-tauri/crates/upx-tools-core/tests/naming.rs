@@ -0,0 +1,202 @@
+//! 命名模板、输出冲突处理与批量中的重复输出
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::args::build_compress_args;
+use upx_tools_core::batch::{self, duplicate_outputs};
+use upx_tools_core::fake::{FakePacker, FAKE_MAGIC};
+use upx_tools_core::naming::{available_path, render_output_name, DEFAULT_NAME_TEMPLATE};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::{ConflictPolicy, CoreError, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(input: &Path, output: &Path, on_conflict: ConflictPolicy) -> UpxOptions {
+    UpxOptions {
+        on_conflict,
+        ..common::options(input, output)
+    }
+}
+
+#[test]
+fn templates_render_relative_to_input_dir() {
+    let input = Path::new("build").join("app.exe");
+
+    assert_eq!(
+        render_output_name("{stem}.packed.{ext}", &input).unwrap(),
+        Path::new("build").join("app.packed.exe")
+    );
+    assert_eq!(
+        render_output_name("{dir}/packed/{name}", &input).unwrap(),
+        Path::new("build").join("packed").join("app.exe")
+    );
+    assert_eq!(
+        render_output_name(DEFAULT_NAME_TEMPLATE, &input).unwrap(),
+        Path::new("build").join("app_packed.exe")
+    );
+}
+
+#[test]
+fn missing_extension_drops_trailing_dot() {
+    assert_eq!(
+        render_output_name(DEFAULT_NAME_TEMPLATE, Path::new("tool")).unwrap(),
+        Path::new("tool_packed")
+    );
+}
+
+#[test]
+fn dir_of_bare_file_name_stays_relative() {
+    assert_eq!(
+        render_output_name("{dir}/packed/{name}", Path::new("app.exe")).unwrap(),
+        Path::new("./packed/app.exe")
+    );
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+    for template in ["{stem", "{size}.exe", "{dir}/"] {
+        let err = render_output_name(template, Path::new("app.exe")).unwrap_err();
+        assert!(
+            matches!(err, CoreError::InvalidNameTemplate(_)),
+            "{}",
+            template
+        );
+    }
+}
+
+#[test]
+fn available_path_counts_up() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("app.exe");
+    assert_eq!(available_path(&target), target);
+
+    write_file(dir.path(), "app.exe", b"");
+    write_file(dir.path(), "app_1.exe", b"");
+    assert_eq!(available_path(&target), dir.path().join("app_2.exe"));
+}
+
+#[test]
+fn force_overwrite_only_with_overwrite_policy() {
+    let input = Path::new("app.exe");
+    let output = Path::new("out.exe");
+    let has_force_overwrite = |policy| {
+        build_compress_args(&options(input, output, policy), false)
+            .any(|a| a == "--force-overwrite")
+    };
+
+    assert!(has_force_overwrite(ConflictPolicy::Overwrite));
+    assert!(!has_force_overwrite(ConflictPolicy::Skip));
+    assert!(!has_force_overwrite(ConflictPolicy::Rename));
+    assert!(!has_force_overwrite(ConflictPolicy::Fail));
+    // 原地处理不需要
+    assert!(
+        !build_compress_args(&options(input, input, ConflictPolicy::Overwrite), true)
+            .any(|a| a == "--force-overwrite")
+    );
+}
+
+#[test]
+fn existing_output_is_skipped_by_default() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = write_file(dir.path(), "app_packed.exe", b"old");
+    let packer = FakePacker::new();
+
+    let result = run_upx(
+        &packer,
+        &options(&input, &output, ConflictPolicy::default()),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert!(result.reason.unwrap().contains("已存在"));
+    assert_eq!(fs::read(&output).unwrap(), b"old");
+    assert!(packer.calls().is_empty());
+}
+
+#[test]
+fn existing_output_can_be_renamed() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = write_file(dir.path(), "app_packed.exe", b"old");
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(&input, &output, ConflictPolicy::Rename),
+    )
+    .unwrap();
+
+    let renamed = dir.path().join("app_packed_1.exe");
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(result.output_file, renamed.to_string_lossy());
+    assert_eq!(fs::read(&output).unwrap(), b"old");
+    assert!(fs::read(&renamed).unwrap().starts_with(FAKE_MAGIC));
+}
+
+#[test]
+fn existing_output_can_fail_or_be_overwritten() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = write_file(dir.path(), "app_packed.exe", b"old");
+
+    let err = run_upx(
+        &FakePacker::new(),
+        &options(&input, &output, ConflictPolicy::Fail),
+    )
+    .unwrap_err();
+    assert!(matches!(err, CoreError::OutputExists(_)));
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(&input, &output, ConflictPolicy::Overwrite),
+    )
+    .unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+    assert!(fs::read(&output).unwrap().starts_with(FAKE_MAGIC));
+}
+
+#[test]
+fn duplicate_outputs_in_a_batch_fail_without_running() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.exe", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.exe", &[0u8; 8192]);
+    let c = write_file(dir.path(), "c.exe", COMPRESSIBLE);
+    let shared = dir.path().join("out.exe");
+    let jobs = vec![
+        options(&a, &shared, ConflictPolicy::Overwrite),
+        options(
+            &b,
+            &dir.path().join("b_packed.exe"),
+            ConflictPolicy::Overwrite,
+        ),
+        // 写法不同但指向同一个文件
+        options(
+            &c,
+            &dir.path().join(".").join("out.exe"),
+            ConflictPolicy::Overwrite,
+        ),
+    ];
+
+    assert_eq!(
+        duplicate_outputs(&jobs).into_iter().collect::<Vec<_>>(),
+        [(2, 0)]
+    );
+
+    let packer = FakePacker::new();
+    let mut failed = Vec::new();
+    let summary = batch::run_batch(&packer, &jobs, 2, |job, result| {
+        if result.status == UpxStatus::Failed {
+            failed.push((job.input_file.clone(), result.message));
+        }
+    });
+
+    assert_eq!(summary.success, 2);
+    assert_eq!(summary.failed, 1);
+    assert_eq!(failed[0].0, c.to_string_lossy());
+    assert!(failed[0].1.contains(&*a.to_string_lossy()));
+    assert_eq!(packer.calls().len(), 2);
+}
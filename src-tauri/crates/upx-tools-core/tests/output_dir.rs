@@ -0,0 +1,112 @@
+//! 输出目录模式：相对路径映射、创建目录与复制其他文件
+
+mod common;
+
+use common::write_file;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use upx_tools_core::fake::{FakePacker, FAKE_MAGIC};
+use upx_tools_core::output_dir::{copy_unsupported_files, mirrored_output};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::{scan, ConflictPolicy, CoreError, UpxOptions, UpxStatus};
+
+/// `release/app.exe`、`release/bin/lib.dll`、`release/readme.txt`、`release/bin/data.json`
+fn release_tree(dir: &TempDir) -> PathBuf {
+    let release = dir.path().join("release");
+    let bin = release.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    write_file(&release, "app.exe", &[0u8; 4096]);
+    write_file(&bin, "lib.dll", &[0u8; 4096]);
+    write_file(&release, "readme.txt", b"readme");
+    write_file(&bin, "data.json", b"{}");
+    release
+}
+
+#[test]
+fn keeps_source_folder_name_and_relative_path() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let out = dir.path().join("out");
+
+    let output = mirrored_output(&release, &release.join("bin/lib.dll"), &out).unwrap();
+    assert_eq!(output, out.join("release/bin/lib.dll"));
+}
+
+#[test]
+fn single_file_goes_to_output_root() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let app = release.join("app.exe");
+    let out = dir.path().join("out");
+
+    assert_eq!(
+        mirrored_output(&app, &app, &out).unwrap(),
+        out.join("app.exe")
+    );
+}
+
+#[test]
+fn output_root_equal_to_source_parent_is_rejected() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+
+    let err = mirrored_output(&release, &release.join("app.exe"), dir.path()).unwrap_err();
+    assert!(matches!(err, CoreError::OutputDirIsSource(_)));
+}
+
+#[test]
+fn run_upx_creates_missing_directories_and_keeps_original() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let input = release.join("bin/lib.dll");
+    let output = mirrored_output(&release, &input, &dir.path().join("out")).unwrap();
+
+    let options = UpxOptions {
+        on_conflict: ConflictPolicy::default(),
+        ..common::options(&input, &output)
+    };
+    let result = run_upx(&FakePacker::new(), &options).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert!(fs::read(&output).unwrap().starts_with(FAKE_MAGIC));
+    assert_eq!(fs::read(&input).unwrap(), [0u8; 4096]);
+}
+
+#[test]
+fn copies_only_unsupported_files() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let out = dir.path().join("out");
+
+    let copied = copy_unsupported_files(&release, true, &out).unwrap();
+
+    assert_eq!(copied, 2);
+    assert_eq!(fs::read(out.join("release/readme.txt")).unwrap(), b"readme");
+    assert_eq!(fs::read(out.join("release/bin/data.json")).unwrap(), b"{}");
+    assert!(!out.join("release/app.exe").exists());
+}
+
+#[test]
+fn copy_skips_output_root_inside_source() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let out = release.join("out");
+
+    assert_eq!(copy_unsupported_files(&release, true, &out).unwrap(), 2);
+    // 第二次运行不会把上次复制的文件再复制一遍
+    assert_eq!(copy_unsupported_files(&release, true, &out).unwrap(), 2);
+    assert!(!out.join("release/out").exists());
+}
+
+#[test]
+fn copy_respects_include_subfolders() {
+    let dir = TempDir::new().unwrap();
+    let release = release_tree(&dir);
+    let out = dir.path().join("out");
+
+    assert_eq!(copy_unsupported_files(&release, false, &out).unwrap(), 1);
+    assert!(!out.join("release/bin").exists());
+    assert!(scan::is_supported(Path::new("APP.EXE")));
+}
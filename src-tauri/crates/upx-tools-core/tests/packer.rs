@@ -0,0 +1,389 @@
+//! 基于 FakePacker 的集成测试：状态映射、备份、批量汇总
+
+mod common;
+
+use common::write_file;
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::batch::{self, BatchSummary};
+use upx_tools_core::fake::{fake_pack, FakeFailure, FakePacker, FAKE_MAGIC};
+use upx_tools_core::hash::sha256_hex;
+use upx_tools_core::output::{classify_upx_exit, match_skip_reason, UPX_EXIT_ERROR};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::{ConflictPolicy, CoreError, Packer, UpxMode, UpxOptions, UpxStatus};
+
+/// 可被假后端压缩的数据（大量重复字节）
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(mode: UpxMode, input: &Path, output: &Path) -> UpxOptions {
+    UpxOptions {
+        mode,
+        on_conflict: ConflictPolicy::default(),
+        ..common::options(input, output)
+    }
+}
+
+#[test]
+fn compress_in_place_reports_sizes() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Compress, &file, &file),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(result.exit_code, Some(0));
+    assert_eq!(result.original_size, COMPRESSIBLE.len() as u64);
+    assert_eq!(result.output_size, fs::metadata(&file).unwrap().len());
+    assert!(result.output_size < result.original_size);
+    assert!(fs::read(&file).unwrap().starts_with(FAKE_MAGIC));
+    assert!(result.message.starts_with("操作成功!"));
+}
+
+#[test]
+fn compress_to_separate_output_keeps_original() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("app_packed.exe");
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Compress, &input, &output),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(fs::read(&input).unwrap(), COMPRESSIBLE);
+    assert!(fs::read(&output).unwrap().starts_with(FAKE_MAGIC));
+}
+
+#[test]
+fn results_carry_input_and_output_hashes() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new();
+
+    // 原地加壳：输入哈希是处理前的内容
+    let packed = run_upx(&packer, &options(UpxMode::Compress, &file, &file)).unwrap();
+    assert_eq!(
+        packed.input_sha256.as_deref(),
+        Some(&*sha256_hex(COMPRESSIBLE))
+    );
+    assert_eq!(
+        packed.output_sha256.as_deref(),
+        Some(&*sha256_hex(&fs::read(&file).unwrap()))
+    );
+
+    // 跳过与检测模式没有输出哈希
+    for mode in [UpxMode::Compress, UpxMode::Test] {
+        let result = run_upx(&packer, &options(mode, &file, &file)).unwrap();
+        assert_eq!(result.input_sha256, packed.output_sha256, "{:?}", mode);
+        assert!(result.output_sha256.is_none(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn decompress_restores_original() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", &fake_pack(COMPRESSIBLE));
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Decompress, &file, &file),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(fs::read(&file).unwrap(), COMPRESSIBLE);
+}
+
+#[test]
+fn already_packed_and_not_compressible_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let packed = write_file(dir.path(), "packed.exe", &fake_pack(COMPRESSIBLE));
+    let random: Vec<u8> = (0..=255u8).collect();
+    let incompressible = write_file(dir.path(), "random.dll", &random);
+    let packer = FakePacker::new();
+
+    let result = run_upx(&packer, &options(UpxMode::Compress, &packed, &packed)).unwrap();
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert_eq!(result.exit_code, Some(2));
+    assert_eq!(result.reason.as_deref(), Some("文件已经被 UPX 加壳过了"));
+
+    let result = run_upx(
+        &packer,
+        &options(UpxMode::Compress, &incompressible, &incompressible),
+    )
+    .unwrap();
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert_eq!(result.output_size, result.original_size);
+    assert_eq!(fs::read(&incompressible).unwrap(), random);
+}
+
+#[test]
+fn decompressing_unpacked_file_is_skipped() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "plain.exe", COMPRESSIBLE);
+
+    let result = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Decompress, &file, &file),
+    )
+    .unwrap();
+
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert_eq!(result.reason.as_deref(), Some("文件未被 UPX 加壳"));
+}
+
+#[test]
+fn not_packed_is_only_skipped_when_decompressing() {
+    let not_packed = "upx: app.exe: NotPackedException: not packed by UPX";
+    assert_eq!(
+        match_skip_reason(UpxMode::Decompress, "", not_packed),
+        Some("文件未被 UPX 加壳")
+    );
+    for mode in [UpxMode::Compress, UpxMode::Test, UpxMode::List] {
+        assert_eq!(match_skip_reason(mode, "", not_packed), None, "{:?}", mode);
+    }
+
+    // 其他错误中出现 "not packed" 字样不算跳过
+    let (status, _) = classify_upx_exit(
+        UpxMode::Decompress,
+        Some(UPX_EXIT_ERROR),
+        "",
+        "upx: app.exe: CantUnpackException: section not packed as expected",
+    );
+    assert_eq!(status, UpxStatus::Failed);
+}
+
+#[test]
+fn every_known_exception_maps_to_status_and_hint() {
+    let cases = [
+        (
+            FakeFailure::AlreadyPacked,
+            UpxStatus::Skipped,
+            "已经被 UPX 加壳",
+        ),
+        // 加壳时出现“未加壳”不是可以忽略的情况，按 UPX 的警告退出码处理
+        (
+            FakeFailure::NotPacked,
+            UpxStatus::Warning,
+            "NotPackedException",
+        ),
+        (
+            FakeFailure::NotCompressible,
+            UpxStatus::Skipped,
+            "无法进一步压缩",
+        ),
+        (
+            FakeFailure::CantPack,
+            UpxStatus::Failed,
+            "[错误] 无法压缩此文件",
+        ),
+        (
+            FakeFailure::Overlay,
+            UpxStatus::Failed,
+            "附加数据（Overlay）",
+        ),
+        (FakeFailure::Io, UpxStatus::Failed, "[错误] 文件访问失败"),
+        (
+            FakeFailure::Error("SomethingElse: boom".to_string()),
+            UpxStatus::Failed,
+            "SomethingElse: boom",
+        ),
+        (
+            FakeFailure::Warning("odd stub".to_string()),
+            UpxStatus::Warning,
+            "odd stub",
+        ),
+    ];
+
+    for (failure, expected_status, expected_text) in cases {
+        let dir = TempDir::new().unwrap();
+        let file = write_file(dir.path(), "target.exe", COMPRESSIBLE);
+        let packer = FakePacker::new().fail_on("target", failure.clone());
+
+        let result = run_upx(&packer, &options(UpxMode::Compress, &file, &file)).unwrap();
+
+        assert_eq!(result.status, expected_status, "{:?}", failure);
+        assert!(
+            result.message.contains(expected_text),
+            "{:?}: {}",
+            failure,
+            result.message
+        );
+        assert_eq!(fs::read(&file).unwrap(), COMPRESSIBLE, "{:?}", failure);
+    }
+}
+
+#[test]
+fn spawn_failure_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new().fail_on("app", FakeFailure::Spawn);
+
+    let err = run_upx(&packer, &options(UpxMode::Compress, &file, &file)).unwrap_err();
+
+    assert!(matches!(err, CoreError::Spawn(_)));
+}
+
+#[test]
+fn unavailable_packer_fails_before_running() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new().unavailable();
+
+    let err = run_upx(&packer, &options(UpxMode::Compress, &file, &file)).unwrap_err();
+
+    assert!(matches!(err, CoreError::UpxNotExecutable(_)));
+    assert!(packer.calls().is_empty());
+}
+
+#[test]
+fn missing_input_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("missing.exe");
+
+    let err = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Compress, &file, &file),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, CoreError::InputNotFound(_)));
+    assert_eq!(
+        err.to_string(),
+        format!("输入文件不存在: {}", file.display())
+    );
+}
+
+#[test]
+fn readonly_file_is_rejected_when_overwriting() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let mut permissions = fs::metadata(&file).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&file, permissions).unwrap();
+
+    let err = run_upx(
+        &FakePacker::new(),
+        &options(UpxMode::Compress, &file, &file),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, CoreError::ReadOnly));
+}
+
+#[test]
+fn backup_is_written_before_packing() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let mut opts = options(UpxMode::Compress, &file, &file);
+    opts.backup = true;
+
+    let result = run_upx(&FakePacker::new(), &opts).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    let backup = dir.path().join("app.exe.bak");
+    assert_eq!(fs::read(backup).unwrap(), COMPRESSIBLE);
+}
+
+#[test]
+fn inspect_modes_never_back_up_or_modify() {
+    let dir = TempDir::new().unwrap();
+    let packed = fake_pack(COMPRESSIBLE);
+    let file = write_file(dir.path(), "app.exe", &packed);
+    let packer = FakePacker::new();
+
+    for mode in [UpxMode::Test, UpxMode::List] {
+        let mut opts = options(mode, &file, &file);
+        opts.backup = true;
+
+        let result = run_upx(&packer, &opts).unwrap();
+
+        assert_eq!(result.status, UpxStatus::Success, "{:?}", mode);
+        assert_eq!(result.output_size, result.original_size);
+    }
+
+    assert_eq!(fs::read(&file).unwrap(), packed);
+    assert!(!dir.path().join("app.exe.bak").exists());
+    let list = packer
+        .calls()
+        .into_iter()
+        .map(|c| c.mode)
+        .collect::<Vec<_>>();
+    assert_eq!(list, vec![UpxMode::Test, UpxMode::List]);
+}
+
+#[test]
+fn list_keeps_table_output() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", &fake_pack(COMPRESSIBLE));
+
+    let result = run_upx(&FakePacker::new(), &options(UpxMode::List, &file, &file)).unwrap();
+
+    assert!(result.message.contains("File size"));
+    assert!(result.message.contains("fake/test"));
+}
+
+#[test]
+fn batch_counts_each_status_and_reports_every_file() {
+    let dir = TempDir::new().unwrap();
+    let ok = write_file(dir.path(), "ok.exe", COMPRESSIBLE);
+    let packed = write_file(dir.path(), "packed.exe", &fake_pack(COMPRESSIBLE));
+    let warn = write_file(dir.path(), "warn.exe", &[0u8; 8192]);
+    let bad = write_file(dir.path(), "bad.exe", &[0u8; 16384]);
+    let missing = dir.path().join("missing.exe");
+    let packer = FakePacker::new()
+        .fail_on("warn", FakeFailure::Warning("odd stub".to_string()))
+        .fail_on("bad", FakeFailure::CantPack);
+
+    let jobs: Vec<UpxOptions> = [&ok, &packed, &warn, &bad, &missing]
+        .into_iter()
+        .map(|p| options(UpxMode::Compress, p, p))
+        .collect();
+
+    let mut seen = Vec::new();
+    let summary = batch::run_batch(&packer, &jobs, 3, |job, result| {
+        assert_eq!(job.input_file, result.input_file);
+        seen.push(result.input_file);
+    });
+
+    assert_eq!(
+        summary,
+        BatchSummary {
+            success: 1,
+            skipped: 1,
+            warning: 1,
+            failed: 2,
+        }
+    );
+    assert_eq!(summary.total(), jobs.len());
+    assert_eq!(seen.len(), jobs.len());
+    assert!(summary.has_failures(false));
+}
+
+#[test]
+fn skipped_and_warnings_do_not_fail_a_batch_by_default() {
+    let summary = BatchSummary {
+        success: 3,
+        skipped: 2,
+        warning: 1,
+        failed: 0,
+    };
+
+    assert!(!summary.has_failures(false));
+    assert!(summary.has_failures(true));
+}
+
+#[test]
+fn fake_version_is_reported() {
+    let packer = FakePacker::new().with_version("upx 3.96");
+
+    assert_eq!(packer.version().unwrap(), "upx 3.96");
+}
@@ -0,0 +1,221 @@
+//! 预览模式：动作、命令行、备份路径与警告，且不修改任何文件
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use upx_tools_core::batch;
+use upx_tools_core::fake::{fake_pack, FakePacker};
+use upx_tools_core::incremental::IncrementalIndex;
+use upx_tools_core::plan::{format_command_line, plan_batch, plan_job, PlanAction};
+use upx_tools_core::{ConflictPolicy, UpxMode, UpxOptions};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+const VERSION: &str = "4.2.4";
+
+fn options(mode: UpxMode, input: &Path, output: &Path) -> UpxOptions {
+    UpxOptions {
+        mode,
+        on_conflict: ConflictPolicy::default(),
+        ..common::options(input, output)
+    }
+}
+
+/// 目录中所有文件及其内容，用于确认预览没有改动任何东西
+fn snapshot(dir: &TempDir) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut files: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let data = fs::read(&path).unwrap();
+            (path, data)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn pack_entry_lists_command_output_and_backup() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("out").join("app_packed.exe");
+    let job = UpxOptions {
+        backup: true,
+        ..options(UpxMode::Compress, &input, &output)
+    };
+
+    let entry = plan_job(&FakePacker::new(), &job);
+
+    assert_eq!(entry.action, PlanAction::Pack);
+    assert_eq!(entry.output_file, output.to_string_lossy());
+    assert_eq!(
+        entry.backup_file.as_deref(),
+        Some(&*format!("{}.bak", input.display()))
+    );
+    assert_eq!(entry.command[0], "upx");
+    assert!(entry.command.iter().any(|a| a == "-o"));
+    assert!(entry.warnings.iter().any(|w| w.contains("将创建目录")));
+}
+
+#[test]
+fn already_packed_and_not_packed_files_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let plain = write_file(dir.path(), "plain.exe", COMPRESSIBLE);
+    let packed = write_file(dir.path(), "packed.exe", &fake_pack(COMPRESSIBLE));
+    let packer = FakePacker::new();
+
+    let entry = plan_job(&packer, &options(UpxMode::Compress, &packed, &packed));
+    assert_eq!(entry.action, PlanAction::Skip);
+    assert!(entry.reason.unwrap().contains("已被 UPX 加壳"));
+
+    let entry = plan_job(&packer, &options(UpxMode::Decompress, &plain, &plain));
+    assert_eq!(entry.action, PlanAction::Skip);
+    assert!(entry.reason.unwrap().contains("未被 UPX 加壳"));
+
+    let entry = plan_job(&packer, &options(UpxMode::Decompress, &packed, &packed));
+    assert_eq!(entry.action, PlanAction::Unpack);
+    assert!(entry.backup_file.is_none());
+    assert!(packer.calls().is_empty());
+}
+
+#[test]
+fn existing_output_follows_conflict_policy() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = write_file(dir.path(), "app_packed.exe", b"old");
+    let plan = |on_conflict| {
+        plan_job(
+            &FakePacker::new(),
+            &UpxOptions {
+                on_conflict,
+                ..options(UpxMode::Compress, &input, &output)
+            },
+        )
+    };
+
+    assert_eq!(plan(ConflictPolicy::Skip).action, PlanAction::Skip);
+    assert_eq!(plan(ConflictPolicy::Fail).action, PlanAction::Fail);
+
+    let renamed = plan(ConflictPolicy::Rename);
+    assert_eq!(renamed.action, PlanAction::Pack);
+    assert_eq!(
+        renamed.output_file,
+        dir.path().join("app_packed_1.exe").to_string_lossy()
+    );
+    assert!(!renamed.command.iter().any(|a| a == "--force-overwrite"));
+
+    let overwritten = plan(ConflictPolicy::Overwrite);
+    assert!(overwritten.command.iter().any(|a| a == "--force-overwrite"));
+    assert_eq!(overwritten.warnings.len(), 1);
+}
+
+#[test]
+fn missing_input_and_duplicate_outputs_fail() {
+    let dir = TempDir::new().unwrap();
+    let a = write_file(dir.path(), "a.exe", COMPRESSIBLE);
+    let b = write_file(dir.path(), "b.exe", COMPRESSIBLE);
+    let shared = dir.path().join("out.exe");
+    let jobs = [
+        options(UpxMode::Compress, &a, &shared),
+        options(UpxMode::Compress, &b, &shared),
+        options(UpxMode::Compress, &dir.path().join("gone.exe"), &a),
+    ];
+
+    let entries = plan_batch(&FakePacker::new(), &jobs, None, VERSION);
+
+    let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [PlanAction::Pack, PlanAction::Fail, PlanAction::Fail]
+    );
+    assert!(entries[1]
+        .reason
+        .as_deref()
+        .unwrap()
+        .contains(&*a.to_string_lossy()));
+    assert!(entries[2].command.is_empty());
+}
+
+#[test]
+fn unchanged_and_identical_files_follow_the_batch() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("release");
+    let index_dir = dir.path().join("index");
+    fs::create_dir_all(&root).unwrap();
+    let done = write_file(&root, "done.exe", COMPRESSIBLE);
+    let a = write_file(&root, "a.dll", &[1u8; 4096]);
+    let b = write_file(&root, "b.dll", &[1u8; 4096]);
+    let packer = FakePacker::new();
+
+    // 上次已处理过 done.exe
+    let mut index = IncrementalIndex::open(&index_dir, [root.clone()]);
+    let job = options(UpxMode::Compress, &done, &done);
+    batch::run_batch(&packer, std::slice::from_ref(&job), 1, |job, result| {
+        index.record(job, &[], VERSION, &result)
+    });
+    index.save().unwrap();
+
+    let index = IncrementalIndex::open(&index_dir, [root.clone()]);
+    let jobs = [
+        job,
+        options(UpxMode::Compress, &a, &a),
+        options(UpxMode::Compress, &b, &b),
+    ];
+    let entries = plan_batch(&packer, &jobs, Some(&index), VERSION);
+
+    let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [PlanAction::Skip, PlanAction::Pack, PlanAction::Pack]
+    );
+    assert_eq!(entries[0].reason.as_deref(), Some("自上次处理后未变化"));
+    assert_eq!(entries[1].duplicate_of, None);
+    assert_eq!(
+        entries[2].duplicate_of.as_deref(),
+        Some(&*a.to_string_lossy())
+    );
+
+    // 不提供索引时照常处理
+    let entries = plan_batch(&packer, &jobs, None, VERSION);
+    assert_eq!(entries[0].action, PlanAction::Skip);
+    assert_eq!(entries[0].reason.as_deref(), Some("文件已被 UPX 加壳"));
+}
+
+#[test]
+fn planning_does_not_touch_files() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    write_file(dir.path(), "app_packed.exe", b"old");
+    let before = snapshot(&dir);
+    let packer = FakePacker::new();
+
+    for on_conflict in [ConflictPolicy::Overwrite, ConflictPolicy::Rename] {
+        let job = UpxOptions {
+            backup: true,
+            on_conflict,
+            ..options(
+                UpxMode::Compress,
+                &input,
+                &dir.path().join("app_packed.exe"),
+            )
+        };
+        plan_batch(
+            &packer,
+            &[job, options(UpxMode::Compress, &input, &input)],
+            None,
+            VERSION,
+        );
+    }
+
+    assert_eq!(snapshot(&dir), before);
+    assert!(packer.calls().is_empty());
+}
+
+#[test]
+fn command_line_quotes_spaces() {
+    let command = ["upx", "-9", "my app.exe", ""].map(String::from);
+    assert_eq!(format_command_line(&command), r#"upx -9 "my app.exe" """#);
+}
@@ -0,0 +1,258 @@
+//! 多版本 UPX 的选用与脱壳重试
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::fake::{fake_pack, FakeFailure, FakePacker};
+use upx_tools_core::manifest::{Manifest, SIDECAR_SUFFIX};
+use upx_tools_core::output::is_version_mismatch;
+use upx_tools_core::plan::plan_job;
+use upx_tools_core::process::run_upx;
+use upx_tools_core::registry::{unregister_upx, RegisteredUpx, UpxRegistry};
+use upx_tools_core::tune::{AutoTuner, Strategy};
+use upx_tools_core::{ConflictPolicy, CoreError, ManifestMode, UpxMode, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(mode: UpxMode, file: &Path, upx_version: Option<&str>) -> UpxOptions {
+    UpxOptions {
+        mode,
+        upx_version: upx_version.map(str::to_string),
+        on_conflict: ConflictPolicy::default(),
+        ..common::options(file, file)
+    }
+}
+
+#[test]
+fn job_uses_the_named_version() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("app.exe");
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new()).with_version(
+        "3.96.0",
+        FakePacker::new().fail_on("app", FakeFailure::CantPack),
+    );
+
+    fs::write(&file, COMPRESSIBLE).unwrap();
+    let result = run_upx(
+        &registry,
+        &options(UpxMode::Compress, &file, Some("3.96.0")),
+    )
+    .unwrap();
+    assert_eq!(result.status, UpxStatus::Failed);
+
+    fs::write(&file, COMPRESSIBLE).unwrap();
+    let result = run_upx(&registry, &options(UpxMode::Compress, &file, None)).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+
+    fs::write(&file, COMPRESSIBLE).unwrap();
+    let result = run_upx(&registry, &options(UpxMode::Compress, &file, Some("4.2.4"))).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+}
+
+#[test]
+fn manifest_records_the_selected_version() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new())
+        .with_version("3.96.0", FakePacker::new().with_version("upx 3.96"));
+    let job = UpxOptions {
+        manifest: ManifestMode::Sidecar,
+        ..options(UpxMode::Compress, &file, Some("3.96.0"))
+    };
+
+    let result = run_upx(&registry, &job).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    let sidecar = dir.path().join(format!("app.exe{}", SIDECAR_SUFFIX));
+    let manifest = Manifest::load(&sidecar).unwrap();
+    assert_eq!(manifest.entries[0].upx_version, "upx 3.96");
+}
+
+#[test]
+fn optional_flags_follow_the_selected_version() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    // 默认的旧版本不支持 --lzma，任务指定的版本支持
+    let registry = UpxRegistry::new(
+        "3.91.0",
+        FakePacker::new()
+            .with_version("upx 3.91")
+            .with_help("--best --force"),
+    )
+    .with_version("4.2.4", FakePacker::new());
+    let job = |upx_version| UpxOptions {
+        lzma: true,
+        ..options(UpxMode::Compress, &file, upx_version)
+    };
+
+    let lzma = "--lzma".to_string();
+    assert!(plan_job(&registry, &job(Some("4.2.4")))
+        .command
+        .contains(&lzma));
+    assert!(!plan_job(&registry, &job(None)).command.contains(&lzma));
+
+    let tuner = AutoTuner::new(registry, Strategy::parse_list(&["--best --lzma"]).unwrap());
+    let auto = UpxOptions {
+        compression_level: "auto".to_string(),
+        ..job(Some("4.2.4"))
+    };
+    let result = run_upx(&tuner, &auto).unwrap();
+    assert_eq!(result.status, UpxStatus::Success, "{}", result.message);
+    assert!(result.strategies[0].chosen);
+}
+
+#[test]
+fn unknown_version_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new());
+
+    let err = run_upx(
+        &registry,
+        &options(UpxMode::Compress, &file, Some("3.91.0")),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, CoreError::UpxVersionNotRegistered(ref v) if v == "3.91.0"));
+    assert_eq!(fs::read(&file).unwrap(), COMPRESSIBLE);
+}
+
+#[test]
+fn decompress_retries_with_other_versions() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "old.exe", &fake_pack(COMPRESSIBLE));
+    let registry = UpxRegistry::new(
+        "4.2.4",
+        FakePacker::new().fail_on(
+            "old",
+            FakeFailure::Error("CantUnpackException: need an older version of UPX".into()),
+        ),
+    )
+    .with_version(
+        "3.96.0",
+        FakePacker::new().fail_on(
+            "old",
+            FakeFailure::Error("need a newer version of UPX".into()),
+        ),
+    )
+    .with_version("3.91.0", FakePacker::new());
+
+    let result = run_upx(&registry, &options(UpxMode::Decompress, &file, None)).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert_eq!(fs::read(&file).unwrap(), COMPRESSIBLE);
+    assert!(
+        result.message.contains("已改用 UPX 3.91.0"),
+        "{}",
+        result.message
+    );
+}
+
+#[test]
+fn unrelated_decompress_failures_are_not_retried() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", &fake_pack(COMPRESSIBLE));
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new().fail_on("app", FakeFailure::Io))
+        .with_version("3.96.0", FakePacker::new());
+
+    let result = run_upx(&registry, &options(UpxMode::Decompress, &file, None)).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Failed);
+    assert_eq!(fs::read(&file).unwrap(), fake_pack(COMPRESSIBLE));
+}
+
+#[test]
+fn not_packed_files_are_not_retried() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "app.exe", &fake_pack(COMPRESSIBLE));
+    // 换版本就能脱壳，但“未加壳”不代表版本不匹配，不应重试
+    let registry = UpxRegistry::new(
+        "4.2.4",
+        FakePacker::new().fail_on("app", FakeFailure::NotPacked),
+    )
+    .with_version("3.96.0", FakePacker::new());
+
+    let result = run_upx(&registry, &options(UpxMode::Decompress, &file, None)).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert!(!result.message.contains("已改用"), "{}", result.message);
+    assert_eq!(fs::read(&file).unwrap(), fake_pack(COMPRESSIBLE));
+    assert!(!is_version_mismatch(
+        "",
+        "upx: app.exe: NotPackedException: not packed by UPX"
+    ));
+    assert!(is_version_mismatch(
+        "",
+        "upx: app.exe: CantUnpackException: need a newer version of UPX"
+    ));
+}
+
+#[test]
+fn really_unpacked_file_is_still_skipped() {
+    let dir = TempDir::new().unwrap();
+    let file = write_file(dir.path(), "plain.exe", COMPRESSIBLE);
+    let registry =
+        UpxRegistry::new("4.2.4", FakePacker::new()).with_version("3.96.0", FakePacker::new());
+
+    let result = run_upx(&registry, &options(UpxMode::Decompress, &file, None)).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Skipped);
+    assert_eq!(result.reason.as_deref(), Some("文件未被 UPX 加壳"));
+}
+
+#[test]
+fn labels_list_default_first_and_skip_duplicates() {
+    let registry = UpxRegistry::new("4.2.4", FakePacker::new())
+        .with_version("3.96.0", FakePacker::new())
+        .with_version("4.2.4", FakePacker::new())
+        .with_version("3.96.0", FakePacker::new());
+
+    assert_eq!(registry.labels(), vec!["4.2.4", "3.96.0"]);
+}
+
+#[test]
+fn unregister_by_version_or_path() {
+    let mut binaries = vec![
+        RegisteredUpx {
+            path: "/opt/upx-3.96/upx".to_string(),
+            version: "3.96.0".to_string(),
+        },
+        RegisteredUpx {
+            path: "/opt/upx-3.95/upx".to_string(),
+            version: "3.95.0".to_string(),
+        },
+    ];
+
+    assert!(unregister_upx(&mut binaries, "3.96.0"));
+    assert!(unregister_upx(&mut binaries, "/opt/upx-3.95/upx"));
+    assert!(!unregister_upx(&mut binaries, "3.96.0"));
+    assert!(binaries.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn register_labels_binary_with_parsed_version() {
+    use std::os::unix::fs::PermissionsExt;
+    use upx_tools_core::registry::register_upx;
+
+    let dir = TempDir::new().unwrap();
+    let mut binaries = Vec::new();
+    for (name, line) in [
+        ("a", "upx 3.96-git-d7ba31cab8ce"),
+        ("b", "upx 4.2.4"),
+        ("c", "upx 3.96"),
+    ] {
+        let path = dir.path().join(name);
+        fs::write(&path, format!("#!/bin/sh\necho \"{}\"\n", line)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        register_upx(&mut binaries, &path).unwrap();
+    }
+
+    // 同版本后注册的替换先注册的，按版本从新到旧排列
+    let versions: Vec<&str> = binaries.iter().map(|b| b.version.as_str()).collect();
+    assert_eq!(versions, vec!["4.2.4", "3.96.0"]);
+    assert!(binaries[1].path.ends_with("c"));
+}
@@ -0,0 +1,158 @@
+//! 批量处理报告：明细行、合计与各种格式
+
+mod common;
+
+use common::write_file;
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::batch;
+use upx_tools_core::fake::{FakeFailure, FakePacker};
+use upx_tools_core::hash::sha256_hex;
+use upx_tools_core::report::{
+    method, render_csv, render_html, write_report, BatchReport, ReportEntry, ReportFormat,
+};
+use upx_tools_core::{UpxMode, UpxOptions, UpxResult, UpxStatus};
+
+fn options(input: &Path, output: &Path) -> UpxOptions {
+    UpxOptions {
+        lzma: true,
+        ..common::options(input, output)
+    }
+}
+
+/// 两个可压缩的文件、一个处理失败的文件和一个不存在的文件
+fn run_sample_batch(dir: &TempDir) -> BatchReport {
+    let mut jobs = Vec::new();
+    for (name, size) in [("small.exe", 4096), ("large.exe", 65536), ("bad.exe", 8192)] {
+        let input = write_file(dir.path(), name, &vec![0u8; size]);
+        jobs.push(options(
+            &input,
+            &dir.path().join(format!("packed_{}", name)),
+        ));
+    }
+
+    let packer = FakePacker::new().fail_on("bad.exe", FakeFailure::CantPack);
+    let missing = UpxResult::failed("gone.exe", "gone.exe", "路径不存在: gone.exe".to_string());
+    let mut entries = vec![ReportEntry::from_result(None, &missing)];
+    batch::run_batch(&packer, &jobs, 1, |job, result| {
+        entries.push(ReportEntry::from_result(Some(job), &result));
+    });
+
+    BatchReport::new(UpxMode::Compress, entries)
+}
+
+#[test]
+fn entries_carry_sizes_method_and_hashes() {
+    let dir = TempDir::new().unwrap();
+    let report = run_sample_batch(&dir);
+
+    let large = report
+        .entries
+        .iter()
+        .find(|e| e.input_file.ends_with("large.exe"))
+        .unwrap();
+    assert_eq!(large.status, UpxStatus::Success);
+    assert_eq!(large.method, "-9 --lzma");
+    assert_eq!(large.saved_bytes, large.original_size - large.output_size);
+    assert_eq!(
+        large.input_sha256.as_deref(),
+        Some(&*sha256_hex(&[0u8; 65536]))
+    );
+    let packed = fs::read(&large.output_file).unwrap();
+    assert_eq!(large.output_sha256.as_deref(), Some(&*sha256_hex(&packed)));
+
+    let missing = &report.entries[0];
+    assert_eq!(missing.method, "");
+    assert!(missing.input_sha256.is_none());
+    assert!(missing.output_sha256.is_none());
+}
+
+#[test]
+fn totals_and_top_savings() {
+    let dir = TempDir::new().unwrap();
+    let report = run_sample_batch(&dir);
+
+    assert_eq!(report.summary.success, 2);
+    assert_eq!(report.summary.failed, 2);
+    assert_eq!(report.original_size, 4096 + 65536);
+    assert_eq!(
+        report.saved_bytes,
+        report.original_size - report.output_size
+    );
+
+    let top = report.top_savings(1);
+    assert_eq!(top.len(), 1);
+    assert!(top[0].input_file.ends_with("large.exe"));
+    assert_eq!(report.problems().count(), 2);
+}
+
+#[test]
+fn method_matches_compress_args() {
+    let input = Path::new("a.exe");
+    let mut job = options(input, input);
+    job.compression_level = "best".to_string();
+    job.lzma = false;
+    assert_eq!(method(&job), "--best");
+
+    job.ultra_brute = true;
+    job.force = true;
+    assert_eq!(method(&job), "--ultra-brute --force");
+
+    job.mode = UpxMode::Decompress;
+    assert_eq!(method(&job), "-d");
+}
+
+#[test]
+fn csv_has_one_record_per_file() {
+    let dir = TempDir::new().unwrap();
+    let report = run_sample_batch(&dir);
+    let csv = render_csv(&report);
+
+    assert!(csv.starts_with("\u{feff}status,input_file,"));
+    // 失败原因可能跨行（已加引号），按行首状态计数
+    let rows = csv
+        .lines()
+        .filter(|line| line.starts_with("success,") || line.starts_with("failed,"))
+        .count();
+    assert_eq!(rows, report.entries.len());
+}
+
+#[test]
+fn html_is_self_contained_and_escaped() {
+    let dir = TempDir::new().unwrap();
+    let mut report = run_sample_batch(&dir);
+    report.entries[0].reason = Some("<script>alert(1)</script>".to_string());
+    let html = render_html(&report);
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("节省最多的文件"));
+    assert!(html.contains("失败与警告"));
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(!html.contains("<script>"));
+    assert!(!html.contains("http://") && !html.contains("https://"));
+}
+
+#[test]
+fn format_follows_extension() {
+    let dir = TempDir::new().unwrap();
+    let report = run_sample_batch(&dir);
+
+    for (name, format) in [
+        ("r.json", ReportFormat::Json),
+        ("r.CSV", ReportFormat::Csv),
+        ("r.htm", ReportFormat::Html),
+        ("r.html", ReportFormat::Html),
+        ("r", ReportFormat::Json),
+    ] {
+        let path = dir.path().join(name);
+        assert_eq!(ReportFormat::from_path(&path), format, "{}", name);
+        write_report(&report, &path, format).unwrap();
+    }
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("r.json")).unwrap()).unwrap();
+    assert_eq!(json["entries"].as_array().unwrap().len(), 4);
+    assert_eq!(json["summary"]["success"], 2);
+}
@@ -0,0 +1,196 @@
+//! 累计统计：按格式、压缩方式与失败原因汇总，按日期与文件夹筛选
+
+mod common;
+
+use chrono::{Duration, Local};
+use std::path::Path;
+use upx_tools_core::history::HistoryRecord;
+use upx_tools_core::output::{failure_kind, parse_upx_error};
+use upx_tools_core::stats::{compute_statistics, StatisticsQuery};
+use upx_tools_core::tune::StrategyTrial;
+use upx_tools_core::{UpxOptions, UpxResult, UpxStatus};
+
+fn options(input: &str, level: &str, lzma: bool) -> UpxOptions {
+    UpxOptions {
+        compression_level: level.to_string(),
+        lzma,
+        ..common::options(Path::new(input), Path::new(input))
+    }
+}
+
+fn packed(input: &str, level: &str, lzma: bool, sizes: (u64, u64), days_ago: i64) -> HistoryRecord {
+    let options = options(input, level, lzma);
+    let (original_size, output_size) = sizes;
+    let result = UpxResult {
+        status: UpxStatus::Success,
+        original_size,
+        output_size,
+        ratio: (output_size * 100 / original_size) as i32,
+        duration_ms: 100,
+        ..UpxResult::failed(input, input, String::new())
+    };
+    let mut record = HistoryRecord::new(&options, None, &result);
+    record.timestamp -= Duration::days(days_ago);
+    record.reason = None;
+    record
+}
+
+fn failed(input: &str, reason: String) -> HistoryRecord {
+    let options = options(input, "9", false);
+    HistoryRecord::new(&options, None, &UpxResult::failed(input, input, reason))
+}
+
+fn sample() -> Vec<HistoryRecord> {
+    vec![
+        packed("C:\\Build\\app.exe", "9", true, (4_194_304, 1_048_576), 0),
+        packed(
+            "C:\\Build\\sub\\core.dll",
+            "9",
+            true,
+            (2_097_152, 1_048_576),
+            1,
+        ),
+        packed("C:/Build/tool.EXE", "best", false, (1_048_576, 524_288), 5),
+        packed(
+            "C:/Buildings/other.exe",
+            "9",
+            true,
+            (1_048_576, 1_048_576),
+            0,
+        ),
+        failed(
+            "C:/Build/setup.exe",
+            parse_upx_error("", "upx: setup.exe: CantPackException: can't pack new-exe"),
+        ),
+        failed(
+            "C:/Build/gone.exe",
+            "路径不存在: C:/Build/gone.exe".to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn totals_by_format_and_method() {
+    let stats = compute_statistics(&sample(), &StatisticsQuery::default());
+
+    assert_eq!(stats.records, 6);
+    assert_eq!(stats.summary.success, 4);
+    assert_eq!(stats.summary.failed, 2);
+    assert_eq!(stats.original_size, 8_388_608);
+    assert_eq!(stats.saved_bytes, 4_718_592);
+
+    let formats: Vec<_> = stats
+        .by_format
+        .iter()
+        .map(|f| (f.format.as_str(), f.files, f.saved_bytes))
+        .collect();
+    assert_eq!(formats, [("exe", 3, 3_670_016), ("dll", 1, 1_048_576)]);
+
+    let methods: Vec<_> = stats
+        .by_method
+        .iter()
+        .map(|m| (m.method.as_str(), m.files, m.average_ratio))
+        .collect();
+    assert_eq!(
+        methods,
+        [("--best", 1, 50.0), ("-9 --lzma", 3, 175.0 / 3.0)]
+    );
+
+    // 8 MB 用时 400 毫秒
+    assert_eq!(stats.ms_per_mb, Some(50.0));
+}
+
+#[test]
+fn tuned_jobs_are_grouped_by_the_chosen_strategy() {
+    let input = "C:/Build/auto.exe";
+    let trial = |strategy: &str, chosen| StrategyTrial {
+        strategy: strategy.to_string(),
+        output_size: 0,
+        duration_ms: 0,
+        passed: true,
+        chosen,
+        reason: None,
+    };
+    let result = UpxResult {
+        status: UpxStatus::Success,
+        original_size: 1000,
+        output_size: 400,
+        ratio: 40,
+        strategies: vec![trial("--best", false), trial("-9 --lzma", true)],
+        ..UpxResult::failed(input, input, String::new())
+    };
+    let records = vec![
+        HistoryRecord::new(&options(input, "auto", false), None, &result),
+        packed("C:/Build/app.exe", "9", true, (1000, 600), 0),
+    ];
+
+    let stats = compute_statistics(&records, &StatisticsQuery::default());
+
+    let methods: Vec<_> = stats
+        .by_method
+        .iter()
+        .map(|m| (m.method.as_str(), m.files, m.average_ratio))
+        .collect();
+    assert_eq!(methods, [("-9 --lzma", 2, 50.0)]);
+}
+
+#[test]
+fn failures_are_grouped_by_exception() {
+    let stats = compute_statistics(&sample(), &StatisticsQuery::default());
+
+    assert!((stats.failure_rate - 2.0 / 6.0).abs() < 1e-9);
+    let kinds: Vec<_> = stats
+        .failures
+        .iter()
+        .map(|f| (f.kind.as_str(), f.count))
+        .collect();
+    assert!(kinds.contains(&("CantPackException", 1)));
+    assert!(kinds.contains(&("Other", 1)));
+}
+
+#[test]
+fn folder_filter_matches_whole_directories() {
+    let query = StatisticsQuery {
+        folder: Some("c:/build/".to_string()),
+        ..Default::default()
+    };
+    let stats = compute_statistics(&sample(), &query);
+
+    // 不含 C:/Buildings，含子文件夹与反斜杠路径
+    assert_eq!(stats.records, 5);
+    assert_eq!(stats.summary.success, 3);
+}
+
+#[test]
+fn date_filter_is_inclusive() {
+    let today = Local::now().date_naive();
+    let query = StatisticsQuery {
+        since: Some(today - Duration::days(1)),
+        until: Some(today),
+        ..Default::default()
+    };
+    let stats = compute_statistics(&sample(), &query);
+
+    assert_eq!(stats.records, 5);
+    assert!(stats.by_method.iter().all(|m| m.method != "--best"));
+}
+
+#[test]
+fn empty_history_has_no_throughput() {
+    let stats = compute_statistics(&[], &StatisticsQuery::default());
+    assert_eq!(stats.records, 0);
+    assert_eq!(stats.failure_rate, 0.0);
+    assert!(stats.ms_per_mb.is_none());
+}
+
+#[test]
+fn failure_kind_recognises_messages_and_raw_output() {
+    let message = parse_upx_error("", "upx: a.exe: OverlayException: file has overlay");
+    assert_eq!(failure_kind(&message), "OverlayException");
+    assert_eq!(failure_kind("upx: IOException: can't open"), "IOException");
+    assert_eq!(
+        failure_kind(&parse_upx_error("", "upx: something odd")),
+        "UpxError"
+    );
+    assert_eq!(failure_kind("UPX 进程启动失败"), "Other");
+}
@@ -0,0 +1,230 @@
+//! 自动选择压缩方式：保留通过测试且最小的结果，并记录每个策略的大小
+
+mod common;
+
+use common::write_file;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use upx_tools_core::args::build_compress_args;
+use upx_tools_core::fake::{fake_pack, FakeFailure, FakePacker};
+use upx_tools_core::manifest::{Manifest, SIDECAR_SUFFIX};
+use upx_tools_core::process::run_upx;
+use upx_tools_core::report::{render_csv, BatchReport, ReportEntry};
+use upx_tools_core::tune::{AutoTuner, Strategy, DEFAULT_STRATEGIES};
+use upx_tools_core::{CoreError, ManifestMode, NrvMethod, UpxMode, UpxOptions, UpxStatus};
+
+const COMPRESSIBLE: &[u8] = &[0u8; 4096];
+
+fn options(input: &Path, output: &Path, level: &str) -> UpxOptions {
+    UpxOptions {
+        compression_level: level.to_string(),
+        ..common::options(input, output)
+    }
+}
+
+fn tuner(packer: FakePacker, strategies: &[&str]) -> AutoTuner<FakePacker> {
+    AutoTuner::new(packer, Strategy::parse_list(strategies).unwrap())
+}
+
+#[test]
+fn strategies_parse_and_display() {
+    let nrv: Strategy = "--best --nrv2e".parse().unwrap();
+    assert_eq!(nrv.compression_level, "best");
+    assert_eq!(nrv.nrv, Some(NrvMethod::Nrv2e));
+    assert_eq!(nrv.to_string(), "--best --nrv2e");
+
+    for (text, display) in [
+        ("-9", "-9"),
+        ("--lzma", "-9 --lzma"),
+        ("--lzma  --best", "--best --lzma"),
+        ("--brute", "--brute"),
+        ("--ultra-brute", "--ultra-brute"),
+    ] {
+        let strategy: Strategy = text.parse().unwrap();
+        assert_eq!(strategy.to_string(), display, "{}", text);
+    }
+
+    for text in ["", "-10", "--best -9", "--lzma --nrv2b", "--fast", "9"] {
+        let err = text.parse::<Strategy>().unwrap_err();
+        assert!(matches!(err, CoreError::InvalidStrategy(_)), "{}", text);
+    }
+
+    let defaults = Strategy::parse_list::<&str>(&[]).unwrap();
+    assert_eq!(defaults.len(), DEFAULT_STRATEGIES.len());
+    assert_eq!(defaults, Strategy::defaults());
+}
+
+#[test]
+fn auto_keeps_the_smallest_output_that_passes_test() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("app_packed.exe");
+    // LZMA 最小但输出损坏，应选用其次的 --nrv2e
+    let packer = FakePacker::new()
+        .pad_method("-9", 200)
+        .pad_method("--best", 100)
+        .pad_method("--best --nrv2e", 60)
+        .corrupt_method("--best --lzma");
+    let tuner = tuner(packer, &["-9", "--best", "--best --lzma", "--best --nrv2e"]);
+
+    let result = run_upx(&tuner, &options(&input, &output, "auto")).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    let base = fake_pack(COMPRESSIBLE).len() as u64;
+    let sizes: Vec<_> = result
+        .strategies
+        .iter()
+        .map(|t| (t.strategy.as_str(), t.passed, t.output_size))
+        .collect();
+    assert_eq!(
+        sizes,
+        [
+            ("-9", true, base + 200),
+            ("--best", true, base + 100),
+            ("--best --lzma", false, 0),
+            ("--best --nrv2e", true, base + 60),
+        ]
+    );
+    let chosen: Vec<_> = result.strategies.iter().filter(|t| t.chosen).collect();
+    assert_eq!(chosen.len(), 1);
+    assert_eq!(chosen[0].strategy, "--best --nrv2e");
+    assert!(result.strategies[2]
+        .reason
+        .as_deref()
+        .unwrap()
+        .contains("upx -t"));
+
+    assert_eq!(result.output_size, base + 60);
+    assert_eq!(fs::read(&input).unwrap(), COMPRESSIBLE);
+    assert!(result.message.contains("[自动] 选用 --best --nrv2e"));
+    // 每个策略加壳一次、测试一次，都在临时副本上进行
+    let calls = tuner.inner().calls();
+    assert_eq!(calls.len(), 8);
+    assert!(calls.iter().all(|c| c.input_file != result.input_file));
+}
+
+#[test]
+fn equal_sizes_prefer_the_earlier_strategy() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let tuner = tuner(FakePacker::new(), &["--best --lzma", "-9"]);
+
+    let result = run_upx(&tuner, &options(&input, &input, "auto")).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert!(result.strategies[0].chosen);
+    assert_eq!(fs::read(&input).unwrap(), fake_pack(COMPRESSIBLE));
+}
+
+#[test]
+fn fixed_levels_pass_straight_through() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let tuner = tuner(FakePacker::new(), &["-9", "--best"]);
+
+    let result = run_upx(&tuner, &options(&input, &input, "9")).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Success);
+    assert!(result.strategies.is_empty());
+    assert_eq!(tuner.inner().calls().len(), 1);
+}
+
+#[test]
+fn upx_errors_are_reported_as_usual() {
+    let dir = TempDir::new().unwrap();
+    let protected = write_file(dir.path(), "protected.exe", COMPRESSIBLE);
+    let packed = write_file(dir.path(), "packed.exe", &fake_pack(COMPRESSIBLE));
+    let packer = FakePacker::new().fail_on("protected", FakeFailure::CantPack);
+    let tuner = tuner(packer, &["-9", "--best"]);
+
+    let failed = run_upx(&tuner, &options(&protected, &protected, "auto")).unwrap();
+    assert_eq!(failed.status, UpxStatus::Failed);
+    assert!(failed.message.contains("无法压缩此文件"));
+    assert!(failed.strategies.iter().all(|t| !t.passed));
+
+    let skipped = run_upx(&tuner, &options(&packed, &packed, "auto")).unwrap();
+    assert_eq!(skipped.status, UpxStatus::Skipped);
+}
+
+#[test]
+fn nothing_passing_test_fails_the_job() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new()
+        .corrupt_method("-9")
+        .corrupt_method("--best");
+    let tuner = tuner(packer, &["-9", "--best"]);
+
+    let result = run_upx(&tuner, &options(&input, &input, "auto")).unwrap();
+
+    assert_eq!(result.status, UpxStatus::Failed);
+    assert!(result.message.contains("所有候选压缩方式都未通过"));
+    assert_eq!(fs::read(&input).unwrap(), COMPRESSIBLE);
+}
+
+#[test]
+fn report_lists_each_strategy() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let packer = FakePacker::new().pad_method("-9", 20);
+    let tuner = tuner(packer, &["-9", "--best"]);
+    let job = options(&input, &input, "auto");
+
+    let result = run_upx(&tuner, &job).unwrap();
+    let entry = ReportEntry::from_result(Some(&job), &result);
+    assert_eq!(entry.method, "auto: --best");
+
+    let base = fake_pack(COMPRESSIBLE).len();
+    let csv = render_csv(&BatchReport::new(UpxMode::Compress, vec![entry]));
+    assert!(csv.contains(&format!("-9={};--best={}*", base + 20, base)));
+}
+
+#[test]
+fn invalid_candidates_only_matter_to_jobs_that_try_them() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let bad = ["-9", "--fast"];
+
+    let fixed = options(&input, &input, "9");
+    assert!(Strategy::parse_for(&fixed, &bad).unwrap().is_empty());
+    let decompress = UpxOptions {
+        mode: UpxMode::Decompress,
+        ..options(&input, &input, "auto")
+    };
+    assert!(Strategy::parse_for(&decompress, &bad).unwrap().is_empty());
+
+    let err = Strategy::parse_for(&options(&input, &input, "auto"), &bad).unwrap_err();
+    assert!(matches!(err, CoreError::InvalidStrategy(_)));
+    assert_eq!(
+        Strategy::parse_for(&options(&input, &input, "auto"), &["--best"])
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn manifest_records_the_chosen_strategy() {
+    let dir = TempDir::new().unwrap();
+    let input = write_file(dir.path(), "app.exe", COMPRESSIBLE);
+    let output = dir.path().join("app_packed.exe");
+    let packer = FakePacker::new().pad_method("-9", 20);
+    let tuner = tuner(packer, &["-9", "--best --lzma"]);
+    let job = UpxOptions {
+        manifest: ManifestMode::Sidecar,
+        ..options(&input, &output, "auto")
+    };
+
+    let result = run_upx(&tuner, &job).unwrap();
+    assert_eq!(result.status, UpxStatus::Success);
+
+    let sidecar = dir.path().join(format!("app_packed.exe{}", SIDECAR_SUFFIX));
+    let entry = &Manifest::load(&sidecar).unwrap().entries[0];
+    let chosen: Strategy = "--best --lzma".parse().unwrap();
+    assert_eq!(
+        entry.args,
+        build_compress_args(&chosen.apply(&job), false).collect::<Vec<_>>()
+    );
+    assert!(!entry.args.iter().any(|arg| arg.contains("auto")));
+}
@@ -0,0 +1,345 @@
+//! UPX 发行包的选择、校验与解压
+
+use std::fs;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::thread;
+use tempfile::TempDir;
+use upx_tools_core::hash::sha256_hex;
+use upx_tools_core::locate::UpxVersion;
+use upx_tools_core::upx_update::{
+    download_asset, fetch_latest_release, install_upx_archive, latest_release_url,
+    platform_asset_suffix, select_asset, UpxRelease, UpxReleaseAsset,
+};
+use upx_tools_core::CoreError;
+
+const UPX_FILE_NAME: &str = if cfg!(windows) { "upx.exe" } else { "upx" };
+const BINARY: &[u8] = b"#!/bin/sh\necho upx 5.0.2\n";
+const VERSION: UpxVersion = UpxVersion::new(5, 0, 2);
+
+/// 与 GitHub `releases/latest` 返回结构一致的样例（省略无关字段）
+const RELEASE_JSON: &str = r#"{
+    "tag_name": "v5.0.2",
+    "html_url": "https://github.com/upx/upx/releases/tag/v5.0.2",
+    "name": "UPX 5.0.2",
+    "assets": [
+        {"name": "upx-5.0.2-amd64_linux.tar.xz", "browser_download_url": "http://127.0.0.1/a", "size": 1, "digest": "sha256:00"},
+        {"name": "upx-5.0.2-arm64_linux.tar.xz", "browser_download_url": "http://127.0.0.1/b", "size": 1, "digest": null},
+        {"name": "upx-5.0.2-win32.zip", "browser_download_url": "http://127.0.0.1/c", "size": 1},
+        {"name": "upx-5.0.2-win64.zip", "browser_download_url": "http://127.0.0.1/d", "size": 1, "digest": "sha256:00"},
+        {"name": "upx-5.0.2-src.tar.xz", "browser_download_url": "http://127.0.0.1/e", "size": 1}
+    ]
+}"#;
+
+fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn tar_xz_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    let tar_data = builder.into_inner().unwrap();
+
+    let mut compressed = Vec::new();
+    lzma_rs::xz_compress(&mut Cursor::new(tar_data), &mut compressed).unwrap();
+    compressed
+}
+
+fn asset_for(name: &str, archive: &[u8]) -> UpxReleaseAsset {
+    UpxReleaseAsset {
+        name: name.to_string(),
+        browser_download_url: format!("http://127.0.0.1/{}", name),
+        size: archive.len() as u64,
+        digest: Some(format!("sha256:{}", sha256_hex(archive))),
+    }
+}
+
+/// 本地模拟的发行信息接口，返回其地址，例如 `http://127.0.0.1:12345`
+fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    (listener, base)
+}
+
+/// 按请求路径返回固定内容，未知路径返回 404
+fn serve(listener: TcpListener, routes: Vec<(String, Vec<u8>)>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let (status, body) = match routes.iter().find(|(p, _)| p == path) {
+                Some((_, body)) => ("200 OK", body.as_slice()),
+                None => ("404 Not Found", &b""[..]),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = stream.write_all(body);
+        }
+    });
+}
+
+#[test]
+fn release_feed_is_parsed_and_platform_asset_selected() {
+    let release: UpxRelease = serde_json::from_str(RELEASE_JSON).unwrap();
+
+    assert_eq!(release.version(), Some(VERSION));
+    match platform_asset_suffix() {
+        Some(suffix) => {
+            let asset = select_asset(&release).unwrap();
+            assert!(asset.name.ends_with(suffix));
+            assert!(!asset.name.contains("src"));
+        }
+        None => assert!(matches!(
+            select_asset(&release),
+            Err(CoreError::UpxUpdateNoAsset(_))
+        )),
+    }
+}
+
+#[test]
+fn api_base_is_configurable() {
+    assert_eq!(
+        latest_release_url("http://127.0.0.1:8080/repos/upx/upx/"),
+        "http://127.0.0.1:8080/repos/upx/upx/releases/latest"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn installs_upx_from_zip() {
+    let root = TempDir::new().unwrap();
+    let entry = format!("upx-5.0.2-win64/{}", UPX_FILE_NAME);
+    let archive = zip_archive(&[("upx-5.0.2-win64/README", b"readme"), (&entry, BINARY)]);
+
+    let entry = install_upx_archive(
+        &archive,
+        &asset_for("upx-5.0.2-win64.zip", &archive),
+        VERSION,
+        root.path(),
+    )
+    .unwrap();
+
+    let path = root.path().join("5.0.2").join(UPX_FILE_NAME);
+    assert_eq!(entry.path, path.to_string_lossy());
+    assert_eq!(entry.version, "5.0.2");
+    assert_eq!(fs::read(&path).unwrap(), BINARY);
+    // 临时文件已替换到目标位置
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn installs_upx_from_tar_xz() {
+    let root = TempDir::new().unwrap();
+    let entry = format!("upx-5.0.2-amd64_linux/{}", UPX_FILE_NAME);
+    let archive = tar_xz_archive(&[
+        ("upx-5.0.2-amd64_linux/upx.1", b"man page"),
+        (&entry, BINARY),
+    ]);
+
+    let entry = install_upx_archive(
+        &archive,
+        &asset_for("upx-5.0.2-amd64_linux.tar.xz", &archive),
+        VERSION,
+        root.path(),
+    )
+    .unwrap();
+
+    assert_eq!(fs::read(&entry.path).unwrap(), BINARY);
+    let mode = fs::metadata(&entry.path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
+#[test]
+fn checksum_mismatch_installs_nothing() {
+    let root = TempDir::new().unwrap();
+    let archive = zip_archive(&[(UPX_FILE_NAME, BINARY)]);
+    let mut asset = asset_for("upx-5.0.2-win64.zip", &archive);
+    asset.digest = Some(format!("sha256:{}", "0".repeat(64)));
+
+    let err = install_upx_archive(&archive, &asset, VERSION, root.path()).unwrap_err();
+
+    assert!(matches!(err, CoreError::UpxUpdateChecksum { .. }));
+    assert_eq!(fs::read_dir(root.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn missing_digest_is_refused() {
+    let root = TempDir::new().unwrap();
+    let archive = zip_archive(&[(UPX_FILE_NAME, BINARY)]);
+    let mut asset = asset_for("upx-5.0.2-win64.zip", &archive);
+    asset.digest = None;
+
+    let err = install_upx_archive(&archive, &asset, VERSION, root.path()).unwrap_err();
+
+    assert!(
+        matches!(err, CoreError::UpxUpdateUnverified(ref name) if name == "upx-5.0.2-win64.zip")
+    );
+}
+
+#[test]
+fn archive_without_upx_is_rejected() {
+    let root = TempDir::new().unwrap();
+    let archive = zip_archive(&[("upx-5.0.2-win64/README", b"readme")]);
+
+    let err = install_upx_archive(
+        &archive,
+        &asset_for("upx-5.0.2-win64.zip", &archive),
+        VERSION,
+        root.path(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, CoreError::UpxUpdateArchive(_)));
+}
+
+#[cfg(unix)]
+#[test]
+fn mismatching_release_leaves_the_registered_binary_in_place() {
+    use upx_tools_core::registry::add_registered;
+
+    let root = TempDir::new().unwrap();
+    let archive = zip_archive(&[(UPX_FILE_NAME, BINARY)]);
+    let asset = asset_for("upx-5.0.2-win64.zip", &archive);
+    let installed = install_upx_archive(&archive, &asset, VERSION, root.path()).unwrap();
+    let mut binaries = Vec::new();
+    add_registered(&mut binaries, installed.clone());
+
+    // 发行版标为 5.0.2，解压出的却是 5.0.1：不能覆盖已注册的 5.0.2
+    let wrong = b"#!/bin/sh\necho upx 5.0.1\n";
+    let archive = zip_archive(&[(UPX_FILE_NAME, wrong)]);
+    let asset = asset_for("upx-5.0.2-win64.zip", &archive);
+
+    let err = install_upx_archive(&archive, &asset, VERSION, root.path()).unwrap_err();
+
+    assert!(
+        matches!(err, CoreError::UpxUpdateVersionMismatch { ref actual, .. } if actual == "5.0.1")
+    );
+    assert_eq!(fs::read(&installed.path).unwrap(), BINARY);
+    assert_eq!(binaries.len(), 1);
+    assert_eq!(binaries[0], installed);
+    // 临时文件不会留在安装目录中
+    let dir = root.path().join("5.0.2");
+    assert_eq!(fs::read_dir(dir).unwrap().count(), 1);
+}
+
+#[test]
+fn release_is_fetched_from_the_configured_api() {
+    let (listener, base) = bind();
+    let archive = zip_archive(&[(UPX_FILE_NAME, BINARY)]);
+    serve(
+        listener,
+        vec![
+            (
+                "/repos/upx/upx/releases/latest".to_string(),
+                RELEASE_JSON.as_bytes().to_vec(),
+            ),
+            ("/upx-5.0.2-win64.zip".to_string(), archive.clone()),
+        ],
+    );
+
+    let release = fetch_latest_release(&format!("{}/repos/upx/upx/", base)).unwrap();
+    assert_eq!(release.version(), Some(VERSION));
+    assert_eq!(release.assets.len(), 5);
+
+    let mut asset = asset_for("upx-5.0.2-win64.zip", &archive);
+    asset.browser_download_url = format!("{}/upx-5.0.2-win64.zip", base);
+    assert_eq!(download_asset(&asset).unwrap(), archive);
+
+    let err = fetch_latest_release(&format!("{}/repos/missing", base)).unwrap_err();
+    assert!(
+        matches!(err, CoreError::UpxUpdateRequest(ref reason) if reason.contains("404")),
+        "{}",
+        err
+    );
+    asset.browser_download_url = format!("{}/gone.zip", base);
+    assert!(matches!(
+        download_asset(&asset),
+        Err(CoreError::UpxUpdateDownload(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn latest_release_is_downloaded_installed_and_registered() {
+    use upx_tools_core::upx_update::install_latest_release;
+
+    let Some(suffix) = platform_asset_suffix() else {
+        return;
+    };
+    let name = format!("upx-5.0.2{}", suffix);
+    let entry = format!("upx-5.0.2/{}", UPX_FILE_NAME);
+    let archive = if suffix.ends_with(".zip") {
+        zip_archive(&[(&entry, BINARY)])
+    } else {
+        tar_xz_archive(&[(&entry, BINARY)])
+    };
+
+    let (listener, base) = bind();
+    let release = serde_json::json!({
+        "tag_name": "v5.0.2",
+        "html_url": format!("{}/release", base),
+        "assets": [{
+            "name": name,
+            "browser_download_url": format!("{}/download/{}", base, name),
+            "size": archive.len(),
+            "digest": format!("sha256:{}", sha256_hex(&archive)),
+        }],
+    });
+    serve(
+        listener,
+        vec![
+            (
+                "/repos/upx/upx/releases/latest".to_string(),
+                release.to_string().into_bytes(),
+            ),
+            (format!("/download/{}", name), archive),
+        ],
+    );
+
+    let root = TempDir::new().unwrap();
+    let mut binaries = Vec::new();
+    let registered = install_latest_release(
+        &format!("{}/repos/upx/upx", base),
+        root.path(),
+        &mut binaries,
+    )
+    .unwrap();
+
+    assert_eq!(registered.version, "5.0.2");
+    assert_eq!(binaries.len(), 1);
+    assert_eq!(binaries[0], registered);
+    assert_eq!(
+        fs::read(root.path().join("5.0.2").join(UPX_FILE_NAME)).unwrap(),
+        BINARY
+    );
+}
@@ -0,0 +1,1726 @@
+// ============================================================================
+// 命令行模式（无窗口运行，供构建服务器使用）
+// ============================================================================
+
+use crate::{pack_cache, upx_location, upx_packer};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use upx_tools_core::batch::BatchSummary;
+use upx_tools_core::bench::{self, BenchFormat, BenchRecord};
+use upx_tools_core::cache::{CachedPacker, PackCache};
+use upx_tools_core::config::{self, get_config_path};
+use upx_tools_core::dedup::DedupSavings;
+use upx_tools_core::history::{self, ExportFormat, HistoryQuery, HistoryRecord, HistoryStore};
+use upx_tools_core::incremental::IncrementalIndex;
+use upx_tools_core::locate::UpxVersion;
+use upx_tools_core::manifest::{self, DriftEntry, DriftStatus};
+use upx_tools_core::naming::{self, DEFAULT_NAME_TEMPLATE};
+use upx_tools_core::output::format_bytes;
+use upx_tools_core::plan::{self, PlanAction, PlanEntry};
+use upx_tools_core::report::{self, BatchReport, ReportEntry, ReportFormat};
+use upx_tools_core::stats::{self, Statistics, StatisticsQuery};
+use upx_tools_core::tune::{self, AutoTuner, Strategy};
+use upx_tools_core::{
+    batch, junit, output_dir, registry, scan, AppConfig, ConflictPolicy, CoreError, ManifestMode,
+    Packer, ScanFolderOptions, SizeBudget, UpxMode, UpxOptions, UpxResult, UpxStatus,
+};
+
+// 退出码：0 全部成功（含跳过），1 存在失败，2 参数错误，3 环境或配置错误
+const EXIT_OK: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_ENV: i32 = 3;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "UPX-Tools",
+    version,
+    about = "UPX 加壳脱壳工具（命令行模式）",
+    long_about = "UPX 加壳脱壳工具（命令行模式）\n\n不带参数启动时打开图形界面；带子命令时以命令行模式运行，不创建窗口。\n未指定的选项使用与图形界面相同的配置文件中的值。"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+    /// 加壳压缩文件或文件夹
+    Compress(PackArgs),
+    /// 脱壳解压文件或文件夹
+    Decompress(PackArgs),
+    /// 测试已加壳文件是否完好
+    Test(InspectArgs),
+    /// 列出已加壳文件的压缩信息
+    List(InspectArgs),
+    /// 扫描文件夹中可处理的文件
+    Scan(ScanArgs),
+    /// 显示 UPX 与配置文件信息
+    Info,
+    /// 查看或修改配置文件
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// 管理注册的多个 UPX 版本
+    Upx {
+        #[command(subcommand)]
+        action: Option<UpxAction>,
+    },
+    /// 查询或导出处理历史
+    History(HistoryArgs),
+    /// 由处理历史汇总累计统计
+    Stats(StatsArgs),
+    /// 按加壳清单校验文件夹，报告被修改、缺失与未登记的文件
+    VerifyManifest(VerifyManifestArgs),
+    /// 查看或清空加壳缓存
+    Cache {
+        #[command(subcommand)]
+        action: Option<CacheAction>,
+    },
+    /// 在临时副本上比较各压缩方式与 UPX 版本的大小与耗时
+    Benchmark(BenchmarkArgs),
+}
+
+#[derive(Debug, Args)]
+struct PackArgs {
+    /// 要处理的文件或文件夹
+    #[arg(required = true)]
+    paths: Vec<String>,
+
+    /// 压缩级别：1-9、best、brute，或 auto 尝试多种压缩方式并保留最小的结果
+    #[arg(short, long, value_parser = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "best", "brute", "auto"])]
+    level: Option<String>,
+
+    /// auto 或设置体积预算时尝试的压缩方式，以逗号分隔，例如 "-9,--best,--best --lzma"
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    strategies: Vec<String>,
+
+    /// 体积预算：输出的最大大小（如 512KB、1.5MB）或相对原文件的比例（如 60%），
+    /// 从快到慢逐个尝试压缩方式，都超出时该文件失败
+    #[arg(long, value_name = "SIZE", overrides_with = "no_max_size")]
+    max_size: Option<SizeBudget>,
+    #[arg(long, hide = true)]
+    no_max_size: bool,
+
+    /// 输出文件（仅处理单个文件时可用）
+    #[arg(short, long, conflicts_with = "output_dir")]
+    output: Option<String>,
+
+    /// 输出目录：按源目录结构写入处理结果，原文件保持不变
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// 输出目录模式下，把不处理的文件也复制过去
+    #[arg(long, overrides_with = "no_copy_unsupported")]
+    copy_unsupported: bool,
+    #[arg(long, hide = true)]
+    no_copy_unsupported: bool,
+
+    /// 加壳输出文件名模板，例如 {stem}.packed.{ext} 或 {dir}/packed/{name}
+    #[arg(long, value_name = "TEMPLATE")]
+    name_template: Option<String>,
+
+    /// 输出文件已存在时：overwrite 覆盖，skip 跳过，rename 自动改名，fail 视为失败
+    #[arg(long, value_name = "POLICY")]
+    on_conflict: Option<ConflictPolicy>,
+
+    /// 加壳后写入清单：sidecar 每个文件旁一个 .upx.json，folder 每个文件夹一个 upx-manifest.json，off 不写
+    #[arg(long, value_name = "MODE")]
+    manifest: Option<ManifestMode>,
+
+    /// 启用 LZMA 压缩
+    #[arg(long, overrides_with = "no_lzma")]
+    lzma: bool,
+    #[arg(long, hide = true)]
+    no_lzma: bool,
+
+    /// 启用极限压缩（ultra-brute）
+    #[arg(long, overrides_with = "no_ultra_brute")]
+    ultra_brute: bool,
+    #[arg(long, hide = true)]
+    no_ultra_brute: bool,
+
+    /// 强制处理受保护的文件
+    #[arg(long, overrides_with = "no_force")]
+    force: bool,
+    #[arg(long, hide = true)]
+    no_force: bool,
+
+    /// 复用本地缓存中相同文件、相同参数与 UPX 版本的加壳结果
+    #[arg(long, overrides_with = "no_cache")]
+    cache: bool,
+    #[arg(long, hide = true)]
+    no_cache: bool,
+
+    /// 跳过自上次处理后未变化的文件（按每个文件夹的处理索引判断）
+    #[arg(long, overrides_with = "no_incremental")]
+    incremental: bool,
+    #[arg(long, hide = true)]
+    no_incremental: bool,
+
+    /// 忽略增量处理索引，处理全部文件并更新索引
+    #[arg(long)]
+    full: bool,
+
+    /// 处理前备份原文件（.bak）
+    #[arg(long, overrides_with = "no_backup")]
+    backup: bool,
+    #[arg(long, hide = true)]
+    no_backup: bool,
+
+    /// 覆盖原文件；关闭时输出为 *_packed.*
+    #[arg(long, overrides_with = "no_overwrite")]
+    overwrite: bool,
+    #[arg(long, hide = true)]
+    no_overwrite: bool,
+
+    #[command(flatten)]
+    batch: BatchArgs,
+
+    /// 将本次使用的选项保存为默认配置
+    #[arg(long)]
+    save_config: bool,
+}
+
+#[derive(Debug, Args)]
+struct InspectArgs {
+    /// 要检查的文件或文件夹
+    #[arg(required = true)]
+    paths: Vec<String>,
+
+    #[command(flatten)]
+    batch: BatchArgs,
+}
+
+#[derive(Debug, Args)]
+struct BatchArgs {
+    /// 递归处理子文件夹
+    #[arg(short, long, overrides_with = "no_recursive")]
+    recursive: bool,
+    #[arg(long, hide = true)]
+    no_recursive: bool,
+
+    /// 并发任务数（默认为 CPU 核心数）
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// 出现警告时也返回失败退出码
+    #[arg(long)]
+    fail_on_warning: bool,
+
+    /// 输出格式：text 为可读文本，jsonl 为每个文件一行 JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// 将结果写入 JUnit XML 报告
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// 生成批量处理报告：.csv 为每个文件一行的 CSV，.html 为摘要页面，其余为 JSON；可指定多次
+    #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+    report: Vec<PathBuf>,
+
+    /// 使用已注册的指定版本 UPX，例如 3.96.0（见 `upx list`）
+    #[arg(long, value_name = "VERSION")]
+    upx_version: Option<String>,
+
+    /// 只预览每个文件的处理方式、UPX 命令行与警告，不修改任何文件
+    #[arg(long, conflicts_with = "junit")]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Jsonl,
+}
+
+#[derive(Debug, Args)]
+struct ScanArgs {
+    /// 要扫描的文件夹
+    folder: String,
+
+    /// 递归扫描子文件夹
+    #[arg(short, long, overrides_with = "no_recursive")]
+    recursive: bool,
+    #[arg(long, hide = true)]
+    no_recursive: bool,
+}
+
+#[derive(Debug, Args)]
+struct VerifyManifestArgs {
+    /// 包含清单的文件夹
+    folder: PathBuf,
+
+    /// 同时校验子文件夹中的清单
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// 输出格式：text 为可读文本，jsonl 为每个文件一行 JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+struct HistoryArgs {
+    /// 只显示输入或输出路径包含此文本的记录
+    #[arg(long)]
+    path: Option<String>,
+
+    /// 起始日期（含当天），格式 YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    /// 截止日期（含当天），格式 YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+
+    /// 只显示指定状态：success、skipped、warning、failed
+    #[arg(long)]
+    status: Option<UpxStatus>,
+
+    /// 最多显示的条数，从最新的记录开始
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    /// 输出格式：text 为可读文本，jsonl 为每条记录一行 JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// 导出到文件：扩展名为 .csv 时导出 CSV，否则导出 JSON
+    #[arg(long, value_name = "FILE")]
+    export: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct StatsArgs {
+    /// 起始日期（含当天），格式 YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    /// 截止日期（含当天），格式 YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+
+    /// 只统计此文件夹（含子文件夹）中的文件
+    #[arg(long, value_name = "DIR")]
+    folder: Option<String>,
+
+    /// 输出格式：text 为可读文本，jsonl 为一行 JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+struct BenchmarkArgs {
+    /// 作为样本的文件或文件夹，原文件不会被修改
+    #[arg(required = true)]
+    paths: Vec<String>,
+
+    /// 递归扫描子文件夹
+    #[arg(short, long, overrides_with = "no_recursive")]
+    recursive: bool,
+    #[arg(long, hide = true)]
+    no_recursive: bool,
+
+    /// 要比较的压缩方式，以逗号分隔，例如 "-9,--best,--best --lzma"；默认同 auto 的候选
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    strategies: Vec<String>,
+
+    /// 要比较的 UPX 版本（见 `upx list`），以逗号分隔或指定多次，all 为全部；默认只用默认版本
+    #[arg(long, value_name = "VERSION", value_delimiter = ',')]
+    upx_version: Vec<String>,
+
+    /// 并发数；大于 1 时各组合互相争用 CPU，耗时不够准确
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// 输出格式：text 为进度与汇总表格，jsonl 为每个组合一行 JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// 导出到文件：扩展名为 .csv 时导出 CSV，.json 时导出 JSON，否则导出文本表格
+    #[arg(long, value_name = "FILE")]
+    export: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// 显示当前配置
+    Show,
+    /// 修改单个配置项，例如 `config set lzma true`
+    Set { key: String, value: String },
+    /// 恢复默认配置
+    Reset,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheAction {
+    /// 显示缓存位置与占用
+    Info,
+    /// 删除全部缓存
+    Clear,
+}
+
+#[derive(Debug, Subcommand)]
+enum UpxAction {
+    /// 列出默认与已注册的 UPX
+    List,
+    /// 注册一个 UPX 可执行文件，以其版本号作为标签
+    Add { path: PathBuf },
+    /// 按版本号或路径移除已注册的 UPX
+    Remove { version: String },
+}
+
+/// 命令行开关与配置默认值合并
+fn resolve_flag(on: bool, off: bool, default: bool) -> bool {
+    if on {
+        true
+    } else if off {
+        false
+    } else {
+        default
+    }
+}
+
+fn level_from_config(level: i32) -> String {
+    if level >= 10 {
+        "best".to_string()
+    } else {
+        level.clamp(1, 9).to_string()
+    }
+}
+
+/// 配置文件没有 brute 对应的级别，保存为 best
+fn level_to_config(level: &str) -> i32 {
+    level.parse().unwrap_or(10)
+}
+
+// ============================================================================
+// 入口
+// ============================================================================
+
+/// 第一个参数是子命令或选项时进入命令行模式
+///
+/// 把文件拖到程序图标上启动时参数是文件路径，仍然打开图形界面。
+pub fn is_cli_invocation() -> bool {
+    let Some(first) = std::env::args_os().nth(1) else {
+        return false;
+    };
+    let first = first.to_string_lossy();
+    if first.starts_with('-') {
+        return true;
+    }
+    first == "help"
+        || Cli::command().get_subcommands().any(|command| {
+            command.get_name() == first || command.get_all_aliases().any(|a| a == first)
+        })
+}
+
+/// 运行命令行模式并返回进程退出码
+pub fn run() -> i32 {
+    #[cfg(target_os = "windows")]
+    attach_parent_console();
+
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { EXIT_USAGE } else { EXIT_OK };
+        }
+    };
+
+    match cli.command {
+        CliCommand::Compress(args) => run_pack(UpxMode::Compress, args),
+        CliCommand::Decompress(args) => run_pack(UpxMode::Decompress, args),
+        CliCommand::Test(args) => run_inspect(UpxMode::Test, args),
+        CliCommand::List(args) => run_inspect(UpxMode::List, args),
+        CliCommand::Scan(args) => run_scan(args),
+        CliCommand::Info => run_info(),
+        CliCommand::Config { action } => run_config(action.unwrap_or(ConfigAction::Show)),
+        CliCommand::Upx { action } => run_upx_registry(action.unwrap_or(UpxAction::List)),
+        CliCommand::History(args) => run_history(args),
+        CliCommand::Stats(args) => run_stats(args),
+        CliCommand::VerifyManifest(args) => run_verify_manifest(args),
+        CliCommand::Cache { action } => run_cache(action.unwrap_or(CacheAction::Info)),
+        CliCommand::Benchmark(args) => run_benchmark(args),
+    }
+}
+
+/// 发布版使用 windows 子系统，需要挂接到父进程的控制台才能输出
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+fn load_config_or_exit() -> Result<AppConfig, i32> {
+    config::load_config().map_err(|e| {
+        eprintln!("[错误] {}", e);
+        EXIT_ENV
+    })
+}
+
+// ============================================================================
+// 子命令
+// ============================================================================
+
+fn run_pack(mode: UpxMode, args: PackArgs) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let level = args.level.clone().unwrap_or_else(|| {
+        if config.auto_tune {
+            "auto".to_string()
+        } else {
+            level_from_config(config.compression_level)
+        }
+    });
+    let tune_strategies = if args.strategies.is_empty() {
+        config.tune_strategies.clone()
+    } else {
+        args.strategies.clone()
+    };
+    let size_budget = if args.no_max_size {
+        None
+    } else {
+        args.max_size.or(config.size_budget)
+    };
+    // 只有自动选择或体积预算才用到候选方式；命令行显式指定的总是校验，避免保存无效的配置
+    let tries_strategies = mode == UpxMode::Compress && (level == "auto" || size_budget.is_some());
+    let strategies = if tries_strategies || !args.strategies.is_empty() {
+        match Strategy::parse_list(&tune_strategies) {
+            Ok(strategies) => strategies,
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_USAGE;
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    let lzma = resolve_flag(args.lzma, args.no_lzma, config.lzma);
+    let ultra_brute = resolve_flag(args.ultra_brute, args.no_ultra_brute, config.ultra_brute);
+    let force = resolve_flag(args.force, args.no_force, config.force_compress);
+    let backup = resolve_flag(args.backup, args.no_backup, config.backup);
+    let overwrite = resolve_flag(args.overwrite, args.no_overwrite, config.overwrite);
+    let use_cache = resolve_flag(args.cache, args.no_cache, config.cache_enabled);
+    let incremental = resolve_flag(args.incremental, args.no_incremental, config.incremental);
+    let recursive = resolve_flag(
+        args.batch.recursive,
+        args.batch.no_recursive,
+        config.include_subfolders,
+    );
+
+    if args.save_config && !args.batch.dry_run {
+        let updated = AppConfig {
+            // 自动选择时保留原来的固定级别
+            compression_level: if level == "auto" {
+                config.compression_level
+            } else {
+                level_to_config(&level)
+            },
+            auto_tune: level == "auto",
+            tune_strategies,
+            size_budget,
+            overwrite,
+            backup,
+            lzma,
+            ultra_brute,
+            include_subfolders: recursive,
+            force_compress: force,
+            cache_enabled: use_cache,
+            incremental,
+            ..config.clone()
+        };
+        if let Err(e) = config::save_config(&updated) {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    }
+
+    let upx_version = args
+        .batch
+        .upx_version
+        .clone()
+        .or(config.upx_version.clone());
+    let copy_unsupported = resolve_flag(
+        args.copy_unsupported,
+        args.no_copy_unsupported,
+        config.copy_unsupported,
+    );
+    // 命令行指定的输出目录总是生效，配置中的仅在不覆盖原文件时生效
+    let on_conflict = args.on_conflict.unwrap_or(config.on_conflict);
+    let manifest = args.manifest.unwrap_or(config.manifest);
+    // 缓存只用于加壳，预览时不需要
+    let cache = if use_cache && mode == UpxMode::Compress && !args.batch.dry_run {
+        match pack_cache(&config) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_ENV;
+            }
+        }
+    } else {
+        None
+    };
+    let incremental = if incremental && !args.batch.dry_run {
+        let roots = args.paths.iter().map(PathBuf::from);
+        match IncrementalIndex::open_default(roots) {
+            Ok(index) => Some(Incremental {
+                index,
+                full: args.full,
+            }),
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_ENV;
+            }
+        }
+    } else {
+        None
+    };
+    // 命名模板只用于加壳
+    let name_template = args
+        .name_template
+        .clone()
+        .or(config.name_template.clone())
+        .filter(|_| mode == UpxMode::Compress);
+    let output_root = args.output_dir.clone().or_else(|| {
+        config
+            .output_dir
+            .clone()
+            .filter(|_| !overwrite && args.output.is_none())
+            .map(PathBuf::from)
+    });
+
+    let (mut files, mut missing) = collect_inputs(&args.paths, recursive);
+
+    if args.output.is_some() && files.len() + missing.len() > 1 {
+        eprintln!("[错误] --output 只能在处理单个文件时使用");
+        return EXIT_USAGE;
+    }
+
+    let mut jobs = Vec::with_capacity(files.len());
+    if let Some(output_root) = &output_root {
+        // 输出目录位于源文件夹中时，不处理其中上次的结果
+        files.retain(|(_, file)| !output_dir::is_within(Path::new(file), output_root));
+
+        if copy_unsupported {
+            for path in &args.paths {
+                let source = Path::new(path);
+                let copied = if args.batch.dry_run {
+                    output_dir::unsupported_copies(source, recursive, output_root)
+                        .map(|copies| copies.len())
+                } else {
+                    output_dir::copy_unsupported_files(source, recursive, output_root)
+                };
+                match copied {
+                    Ok(0) => {}
+                    Ok(count) if args.batch.dry_run => {
+                        eprintln!("[信息] 将复制 {} 个其他文件: {}", count, path)
+                    }
+                    Ok(count) => eprintln!("[信息] 已复制 {} 个其他文件: {}", count, path),
+                    Err(e) => {
+                        eprintln!("[错误] {}", e);
+                        return EXIT_FAILED;
+                    }
+                }
+            }
+        }
+    }
+
+    for (source, input_file) in files {
+        let output_file = match &args.output {
+            Some(output) => output.clone(),
+            None => match pack_output(
+                &source,
+                &input_file,
+                output_root.as_deref(),
+                overwrite || mode == UpxMode::Decompress,
+                name_template.as_deref(),
+            ) {
+                Ok(output) => output,
+                Err(e) => {
+                    missing.push(UpxResult::failed(&input_file, &input_file, e.to_string()));
+                    continue;
+                }
+            },
+        };
+        jobs.push(UpxOptions {
+            mode,
+            input_file,
+            output_file,
+            compression_level: level.clone(),
+            backup,
+            lzma,
+            nrv: None,
+            ultra_brute,
+            force,
+            upx_version: upx_version.clone(),
+            on_conflict,
+            manifest,
+            budget: size_budget.filter(|_| mode == UpxMode::Compress),
+        });
+    }
+
+    run_batch(
+        mode,
+        jobs,
+        missing,
+        &args.batch,
+        cache,
+        incremental,
+        strategies,
+    )
+}
+
+/// 未指定 `--output` 时的输出路径
+///
+/// 有输出目录时按源目录结构映射，设置了模板再按模板改名；否则覆盖原文件，
+/// 或在原文件旁按模板（默认 `name_packed.ext`）生成。
+fn pack_output(
+    source: &str,
+    input: &str,
+    output_root: Option<&Path>,
+    in_place: bool,
+    name_template: Option<&str>,
+) -> Result<String, CoreError> {
+    let output = match output_root {
+        Some(root) => {
+            let mirrored = output_dir::mirrored_output(Path::new(source), Path::new(input), root)?;
+            match name_template {
+                Some(template) => naming::render_output_name(template, &mirrored)?,
+                None => mirrored,
+            }
+        }
+        None if in_place => return Ok(input.to_string()),
+        None => naming::render_output_name(
+            name_template.unwrap_or(DEFAULT_NAME_TEMPLATE),
+            Path::new(input),
+        )?,
+    };
+    Ok(output.to_string_lossy().into_owned())
+}
+
+fn run_inspect(mode: UpxMode, args: InspectArgs) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let recursive = resolve_flag(
+        args.batch.recursive,
+        args.batch.no_recursive,
+        config.include_subfolders,
+    );
+    let upx_version = args
+        .batch
+        .upx_version
+        .clone()
+        .or(config.upx_version.clone());
+    let (files, missing) = collect_inputs(&args.paths, recursive);
+
+    let jobs: Vec<UpxOptions> = files
+        .into_iter()
+        .map(|(_, input_file)| UpxOptions {
+            mode,
+            output_file: input_file.clone(),
+            input_file,
+            compression_level: level_from_config(config.compression_level),
+            backup: false,
+            lzma: false,
+            nrv: None,
+            ultra_brute: false,
+            force: false,
+            upx_version: upx_version.clone(),
+            on_conflict: ConflictPolicy::default(),
+            manifest: ManifestMode::Off,
+            budget: None,
+        })
+        .collect();
+
+    run_batch(mode, jobs, missing, &args.batch, None, None, Vec::new())
+}
+
+fn run_scan(args: ScanArgs) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let options = ScanFolderOptions {
+        folder_path: args.folder,
+        include_subfolders: resolve_flag(
+            args.recursive,
+            args.no_recursive,
+            config.include_subfolders,
+        ),
+    };
+
+    match scan::scan_folder(&options) {
+        Ok(files) => {
+            for file in files {
+                println!("{}", file);
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            EXIT_USAGE
+        }
+    }
+}
+
+fn run_info() -> i32 {
+    println!("UPX-Tools {}", env!("CARGO_PKG_VERSION"));
+
+    let mut code = EXIT_OK;
+    match upx_location() {
+        Ok(location) => {
+            println!("UPX 路径: {}", location.path.display());
+            println!("UPX 版本: {}", location.version);
+            println!("选择原因: {}", location.reason);
+            if let Ok(caps) = location.packer().capabilities() {
+                let libraries: Vec<String> = [
+                    ("UCL", &caps.ucl),
+                    ("zlib", &caps.zlib),
+                    ("LZMA", &caps.lzma),
+                ]
+                .into_iter()
+                .filter_map(|(name, version)| Some(format!("{} {}", name, version.as_ref()?)))
+                .collect();
+                if !libraries.is_empty() {
+                    println!("压缩库: {}", libraries.join(", "));
+                }
+                if !caps.formats.is_empty() {
+                    println!("支持格式: {}", caps.formats.join(", "));
+                }
+            }
+        }
+        Err(e) => {
+            println!("UPX 路径: {}", e);
+            code = EXIT_ENV;
+        }
+    }
+
+    if let Some(path) = get_config_path() {
+        let state = if path.exists() { "" } else { "（未创建）" };
+        println!("配置文件: {}{}", path.display(), state);
+    }
+
+    code
+}
+
+fn run_config(action: ConfigAction) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let updated = match action {
+        ConfigAction::Show => {
+            match serde_json::to_string_pretty(&config) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    eprintln!("[错误] 序列化配置失败: {}", e);
+                    return EXIT_ENV;
+                }
+            }
+            return EXIT_OK;
+        }
+        ConfigAction::Reset => AppConfig::default(),
+        ConfigAction::Set { key, value } => match set_config_value(&config, &key, &value) {
+            Ok(updated) => updated,
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_USAGE;
+            }
+        },
+    };
+
+    match config::save_config(&updated) {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            EXIT_ENV
+        }
+    }
+}
+
+fn run_upx_registry(action: UpxAction) -> i32 {
+    let mut config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    match action {
+        UpxAction::List => {
+            match upx_location() {
+                Ok(location) => {
+                    let label = UpxVersion::parse(&location.version)
+                        .map_or(location.version.clone(), |v| v.to_string());
+                    println!("{}\t{}（默认）", label, location.path.display());
+                }
+                Err(e) => println!("[默认] {}", e),
+            }
+            for binary in &config.upx_binaries {
+                let mark = if config.upx_version.as_deref() == Some(binary.version.as_str()) {
+                    "（当前选用）"
+                } else {
+                    ""
+                };
+                println!("{}\t{}{}", binary.version, binary.path, mark);
+            }
+            return EXIT_OK;
+        }
+        UpxAction::Add { path } => match registry::register_upx(&mut config.upx_binaries, &path) {
+            Ok(entry) => println!("已注册 UPX {}: {}", entry.version, entry.path),
+            Err(e) => {
+                eprintln!("[错误] {}: {}", path.display(), e);
+                return EXIT_USAGE;
+            }
+        },
+        UpxAction::Remove { version } => {
+            if !registry::unregister_upx(&mut config.upx_binaries, &version) {
+                eprintln!("[错误] {}", CoreError::UpxVersionNotRegistered(version));
+                return EXIT_USAGE;
+            }
+            // 移除的正是默认选用的版本时恢复自动选择
+            if config.upx_version.as_deref() == Some(version.as_str()) {
+                config.upx_version = None;
+            }
+            println!("已移除 UPX {}", version);
+        }
+    }
+
+    match config::save_config(&config) {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            EXIT_ENV
+        }
+    }
+}
+
+/// 通过 JSON 修改配置项，键名与配置文件一致
+fn set_config_value(config: &AppConfig, key: &str, value: &str) -> Result<AppConfig, String> {
+    let mut json = serde_json::to_value(config).map_err(|e| format!("序列化配置失败: {}", e))?;
+    let fields = json.as_object_mut().ok_or("配置格式错误")?;
+
+    if !fields.contains_key(key) {
+        let keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+        return Err(format!(
+            "未知的配置项: {}（可用: {}）",
+            key,
+            keys.join(", ")
+        ));
+    }
+
+    let parsed = serde_json::from_str(value)
+        .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    fields.insert(key.to_string(), parsed);
+
+    serde_json::from_value(json).map_err(|e| format!("配置项 {} 的值无效: {}", key, e))
+}
+
+// ============================================================================
+// 批量执行
+// ============================================================================
+
+/// 展开输入路径：文件夹按配置扫描，文件直接加入
+///
+/// 返回 `(输入路径, 文件)` 列表与无法读取的路径，输入路径用于输出目录模式下计算相对路径。
+fn collect_inputs(paths: &[String], recursive: bool) -> (Vec<(String, String)>, Vec<UpxResult>) {
+    let mut files = Vec::new();
+    let mut missing = Vec::new();
+
+    for path in paths {
+        let p = Path::new(path);
+        if p.is_dir() {
+            match scan::scan_folder(&ScanFolderOptions {
+                folder_path: path.clone(),
+                include_subfolders: recursive,
+            }) {
+                Ok(found) => files.extend(found.into_iter().map(|file| (path.clone(), file))),
+                Err(e) => missing.push(UpxResult::failed(path, path, e.to_string())),
+            }
+        } else if p.is_file() {
+            files.push((path.clone(), path.clone()));
+        } else {
+            missing.push(UpxResult::failed(
+                path,
+                path,
+                format!("路径不存在: {}", path),
+            ));
+        }
+    }
+
+    (files, missing)
+}
+
+/// 所有任务使用相同的压缩选项，只需检查一次；不支持的选项在执行时会被忽略
+fn warn_unsupported_options(packer: &dyn Packer, options: &UpxOptions) {
+    let Ok(caps) = packer.capabilities() else {
+        return;
+    };
+    let requested = [
+        ("--lzma", options.lzma),
+        ("--ultra-brute", options.ultra_brute),
+        ("--force", options.force),
+    ];
+    for (option, enabled) in requested {
+        if enabled && !caps.supports(option) {
+            eprintln!("[警告] 当前 UPX 不支持 {}，已忽略", option);
+        }
+    }
+}
+
+/// 增量处理的索引；`full` 时不跳过任何文件，只更新索引
+struct Incremental {
+    index: IncrementalIndex,
+    full: bool,
+}
+
+fn run_batch(
+    mode: UpxMode,
+    jobs: Vec<UpxOptions>,
+    missing: Vec<UpxResult>,
+    batch_args: &BatchArgs,
+    cache: Option<PackCache>,
+    mut incremental: Option<Incremental>,
+    strategies: Vec<Strategy>,
+) -> i32 {
+    if batch_args.dry_run {
+        return run_plan(
+            jobs,
+            missing,
+            batch_args.format,
+            strategies,
+            incremental.as_ref(),
+        );
+    }
+
+    let mut summary = BatchSummary::default();
+    let mut results = Vec::with_capacity(jobs.len() + missing.len());
+
+    if jobs.is_empty() && missing.is_empty() {
+        eprintln!("[警告] 没有找到可处理的文件");
+    }
+
+    // 只在需要报告时收集报告条目；哈希已由 run_upx 计算，记在结果中
+    let want_report = !batch_args.report.is_empty();
+    let mut report_entries = Vec::new();
+
+    for result in missing {
+        print_result(mode, &result, batch_args.format);
+        summary.record(result.status);
+        if want_report {
+            report_entries.push(ReportEntry::from_result(None, &result));
+        }
+        results.push(result);
+    }
+
+    if !jobs.is_empty() {
+        let packer = match upx_packer() {
+            // 自动选择的每个候选策略分别查缓存
+            Ok(registry) => AutoTuner::new(CachedPacker::new(registry, cache), strategies),
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_ENV;
+            }
+        };
+        let default_version = packer.inner().inner().default_label().to_string();
+
+        warn_unsupported_options(&packer, &jobs[0]);
+
+        let jobs = match &incremental {
+            Some(Incremental { index, full: false }) => {
+                let (pending, unchanged) =
+                    index.partition(jobs, packer.strategies(), &default_version);
+                for (job, result) in unchanged {
+                    print_result(mode, &result, batch_args.format);
+                    summary.record(result.status);
+                    if want_report {
+                        report_entries.push(ReportEntry::from_result(Some(&job), &result));
+                    }
+                    results.push(result);
+                }
+                pending
+            }
+            _ => jobs,
+        };
+
+        // 历史记录写入失败只提示一次，不影响处理结果
+        let history = HistoryStore::open_default().ok();
+        let mut history_warned = false;
+
+        let threads = batch_args.jobs.unwrap_or_else(batch::default_threads);
+        // 每个文件完成后立即输出
+        summary.merge(batch::run_batch(&packer, &jobs, threads, |job, result| {
+            print_result(mode, &result, batch_args.format);
+            if let Some(history) = &history {
+                let upx_version = job
+                    .upx_version
+                    .clone()
+                    .unwrap_or_else(|| default_version.clone());
+                let record = HistoryRecord::new(job, Some(upx_version), &result);
+                if let Err(e) = history.append(&record) {
+                    if !history_warned {
+                        eprintln!("[警告] {}", e);
+                        history_warned = true;
+                    }
+                }
+            }
+            if let Some(incremental) = &mut incremental {
+                incremental
+                    .index
+                    .record(job, packer.strategies(), &default_version, &result);
+            }
+            if want_report {
+                report_entries.push(ReportEntry::from_result(Some(job), &result));
+            }
+            results.push(result);
+        }));
+
+        // 索引保存失败只影响下次能否跳过
+        if let Some(Err(e)) = incremental.map(|incremental| incremental.index.save()) {
+            eprintln!("[警告] {}", e);
+        }
+    }
+
+    eprintln!(
+        "处理完成! 成功: {} 个，跳过: {} 个，警告: {} 个，失败: {} 个",
+        summary.success, summary.skipped, summary.warning, summary.failed
+    );
+    let mut deduplicated = DedupSavings::default();
+    results.iter().for_each(|result| {
+        deduplicated.record(result.duplicate_of.as_deref(), result.original_size)
+    });
+    if deduplicated.files > 0 {
+        eprintln!(
+            "[信息] 去重: {} 个文件与其他文件内容相同，已直接复制结果，未运行 UPX（共 {}）",
+            deduplicated.files,
+            format_bytes(deduplicated.bytes)
+        );
+    }
+    let over_budget = results
+        .iter()
+        .filter(|result| result.budget.as_ref().is_some_and(|check| !check.met))
+        .count();
+    if over_budget > 0 {
+        eprintln!(
+            "[错误] 未达到体积预算: {} 个文件（已计入失败）",
+            over_budget
+        );
+    }
+
+    if let Some(path) = &batch_args.junit {
+        if let Err(e) =
+            junit::write_report(path, mode.as_str(), &results, batch_args.fail_on_warning)
+        {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    }
+
+    if want_report {
+        let report = BatchReport::new(mode, report_entries);
+        for path in &batch_args.report {
+            if let Err(e) = report::write_report(&report, path, ReportFormat::from_path(path)) {
+                eprintln!("[错误] {}", e);
+                return EXIT_ENV;
+            }
+            eprintln!("[信息] 已生成报告: {}", path.display());
+        }
+    }
+
+    if summary.has_failures(batch_args.fail_on_warning) {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+fn run_history(args: HistoryArgs) -> i32 {
+    let parse_date = |date: &Option<String>| date.as_deref().map(history::parse_date).transpose();
+    let query = match (parse_date(&args.since), parse_date(&args.until)) {
+        (Ok(since), Ok(until)) => HistoryQuery {
+            path: args.path,
+            since,
+            until,
+            status: args.status,
+            limit: args.limit,
+        },
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    let records = match HistoryStore::open_default().and_then(|store| store.query(&query)) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    };
+
+    if let Some(path) = &args.export {
+        let format = ExportFormat::from_path(path);
+        if let Err(e) = history::export_history(&records, path, format) {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+        eprintln!("已导出 {} 条记录到 {}", records.len(), path.display());
+        return EXIT_OK;
+    }
+
+    if records.is_empty() {
+        eprintln!("没有符合条件的历史记录");
+    }
+    for record in &records {
+        if args.format == OutputFormat::Jsonl {
+            match serde_json::to_string(record) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+            }
+            continue;
+        }
+        print_history_record(record);
+    }
+    EXIT_OK
+}
+
+fn print_history_record(record: &HistoryRecord) {
+    let time = record.timestamp.format("%Y-%m-%d %H:%M:%S");
+    let status = record.status.label();
+    let mode = record.options.mode.as_str();
+
+    match record.status {
+        UpxStatus::Success | UpxStatus::Warning if !record.options.mode.is_inspect() => println!(
+            "{} [{}] {} {} -> {} ({} -> {} 字节, {}%)",
+            time,
+            status,
+            mode,
+            record.input_file,
+            record.output_file,
+            record.original_size,
+            record.output_size,
+            record.ratio
+        ),
+        UpxStatus::Success | UpxStatus::Warning => {
+            println!("{} [{}] {} {}", time, status, mode, record.input_file)
+        }
+        _ => println!(
+            "{} [{}] {} {}: {}",
+            time,
+            status,
+            mode,
+            record.input_file,
+            record.reason.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+fn run_stats(args: StatsArgs) -> i32 {
+    let parse_date = |date: &Option<String>| date.as_deref().map(history::parse_date).transpose();
+    let query = match (parse_date(&args.since), parse_date(&args.until)) {
+        (Ok(since), Ok(until)) => StatisticsQuery {
+            since,
+            until,
+            folder: args.folder,
+        },
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    let records = match HistoryStore::open_default().and_then(|store| store.load()) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    };
+    let stats = stats::compute_statistics(&records, &query);
+
+    if args.format == OutputFormat::Jsonl {
+        match serde_json::to_string(&stats) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+        }
+        return EXIT_OK;
+    }
+    print_statistics(&stats);
+    EXIT_OK
+}
+
+fn print_statistics(stats: &Statistics) {
+    if stats.records == 0 {
+        println!("没有符合条件的历史记录");
+        return;
+    }
+    let summary = &stats.summary;
+    println!(
+        "记录: {} 条（成功 {}，跳过 {}，警告 {}，失败 {}）",
+        stats.records, summary.success, summary.skipped, summary.warning, summary.failed
+    );
+    println!(
+        "累计节省: {}（{} -> {}）",
+        format_bytes(stats.saved_bytes),
+        format_bytes(stats.original_size),
+        format_bytes(stats.output_size)
+    );
+
+    if !stats.by_format.is_empty() {
+        println!("\n按格式:");
+        for format in &stats.by_format {
+            let name = if format.format.is_empty() {
+                "(无扩展名)"
+            } else {
+                &format.format
+            };
+            println!(
+                "  {:<12} {} 个文件，节省 {}",
+                name,
+                format.files,
+                format_bytes(format.saved_bytes)
+            );
+        }
+    }
+    if !stats.by_method.is_empty() {
+        println!("\n按压缩方式的平均压缩率:");
+        for method in &stats.by_method {
+            println!(
+                "  {:<24} {} 个文件，{:.1}%",
+                method.method, method.files, method.average_ratio
+            );
+        }
+    }
+
+    println!("\n失败率: {:.1}%", stats.failure_rate * 100.0);
+    for failure in &stats.failures {
+        println!(
+            "  {:<24} {} 次（{:.1}%）",
+            failure.kind,
+            failure.count,
+            failure.rate * 100.0
+        );
+    }
+    if let Some(ms_per_mb) = stats.ms_per_mb {
+        println!("\n处理速度: {:.0} 毫秒/MB", ms_per_mb);
+    }
+}
+
+fn run_verify_manifest(args: VerifyManifestArgs) -> i32 {
+    let report = match manifest::verify_manifest(&args.folder, args.recursive) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return match e {
+                CoreError::PathNotFound(_)
+                | CoreError::NotADirectory(_)
+                | CoreError::ManifestNotFound(_) => EXIT_USAGE,
+                _ => EXIT_ENV,
+            };
+        }
+    };
+
+    for entry in &report.entries {
+        if args.format == OutputFormat::Jsonl {
+            match serde_json::to_string(entry) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+            }
+            continue;
+        }
+        print_drift_entry(entry);
+    }
+
+    let drift = report.drift().count();
+    eprintln!(
+        "\n校验完成: {} 个清单，{} 个文件一致，{} 个不一致",
+        report.manifests.len(),
+        report.entries.len() - drift,
+        drift
+    );
+    if report.is_clean() {
+        EXIT_OK
+    } else {
+        EXIT_FAILED
+    }
+}
+
+fn print_drift_entry(entry: &DriftEntry) {
+    let status = entry.status.label();
+    match entry.status {
+        DriftStatus::Ok | DriftStatus::Missing => println!("[{}] {}", status, entry.file),
+        DriftStatus::Modified => println!(
+            "[{}] {}\n  清单: {}\n  实际: {}",
+            status,
+            entry.file,
+            entry.expected_sha256.as_deref().unwrap_or_default(),
+            entry.actual_sha256.as_deref().unwrap_or_default()
+        ),
+        DriftStatus::Untracked => println!("[{}] {}（不在任何清单中）", status, entry.file),
+    }
+}
+
+fn run_cache(action: CacheAction) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+    let cache = match pack_cache(&config) {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    };
+
+    match action {
+        CacheAction::Info => {
+            let info = cache.info();
+            let state = if config.cache_enabled {
+                "已启用"
+            } else {
+                "未启用（加壳时使用 --cache）"
+            };
+            println!("缓存目录: {}", info.path);
+            println!("状态: {}", state);
+            println!("条目: {} 个", info.entries);
+            println!(
+                "占用: {} / {}",
+                format_bytes(info.total_bytes),
+                format_bytes(info.max_bytes)
+            );
+            EXIT_OK
+        }
+        CacheAction::Clear => match cache.clear() {
+            Ok(info) => {
+                println!(
+                    "已清空加壳缓存: {} 个条目，释放 {}",
+                    info.entries,
+                    format_bytes(info.total_bytes)
+                );
+                EXIT_OK
+            }
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                EXIT_ENV
+            }
+        },
+    }
+}
+
+fn run_benchmark(args: BenchmarkArgs) -> i32 {
+    let config = match load_config_or_exit() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let tune_strategies = if args.strategies.is_empty() {
+        config.tune_strategies.clone()
+    } else {
+        args.strategies.clone()
+    };
+    let strategies = match Strategy::parse_list(&tune_strategies) {
+        Ok(strategies) => strategies,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    let recursive = resolve_flag(args.recursive, args.no_recursive, config.include_subfolders);
+    let (files, missing) = collect_inputs(&args.paths, recursive);
+    for result in &missing {
+        eprintln!("[错误] {}", result.message);
+    }
+    let files: Vec<String> = files.into_iter().map(|(_, file)| file).collect();
+    if files.is_empty() {
+        eprintln!("[警告] 没有找到可处理的文件");
+        return if missing.is_empty() {
+            EXIT_OK
+        } else {
+            EXIT_FAILED
+        };
+    }
+
+    let packer = match upx_packer() {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    };
+    let labels = packer.labels();
+    let versions: Vec<Option<String>> = if args.upx_version.iter().any(|v| v == "all") {
+        labels.iter().map(|label| Some(label.to_string())).collect()
+    } else if args.upx_version.is_empty() {
+        vec![Some(packer.default_label().to_string())]
+    } else {
+        let mut versions = Vec::new();
+        for version in &args.upx_version {
+            if !labels.contains(&version.as_str()) {
+                eprintln!(
+                    "[错误] {}",
+                    CoreError::UpxVersionNotRegistered(version.clone())
+                );
+                return EXIT_USAGE;
+            }
+            versions.push(Some(version.clone()));
+        }
+        versions
+    };
+
+    let cases = bench::bench_cases(&files, &strategies, &versions);
+    let total = cases.len();
+    if args.format == OutputFormat::Text {
+        eprintln!(
+            "基准测试: {} 个文件 × {} 种压缩方式 × {} 个 UPX 版本，共 {} 个组合",
+            files.len(),
+            strategies.len(),
+            versions.len(),
+            total
+        );
+    }
+
+    let mut done = 0;
+    let records = bench::run_benchmark(&packer, &cases, args.jobs, |record| {
+        done += 1;
+        print_bench_record(done, total, record, args.format);
+    });
+    let records = match records {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+    };
+
+    let default_version = packer.default_label();
+    if args.format == OutputFormat::Text {
+        println!();
+        print!("{}", bench::render_table(&records, default_version));
+    }
+    if let Some(path) = &args.export {
+        let format = BenchFormat::from_path(path);
+        if let Err(e) = bench::export_benchmark(&records, default_version, path, format) {
+            eprintln!("[错误] {}", e);
+            return EXIT_ENV;
+        }
+        eprintln!("已导出 {} 个组合的结果到 {}", records.len(), path.display());
+    }
+
+    if missing.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILED
+    }
+}
+
+fn print_bench_record(done: usize, total: usize, record: &BenchRecord, format: OutputFormat) {
+    if format == OutputFormat::Jsonl {
+        match serde_json::to_string(record) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+        }
+        return;
+    }
+
+    let version = record.upx_version.as_deref().unwrap_or_default();
+    let label = format!(
+        "[{}/{}] {} {} {}",
+        done, total, version, record.strategy, record.file
+    );
+    match &record.error {
+        None => eprintln!(
+            "{}: {}（{:.1}%），加壳 {} ms，脱壳 {} ms",
+            label,
+            format_bytes(record.output_size),
+            record.ratio,
+            record.pack_ms,
+            record.unpack_ms.unwrap_or_default()
+        ),
+        Some(error) => eprintln!("{}: {}", label, error),
+    }
+}
+
+/// `--dry-run`：只输出预览，任一文件预检失败时返回失败退出码
+///
+/// 与实际处理一样按增量索引跳过未变化的文件（`--full` 时不跳过），并标出去重的文件。
+fn run_plan(
+    jobs: Vec<UpxOptions>,
+    missing: Vec<UpxResult>,
+    format: OutputFormat,
+    strategies: Vec<Strategy>,
+    incremental: Option<&Incremental>,
+) -> i32 {
+    let mut entries: Vec<PlanEntry> = missing
+        .iter()
+        .map(|result| {
+            PlanEntry::failed(
+                &result.input_file,
+                &result.output_file,
+                result.message.clone(),
+            )
+        })
+        .collect();
+
+    if !jobs.is_empty() {
+        let packer = match upx_packer() {
+            Ok(registry) => AutoTuner::new(registry, strategies),
+            Err(e) => {
+                eprintln!("[错误] {}", e);
+                return EXIT_ENV;
+            }
+        };
+        let index = incremental
+            .filter(|incremental| !incremental.full)
+            .map(|incremental| &incremental.index);
+        let default_version = packer.inner().default_label().to_string();
+        entries.extend(plan::plan_batch(&packer, &jobs, index, &default_version));
+    }
+
+    if entries.is_empty() {
+        eprintln!("[警告] 没有找到可处理的文件");
+    }
+    for entry in &entries {
+        print_plan_entry(entry, format);
+    }
+
+    let count = |action| entries.iter().filter(|e| e.action == action).count();
+    let (skipped, failed) = (count(PlanAction::Skip), count(PlanAction::Fail));
+    eprintln!(
+        "预览完成（未修改任何文件）! 将处理: {} 个，跳过: {} 个，失败: {} 个",
+        entries.len() - skipped - failed,
+        skipped,
+        failed
+    );
+
+    if failed > 0 {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+fn print_plan_entry(entry: &PlanEntry, format: OutputFormat) {
+    if format == OutputFormat::Jsonl {
+        match serde_json::to_string(entry) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+        }
+        return;
+    }
+
+    let tag = format!("[{}]", entry.action.label());
+    match (entry.action, &entry.reason) {
+        (PlanAction::Skip | PlanAction::Fail, Some(reason)) => {
+            println!("{} {}: {}", tag, entry.input_file, reason)
+        }
+        _ if entry.output_file == entry.input_file => println!("{} {}", tag, entry.input_file),
+        _ => println!("{} {} -> {}", tag, entry.input_file, entry.output_file),
+    }
+    if !entry.command_line.is_empty() && entry.action != PlanAction::Skip {
+        println!("    命令: {}", entry.command_line);
+    }
+    if let Some(backup) = &entry.backup_file {
+        println!("    备份: {}", backup);
+    }
+    for warning in &entry.warnings {
+        println!("    [警告] {}", warning);
+    }
+}
+
+fn print_result(mode: UpxMode, result: &UpxResult, format: OutputFormat) {
+    if format == OutputFormat::Jsonl {
+        match serde_json::to_string(result) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("[错误] 序列化结果失败: {}", e),
+        }
+        return;
+    }
+
+    match result.status {
+        UpxStatus::Success | UpxStatus::Warning => {
+            let tag = if result.status == UpxStatus::Success {
+                "[成功]"
+            } else {
+                "[警告]"
+            };
+            if mode.is_inspect() {
+                println!("{} {}", tag, result.input_file);
+                if let Some((_, listing)) = result.message.split_once("UPX 输出:\n") {
+                    println!("{}", listing);
+                }
+            } else {
+                println!(
+                    "{} {} -> {} ({} -> {} 字节, {}%)",
+                    tag,
+                    result.input_file,
+                    result.output_file,
+                    result.original_size,
+                    result.output_size,
+                    result.ratio
+                );
+                match &result.budget {
+                    Some(check) => println!(
+                        "  体积预算 {}: {}",
+                        check.budget.describe(),
+                        tune::format_trials(&result.strategies)
+                    ),
+                    None if !result.strategies.is_empty() => {
+                        println!("  自动选择: {}", tune::format_trials(&result.strategies));
+                    }
+                    None => {}
+                }
+            }
+            if let (UpxStatus::Warning, Some(reason)) = (result.status, &result.reason) {
+                eprintln!("{}", reason);
+            }
+        }
+        UpxStatus::Skipped => {
+            println!(
+                "[跳过] {}: {}",
+                result.input_file,
+                result.reason.as_deref().unwrap_or_default()
+            );
+        }
+        UpxStatus::Failed => match &result.budget {
+            Some(check) if !check.met => {
+                eprintln!("[未达预算] {}", result.input_file);
+                eprintln!("{}", result.message);
+                eprintln!("  各压缩方式: {}", tune::format_trials(&result.strategies));
+            }
+            _ => {
+                eprintln!("[失败] {}", result.input_file);
+                eprintln!("{}", result.message);
+            }
+        },
+    }
+}
@@ -0,0 +1,667 @@
+// ============================================================================
+// 图形界面：Tauri 命令只做参数转换，逻辑都在 upx-tools-core 中
+// ============================================================================
+
+use crate::{load_config_or_default, pack_cache, upx_location, upx_packer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use upx_tools_core::cache::{CacheInfo, CachedPacker};
+use upx_tools_core::history::{self, ExportFormat, HistoryQuery, HistoryRecord, HistoryStore};
+use upx_tools_core::incremental::IncrementalIndex;
+use upx_tools_core::locate::{UpxLocation, UpxVersion};
+use upx_tools_core::manifest::{self, VerifyReport};
+use upx_tools_core::plan::{self, PlanEntry};
+use upx_tools_core::registry::{self, RegisteredUpx};
+use upx_tools_core::report::{self, BatchReport, ReportEntry, ReportFormat};
+use upx_tools_core::stats::{self, Statistics, StatisticsQuery};
+use upx_tools_core::tune::{AutoTuner, Strategy};
+use upx_tools_core::upx_update::{self, UpxReleaseAsset};
+use upx_tools_core::{
+    config, dedup, http, naming, output_dir, process, scan, AppConfig, CoreError, Packer,
+    ScanFolderOptions, UpxCapabilities, UpxMode, UpxOptions, UpxResult,
+};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// ============================================================================
+// UPX 处理
+// ============================================================================
+
+/// `root` 为拖入的文件夹（或文件），开启增量处理时按其索引跳过未变化的文件；
+/// `full_run` 时照常处理并更新索引。`duplicate_of` 为内容相同的代表文件的结果，
+/// 代表文件处理成功时直接复制其输出
+#[tauri::command]
+async fn process_upx(
+    options: UpxOptions,
+    root: Option<String>,
+    full_run: bool,
+    duplicate_of: Option<UpxResult>,
+) -> Result<UpxResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let config = load_config_or_default();
+        // 缓存打不开时照常处理
+        let cache = config
+            .cache_enabled
+            .then(|| pack_cache(&config).ok())
+            .flatten();
+        let strategies =
+            Strategy::parse_for(&options, &config.tune_strategies).map_err(|e| e.to_string())?;
+        let upx = upx_packer().map_err(|e| e.to_string())?;
+        let packer = AutoTuner::new(CachedPacker::new(upx, cache), strategies);
+        let default_version = packer.inner().inner().default_label().to_string();
+
+        // 索引打不开时照常处理
+        let mut index = root
+            .filter(|_| config.incremental && !options.mode.is_inspect())
+            .and_then(|root| IncrementalIndex::open_default([root.into()]).ok());
+        if let Some(index) = index.as_ref().filter(|_| !full_run) {
+            if let Some(skipped) = index.unchanged(&options, packer.strategies(), &default_version)
+            {
+                return Ok(skipped);
+            }
+        }
+
+        let result = match &duplicate_of {
+            Some(representative) => dedup::run_duplicate(&packer, &options, representative),
+            None => process::run_upx(&packer, &options),
+        };
+        // 界面程序没有控制台，不影响处理结果的问题附加到日志信息中
+        let mut warnings = Vec::new();
+        if let (Some(index), Ok(result)) = (&mut index, &result) {
+            index.record(&options, packer.strategies(), &default_version, result);
+            if let Err(e) = index.save() {
+                warnings.push(e.to_string());
+            }
+        }
+
+        let upx_version = options.upx_version.clone().unwrap_or(default_version);
+        let record = match &result {
+            Ok(result) => HistoryRecord::new(&options, Some(upx_version), result),
+            Err(e) => HistoryRecord::failed(&options, Some(upx_version), e),
+        };
+        // 历史记录写入失败不影响处理结果
+        if let Err(e) = HistoryStore::open_default().and_then(|store| store.append(&record)) {
+            warnings.push(e.to_string());
+        }
+
+        with_warnings(result, &warnings)
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+}
+
+/// 在处理结果的日志信息（失败时为错误信息）末尾附加警告
+fn with_warnings(
+    result: Result<UpxResult, CoreError>,
+    warnings: &[String],
+) -> Result<UpxResult, String> {
+    let suffix: String = warnings
+        .iter()
+        .map(|warning| format!("\n[警告] {}", warning))
+        .collect();
+    match result {
+        Ok(mut result) => {
+            result.message.push_str(&suffix);
+            Ok(result)
+        }
+        Err(e) => Err(format!("{}{}", e, suffix)),
+    }
+}
+
+/// 找出内容相同的文件，返回 `重复文件 -> 第一个相同的文件`
+#[tauri::command]
+async fn find_identical_files(files: Vec<String>) -> Result<HashMap<String, String>, String> {
+    tokio::task::spawn_blocking(move || {
+        dedup::identical_files(&files)
+            .into_iter()
+            .map(|(duplicate, first)| (files[duplicate].clone(), files[first].clone()))
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))
+}
+
+/// 预览处理方式与 UPX 命令行，不修改任何文件
+///
+/// 参数与 [`process_upx`] 相同：按增量索引跳过未变化的文件，`duplicate_of` 为内容相同的代表文件
+#[tauri::command]
+async fn plan_upx(
+    options: UpxOptions,
+    root: Option<String>,
+    full_run: bool,
+    duplicate_of: Option<String>,
+) -> Result<PlanEntry, String> {
+    tokio::task::spawn_blocking(move || {
+        let config = load_config_or_default();
+        let strategies = Strategy::parse_for(&options, &config.tune_strategies)?;
+        let packer = AutoTuner::new(upx_packer()?, strategies);
+        let default_version = packer.inner().default_label().to_string();
+
+        let index = root
+            .filter(|_| config.incremental && !full_run && !options.mode.is_inspect())
+            .and_then(|root| IncrementalIndex::open_default([root.into()]).ok());
+        if let Some(unchanged) =
+            index.and_then(|index| index.unchanged(&options, packer.strategies(), &default_version))
+        {
+            return Ok(PlanEntry::unchanged(&unchanged));
+        }
+
+        let mut entry = plan::plan_job(&packer, &options);
+        if let Some(representative) = &duplicate_of {
+            entry.mark_duplicate(representative);
+        }
+        Ok(entry)
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e: CoreError| e.to_string())
+}
+
+/// 前端记录的批量任务中单个文件的选项与结果
+#[derive(Debug, Deserialize)]
+struct BatchItem {
+    options: UpxOptions,
+    result: UpxResult,
+}
+
+/// 导出最近一次批量处理的报告（.html 为摘要，.csv 为 CSV，其余为 JSON）
+#[tauri::command]
+async fn export_batch_report(
+    mode: UpxMode,
+    items: Vec<BatchItem>,
+    path: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = items
+            .iter()
+            .map(|item| ReportEntry::from_result(Some(&item.options), &item.result))
+            .collect();
+        let path = Path::new(&path);
+        report::write_report(
+            &BatchReport::new(mode, entries),
+            path,
+            ReportFormat::from_path(path),
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e| e.to_string())
+}
+
+/// 按路径、日期与状态查询处理历史，最新的在前
+#[tauri::command]
+async fn query_history(query: HistoryQuery) -> Result<Vec<HistoryRecord>, String> {
+    tokio::task::spawn_blocking(move || HistoryStore::open_default()?.query(&query))
+        .await
+        .map_err(|e| format!("任务执行错误: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// 把符合条件的历史导出到文件（.csv 为 CSV，其余为 JSON），返回导出的条数
+#[tauri::command]
+async fn export_history(query: HistoryQuery, path: String) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        let records = HistoryStore::open_default()?.query(&query)?;
+        let path = Path::new(&path);
+        history::export_history(&records, path, ExportFormat::from_path(path))?;
+        Ok(records.len())
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e: CoreError| e.to_string())
+}
+
+/// 按文件夹中的加壳清单校验文件，报告被修改、缺失与未登记的文件
+#[tauri::command]
+async fn verify_manifest(folder: String, include_subfolders: bool) -> Result<VerifyReport, String> {
+    tokio::task::spawn_blocking(move || {
+        manifest::verify_manifest(Path::new(&folder), include_subfolders)
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e| e.to_string())
+}
+
+/// 加壳缓存的位置与占用
+#[tauri::command]
+async fn get_cache_info() -> Result<CacheInfo, String> {
+    tokio::task::spawn_blocking(|| Ok(pack_cache(&load_config_or_default())?.info()))
+        .await
+        .map_err(|e| format!("任务执行错误: {}", e))?
+        .map_err(|e: CoreError| e.to_string())
+}
+
+/// 删除全部加壳缓存，返回清空前的占用
+#[tauri::command]
+async fn clear_cache() -> Result<CacheInfo, String> {
+    tokio::task::spawn_blocking(|| pack_cache(&load_config_or_default())?.clear())
+        .await
+        .map_err(|e| format!("任务执行错误: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// 按日期与文件夹汇总处理历史的累计统计
+#[tauri::command]
+async fn get_statistics(query: StatisticsQuery) -> Result<Statistics, String> {
+    tokio::task::spawn_blocking(move || {
+        let records = HistoryStore::open_default()?.load()?;
+        Ok(stats::compute_statistics(&records, &query))
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e: CoreError| e.to_string())
+}
+
+#[tauri::command]
+fn scan_folder(options: ScanFolderOptions) -> Result<Vec<String>, String> {
+    scan::scan_folder(&options).map_err(|e| e.to_string())
+}
+
+/// 输出目录模式下 `input` 的输出路径，`source` 为用户拖入或选择的文件、文件夹
+#[tauri::command]
+fn mirror_output_path(source: String, input: String, output_dir: String) -> Result<String, String> {
+    output_dir::mirrored_output(
+        Path::new(&source),
+        Path::new(&input),
+        Path::new(&output_dir),
+    )
+    .map(|path| path.to_string_lossy().into_owned())
+    .map_err(|e| e.to_string())
+}
+
+/// 按命名模板生成输出路径
+#[tauri::command]
+fn render_output_name(template: String, input: String) -> Result<String, String> {
+    naming::render_output_name(&template, Path::new(&input))
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| e.to_string())
+}
+
+/// 把文件夹中不处理的文件复制到输出目录，返回复制的文件数
+#[tauri::command]
+async fn copy_unsupported_files(
+    source: String,
+    include_subfolders: bool,
+    output_dir: String,
+) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        output_dir::copy_unsupported_files(
+            Path::new(&source),
+            include_subfolders,
+            Path::new(&output_dir),
+        )
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_upx_version() -> Result<String, String> {
+    upx_location()
+        .map(|location| location.version)
+        .map_err(|e| e.to_string())
+}
+
+/// 当前使用的 UPX 路径、来源与选择原因
+#[tauri::command]
+fn get_upx_location() -> Result<UpxLocation, String> {
+    upx_location().map_err(|e| e.to_string())
+}
+
+/// 默认 UPX 的压缩库版本、支持的格式与选项，用于隐藏不支持的开关
+#[tauri::command]
+fn get_upx_capabilities() -> Result<UpxCapabilities, String> {
+    upx_packer()
+        .and_then(|packer| packer.capabilities())
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// 图标缓存刷新（仅 Windows）
+// ============================================================================
+
+#[cfg(target_os = "windows")]
+fn refresh_icon_cache_internal() {
+    use std::env;
+
+    // 关闭 Explorer
+    let _ = Command::new("taskkill")
+        .args(["/f", "/im", "explorer.exe"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // 删除图标缓存
+    if let Ok(userprofile) = env::var("USERPROFILE") {
+        let cache_db = format!("{}\\AppData\\Local\\IconCache.db", userprofile);
+        let _ = fs::remove_file(cache_db);
+
+        // 删除缩略图缓存
+        let explorer_path = format!(
+            "{}\\AppData\\Local\\Microsoft\\Windows\\Explorer",
+            userprofile
+        );
+        if let Ok(entries) = fs::read_dir(explorer_path) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.starts_with("thumbcache_"))
+                    .unwrap_or(false)
+                {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+
+    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    // 重启 Explorer
+    let _ = Command::new("explorer.exe")
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn();
+}
+
+#[tauri::command]
+async fn refresh_icon_cache() -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        #[cfg(target_os = "windows")]
+        refresh_icon_cache_internal();
+    })
+    .await
+    .ok();
+
+    Ok(())
+}
+
+// ============================================================================
+// 检查更新
+// ============================================================================
+
+const GITHUB_REPO: &str = "Y-ASLant/UPX-Tools";
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    html_url: String,
+    name: String,
+    body: Option<String>,
+    published_at: String,
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateInfo {
+    has_update: bool,
+    current_version: String,
+    latest_version: String,
+    release_url: String,
+    release_name: String,
+    release_notes: String,
+    published_at: String,
+    assets: Vec<GitHubAsset>,
+}
+
+#[tauri::command]
+async fn check_update() -> Result<UpdateInfo, String> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        GITHUB_REPO
+    );
+
+    let release: GitHubRelease = tokio::task::spawn_blocking(move || http::get_github_json(&url))
+        .await
+        .map_err(|e| format!("任务执行错误: {}", e))?
+        .map_err(|e| format!("网络请求失败: {}", e))?;
+
+    // 移除版本号前的 'v' 前缀进行比较
+    let latest = release.tag_name.trim_start_matches('v');
+    let current = CURRENT_VERSION.trim_start_matches('v');
+
+    let has_update = version_compare(latest, current);
+
+    // 过滤出 Windows 相关的下载资源
+    let assets: Vec<GitHubAsset> = release
+        .assets
+        .iter()
+        .filter(|a| {
+            a.name.ends_with("-portable.exe")
+                || a.name.ends_with("-setup.exe")
+                || a.name.ends_with(".msi")
+        })
+        .cloned()
+        .collect();
+
+    Ok(UpdateInfo {
+        has_update,
+        current_version: format!("v{}", CURRENT_VERSION),
+        latest_version: release.tag_name.clone(),
+        release_url: release.html_url,
+        release_name: release.name,
+        release_notes: release.body.unwrap_or_default(),
+        published_at: release.published_at,
+        assets,
+    })
+}
+
+#[tauri::command]
+async fn download_and_install(url: String, filename: String) -> Result<String, String> {
+    // 下载文件
+    let bytes = tokio::task::spawn_blocking(move || http::download(&url, 0))
+        .await
+        .map_err(|e| format!("任务执行错误: {}", e))?
+        .map_err(|e| format!("下载失败: {}", e))?;
+
+    // 保存到临时目录
+    let temp_dir = std::env::temp_dir().join("upx-tools-update");
+    fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+    let file_path = temp_dir.join(&filename);
+    fs::write(&file_path, &bytes).map_err(|e| format!("保存文件失败: {}", e))?;
+
+    // 运行安装程序
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("cmd")
+            .args(["/C", "start", "", file_path.to_str().unwrap_or_default()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .map_err(|e| format!("启动安装程序失败: {}", e))?;
+    }
+
+    Ok(file_path.to_string_lossy().to_string())
+}
+
+/// 比较版本号，返回 latest > current
+fn version_compare(latest: &str, current: &str) -> bool {
+    let parse_version =
+        |v: &str| -> Vec<u32> { v.split('.').filter_map(|s| s.parse().ok()).collect() };
+
+    let latest_parts = parse_version(latest);
+    let current_parts = parse_version(current);
+
+    for i in 0..latest_parts.len().max(current_parts.len()) {
+        let l = latest_parts.get(i).copied().unwrap_or(0);
+        let c = current_parts.get(i).copied().unwrap_or(0);
+        if l > c {
+            return true;
+        }
+        if l < c {
+            return false;
+        }
+    }
+    false
+}
+
+// ============================================================================
+// 更新 UPX
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct UpxUpdateInfo {
+    has_update: bool,
+    /// 默认与已注册 UPX 中的最高版本
+    current_version: Option<String>,
+    latest_version: String,
+    release_url: String,
+    asset: UpxReleaseAsset,
+}
+
+/// 本机可用的最高 UPX 版本
+fn installed_upx_version(config: &AppConfig) -> Option<UpxVersion> {
+    let default = upx_location()
+        .ok()
+        .and_then(|location| UpxVersion::parse(&location.version));
+
+    config
+        .upx_binaries
+        .iter()
+        .filter_map(|b| UpxVersion::parse(&b.version))
+        .chain(default)
+        .max()
+}
+
+#[tauri::command]
+async fn check_upx_update() -> Result<UpxUpdateInfo, String> {
+    tokio::task::spawn_blocking(|| {
+        let config = config::load_config()?;
+        let release = upx_update::fetch_latest_release(&config.upx_release_api)?;
+        let latest = release.version().ok_or_else(|| {
+            CoreError::UpxUpdateRequest(format!("无法识别的 UPX 版本: {}", release.tag_name))
+        })?;
+        let asset = upx_update::select_asset(&release)?.clone();
+        let current = installed_upx_version(&config);
+
+        Ok(UpxUpdateInfo {
+            has_update: current.is_none_or(|c| latest > c),
+            current_version: current.map(|c| c.to_string()),
+            latest_version: latest.to_string(),
+            release_url: release.html_url,
+            asset,
+        })
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e: CoreError| e.to_string())
+}
+
+/// 下载最新的 UPX 发行包，校验后安装到数据目录并注册，返回更新后的列表
+#[tauri::command]
+async fn install_upx_update() -> Result<Vec<RegisteredUpx>, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut config = config::load_config()?;
+        let install_root = upx_update::upx_install_root().ok_or(CoreError::DataDirUnavailable)?;
+        upx_update::install_latest_release(
+            &config.upx_release_api,
+            &install_root,
+            &mut config.upx_binaries,
+        )?;
+        config::save_config(&config)?;
+        Ok(config.upx_binaries)
+    })
+    .await
+    .map_err(|e| format!("任务执行错误: {}", e))?
+    .map_err(|e: CoreError| e.to_string())
+}
+
+// ============================================================================
+// 配置持久化
+// ============================================================================
+
+/// 设置面板不提交已注册的 UPX 列表和发行信息接口，保留配置文件中已有的值
+#[tauri::command]
+fn save_config(mut config: AppConfig) -> Result<(), String> {
+    if let Ok(saved) = config::load_config() {
+        config.upx_binaries = saved.upx_binaries;
+        config.upx_release_api = saved.upx_release_api;
+    }
+    config::save_config(&config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn load_config() -> Result<AppConfig, String> {
+    config::load_config().map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// 多版本 UPX 注册
+// ============================================================================
+
+/// 注册 UPX 并返回更新后的列表
+#[tauri::command]
+fn register_upx(path: String) -> Result<Vec<RegisteredUpx>, String> {
+    let mut config = config::load_config().map_err(|e| e.to_string())?;
+    registry::register_upx(&mut config.upx_binaries, &path).map_err(|e| e.to_string())?;
+    config::save_config(&config).map_err(|e| e.to_string())?;
+    Ok(config.upx_binaries)
+}
+
+/// 按版本号移除已注册的 UPX 并返回更新后的列表
+#[tauri::command]
+fn unregister_upx(version: String) -> Result<Vec<RegisteredUpx>, String> {
+    let mut config = config::load_config().map_err(|e| e.to_string())?;
+    if !registry::unregister_upx(&mut config.upx_binaries, &version) {
+        return Err(CoreError::UpxVersionNotRegistered(version).to_string());
+    }
+    if config.upx_version.as_deref() == Some(version.as_str()) {
+        config.upx_version = None;
+    }
+    config::save_config(&config).map_err(|e| e.to_string())?;
+    Ok(config.upx_binaries)
+}
+
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .invoke_handler(tauri::generate_handler![
+            process_upx,
+            plan_upx,
+            find_identical_files,
+            query_history,
+            export_history,
+            get_statistics,
+            verify_manifest,
+            get_cache_info,
+            clear_cache,
+            export_batch_report,
+            scan_folder,
+            mirror_output_path,
+            render_output_name,
+            copy_unsupported_files,
+            get_upx_version,
+            get_upx_location,
+            get_upx_capabilities,
+            refresh_icon_cache,
+            save_config,
+            load_config,
+            register_upx,
+            unregister_upx,
+            check_update,
+            check_upx_update,
+            install_upx_update,
+            download_and_install
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
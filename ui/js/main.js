@@ -13,21 +13,64 @@ const PERFORMANCE_CONFIG = {
     batchSize: Math.max(2, Math.min((navigator.hardwareConcurrency || 4) * 2, 16)),
 }
 
+// 拖入的文件 -> 拖入时的文件或文件夹，输出目录模式下据此计算相对路径
+const fileSources = new Map()
+
+// 当前批量任务已占用的输出路径，用于发现写入同一文件的任务
+let batchOutputs = null
+
+// 当前批量任务每个文件的选项与结果，完成后保存到 lastBatch 用于导出报告
+let batchResults = null
+let lastBatch = null
+
+// 当前批量任务中内容相同的文件（重复文件 -> 代表文件），以及已处理文件的结果
+let batchDuplicates = null
+let batchRepresentatives = null
+
 // DOM 元素（延迟初始化）
 let compressBtn,
     decompressBtn,
     compressionLevel,
     levelDisplay,
     levelDescription,
+    autoTuneCheckbox,
+    tuneStrategiesInput,
+    sizeBudgetInput,
     overwriteCheckbox,
+    outputDirInput,
+    copyUnsupportedCheckbox,
+    nameTemplateInput,
+    onConflictSelect,
+    manifestSelect,
+    verifyManifestBtn,
+    cacheEnabledCheckbox,
+    cacheMaxMbInput,
+    clearCacheBtn,
+    dryRunCheckbox,
+    incrementalCheckbox,
+    fullRunCheckbox,
     backupCheckbox,
     lzmaCheckbox,
     ultraBruteCheckbox,
     includeSubfoldersCheckbox,
     forceCompressCheckbox,
     autoCheckUpdateCheckbox,
+    upxPathInput,
+    upxVersionSelect,
+    addUpxBtn,
+    removeUpxBtn,
+    updateUpxBtn,
+    historyPathInput,
+    historyStatusSelect,
+    historySinceInput,
+    historyUntilInput,
+    viewHistoryBtn,
+    exportHistoryBtn,
+    statsFolderInput,
+    viewStatsBtn,
     logOutput,
     clearLogBtn,
+    exportReportBtn,
     settingsModal,
     settingsBtn,
     closeSettingsBtn,
@@ -45,15 +88,44 @@ function initDOMElements() {
     compressionLevel = $('compression-level')
     levelDisplay = $('level-display')
     levelDescription = $('level-description')
+    autoTuneCheckbox = $('auto-tune')
+    tuneStrategiesInput = $('tune-strategies')
+    sizeBudgetInput = $('size-budget')
     overwriteCheckbox = $('overwrite')
+    outputDirInput = $('output-dir')
+    copyUnsupportedCheckbox = $('copy-unsupported')
+    nameTemplateInput = $('name-template')
+    onConflictSelect = $('on-conflict-select')
+    manifestSelect = $('manifest-select')
+    verifyManifestBtn = $('verify-manifest-btn')
+    cacheEnabledCheckbox = $('cache-enabled')
+    cacheMaxMbInput = $('cache-max-mb')
+    clearCacheBtn = $('clear-cache-btn')
+    dryRunCheckbox = $('dry-run')
+    incrementalCheckbox = $('incremental')
+    fullRunCheckbox = $('full-run')
     backupCheckbox = $('backup')
     lzmaCheckbox = $('lzma')
     ultraBruteCheckbox = $('ultra-brute')
     includeSubfoldersCheckbox = $('include-subfolders')
     forceCompressCheckbox = $('force-compress')
     autoCheckUpdateCheckbox = $('auto-check-update')
+    upxPathInput = $('upx-path')
+    upxVersionSelect = $('upx-version-select')
+    addUpxBtn = $('add-upx-btn')
+    removeUpxBtn = $('remove-upx-btn')
+    updateUpxBtn = $('update-upx-btn')
+    historyPathInput = $('history-path')
+    historyStatusSelect = $('history-status')
+    historySinceInput = $('history-since')
+    historyUntilInput = $('history-until')
+    viewHistoryBtn = $('view-history-btn')
+    exportHistoryBtn = $('export-history-btn')
+    statsFolderInput = $('stats-folder')
+    viewStatsBtn = $('view-stats-btn')
     logOutput = $('log-output')
     clearLogBtn = $('clear-log-btn')
+    exportReportBtn = $('export-report-btn')
     settingsModal = $('settings-modal')
     settingsBtn = $('settings-btn')
     closeSettingsBtn = $('close-settings')
@@ -79,12 +151,15 @@ document.addEventListener('DOMContentLoaded', async () => {
 
     // 获取并显示 UPX 版本
     try {
-        const version = await invoke('get_upx_version')
+        const location = await invoke('get_upx_location')
         const versionElement = $('upx-version')
-        if (versionElement) versionElement.textContent = `- ${version}`
-        addLog(`UPX GUI 已就绪 - ${version}`, 'info')
-    } catch {
+        if (versionElement) versionElement.textContent = `- ${location.version}`
+        addLog(`UPX GUI 已就绪 - ${location.version}`, 'info')
+        addLog(`UPX 路径: ${location.path}（${location.reason}）`, 'info')
+        await applyUpxCapabilities()
+    } catch (error) {
         addLog('UPX GUI 已就绪 - 请选择操作', 'info')
+        if (error) addLog(`[错误] ${error}`, 'error')
     }
 
     // 页面加载完成后显示窗口
@@ -159,6 +234,18 @@ function initOperationButtons() {
     closeSettingsBtn.addEventListener('click', handleCloseSettings)
     settingsModal.addEventListener('click', handleModalBackdropClick)
     clearLogBtn.addEventListener('click', handleClearLog)
+    exportReportBtn.addEventListener('click', handleExportReport)
+    addUpxBtn.addEventListener('click', handleAddUpx)
+    removeUpxBtn.addEventListener('click', handleRemoveUpx)
+    updateUpxBtn.addEventListener('click', handleUpdateUpx)
+    viewHistoryBtn.addEventListener('click', handleViewHistory)
+    exportHistoryBtn.addEventListener('click', handleExportHistory)
+    viewStatsBtn.addEventListener('click', handleViewStatistics)
+    verifyManifestBtn.addEventListener('click', handleVerifyManifest)
+    clearCacheBtn.addEventListener('click', handleClearCache)
+    upxVersionSelect.addEventListener('change', () => {
+        removeUpxBtn.disabled = !upxVersionSelect.value
+    })
 
     compressBtn.addEventListener('click', () => handleOperationClick('compress'))
     decompressBtn.addEventListener('click', () => handleOperationClick('decompress'))
@@ -170,6 +257,280 @@ async function handleCloseSettings() {
     addLog('设置已保存', 'success')
 }
 
+// ============================================================================
+// 处理历史
+// ============================================================================
+
+const HISTORY_VIEW_LIMIT = 200
+const HISTORY_STATUS_LABELS = {
+    success: '成功',
+    skipped: '跳过',
+    warning: '警告',
+    failed: '失败',
+}
+const HISTORY_LOG_TYPES = {
+    success: 'success',
+    skipped: 'warning',
+    warning: 'warning',
+    failed: 'error',
+}
+
+function getHistoryQuery() {
+    return {
+        path: historyPathInput.value.trim() || null,
+        since: historySinceInput.value || null,
+        until: historyUntilInput.value || null,
+        status: historyStatusSelect.value || null,
+    }
+}
+
+async function handleViewHistory() {
+    try {
+        const records = await invoke('query_history', {
+            query: { ...getHistoryQuery(), limit: HISTORY_VIEW_LIMIT },
+        })
+        hideSettingsModal()
+
+        if (records.length === 0) {
+            addLog('没有符合条件的历史记录', 'warning')
+            return
+        }
+
+        addLog(`处理历史（最新的 ${records.length} 条）`, 'info', true)
+        records.forEach((record) => {
+            const time = new Date(record.timestamp).toLocaleString()
+            const status = HISTORY_STATUS_LABELS[record.status]
+            const type = HISTORY_LOG_TYPES[record.status]
+            const detail =
+                type === 'error' || record.status === 'skipped'
+                    ? `: ${record.reason ?? ''}`
+                    : `-> ${record.output_file} (${record.original_size} -> ${record.output_size} 字节, ${record.ratio}%)`
+            addLog(`${time} [${status}] ${record.input_file} ${detail}`, type)
+        })
+    } catch (error) {
+        addLog(`读取历史记录失败: ${error}`, 'error')
+    }
+}
+
+async function handleExportHistory() {
+    try {
+        const path = await save({
+            filters: [
+                { name: 'CSV', extensions: ['csv'] },
+                { name: 'JSON', extensions: ['json'] },
+            ],
+            defaultPath: 'upx-history.csv',
+        })
+        if (!path) return
+
+        const count = await invoke('export_history', { query: getHistoryQuery(), path })
+        addLog(`已导出 ${count} 条历史记录: ${path}`, 'success')
+    } catch (error) {
+        addLog(`导出历史记录失败: ${error}`, 'error')
+    }
+}
+
+// 与核心库的 format_bytes 一致
+function formatBytes(bytes) {
+    const units = ['GB', 'MB', 'KB']
+    for (const [i, unit] of units.entries()) {
+        const size = 1024 ** (units.length - i)
+        if (bytes >= size) return `${(bytes / size).toFixed(2)} ${unit}`
+    }
+    return `${bytes} bytes`
+}
+
+const percent = (rate) => `${(rate * 100).toFixed(1)}%`
+
+async function handleViewStatistics() {
+    try {
+        const stats = await invoke('get_statistics', {
+            query: {
+                since: historySinceInput.value || null,
+                until: historyUntilInput.value || null,
+                folder: statsFolderInput.value.trim() || null,
+            },
+        })
+        hideSettingsModal()
+
+        if (stats.records === 0) {
+            addLog('没有符合条件的历史记录', 'warning')
+            return
+        }
+
+        const { success, skipped, warning, failed } = stats.summary
+        addLog(
+            `累计统计: ${stats.records} 条记录（成功 ${success}，跳过 ${skipped}，警告 ${warning}，失败 ${failed}）`,
+            'info',
+            true
+        )
+        addLog(
+            `累计节省 ${formatBytes(stats.saved_bytes)}（${formatBytes(stats.original_size)} -> ${formatBytes(stats.output_size)}）`,
+            'success'
+        )
+        stats.by_format.forEach((format) => {
+            const name = format.format || '(无扩展名)'
+            addLog(`  ${name}: ${format.files} 个文件，节省 ${formatBytes(format.saved_bytes)}`)
+        })
+        stats.by_method.forEach((method) => {
+            const ratio = method.average_ratio.toFixed(1)
+            addLog(`  ${method.method}: ${method.files} 个文件，平均压缩率 ${ratio}%`)
+        })
+        addLog(`失败率 ${percent(stats.failure_rate)}`, failed > 0 ? 'warning' : 'info')
+        stats.failures.forEach((failure) => {
+            addLog(`  ${failure.kind}: ${failure.count} 次（${percent(failure.rate)}）`, 'error')
+        })
+        if (stats.ms_per_mb !== null) {
+            addLog(`处理速度 ${Math.round(stats.ms_per_mb)} 毫秒/MB`)
+        }
+    } catch (error) {
+        addLog(`统计失败: ${error}`, 'error')
+    }
+}
+
+const DRIFT_LOG_TYPES = {
+    ok: 'success',
+    modified: 'error',
+    missing: 'error',
+    untracked: 'warning',
+}
+
+const DRIFT_LABELS = {
+    ok: '一致',
+    modified: '已修改',
+    missing: '缺失',
+    untracked: '未登记',
+}
+
+async function handleVerifyManifest() {
+    try {
+        const folder = await open({ directory: true, multiple: false })
+        if (!folder) return
+
+        const report = await invoke('verify_manifest', {
+            folder,
+            includeSubfolders: includeSubfoldersCheckbox.checked,
+        })
+        hideSettingsModal()
+
+        addLog(`按 ${report.manifests.length} 个清单校验: ${folder}`, 'info', true)
+        report.entries.forEach((entry) => {
+            const label = DRIFT_LABELS[entry.status]
+            const detail =
+                entry.status === 'modified'
+                    ? `（清单 ${entry.expected_sha256}，实际 ${entry.actual_sha256}）`
+                    : ''
+            addLog(`[${label}] ${entry.file}${detail}`, DRIFT_LOG_TYPES[entry.status])
+        })
+
+        const drift = report.entries.filter((entry) => entry.status !== 'ok').length
+        if (drift === 0) {
+            addLog(`校验通过，${report.entries.length} 个文件与清单一致`, 'success')
+        } else {
+            addLog(`校验完成，${drift} 个文件与清单不一致`, 'warning')
+        }
+    } catch (error) {
+        addLog(`校验清单失败: ${error}`, 'error')
+    }
+}
+
+async function handleClearCache() {
+    try {
+        const info = await invoke('clear_cache')
+        addLog(
+            `已清空加壳缓存: ${info.entries} 个条目，释放 ${formatBytes(info.total_bytes)}`,
+            'success'
+        )
+    } catch (error) {
+        addLog(`清空缓存失败: ${error}`, 'error')
+    }
+}
+
+async function handleAddUpx() {
+    try {
+        const selected = await open({ multiple: false })
+        if (!selected) return
+
+        const binaries = await invoke('register_upx', { path: selected })
+        const added = binaries.find((b) => b.path === selected)
+        renderUpxVersions(binaries, added?.version ?? upxVersionSelect.value)
+        addLog(`已注册 UPX ${added?.version ?? ''}: ${selected}`, 'success')
+    } catch (error) {
+        addLog(`注册 UPX 失败: ${error}`, 'error')
+    }
+}
+
+async function handleRemoveUpx() {
+    const version = upxVersionSelect.value
+    if (!version) return
+
+    try {
+        const binaries = await invoke('unregister_upx', { version })
+        renderUpxVersions(binaries, '')
+        addLog(`已移除 UPX ${version}`, 'info')
+    } catch (error) {
+        addLog(`移除 UPX 失败: ${error}`, 'error')
+    }
+}
+
+async function handleUpdateUpx() {
+    updateUpxBtn.disabled = true
+    try {
+        addLog('正在检查 UPX 更新...', 'info')
+        const info = await invoke('check_upx_update')
+        if (!info.has_update) {
+            addLog(`UPX 已是最新版本 (${info.current_version})`, 'success')
+            return
+        }
+
+        addLog(`发现 UPX ${info.latest_version}，正在下载 ${info.asset.name}...`, 'info')
+        const binaries = await invoke('install_upx_update')
+        renderUpxVersions(binaries, info.latest_version)
+        addLog(`已安装并注册 UPX ${info.latest_version}`, 'success')
+    } catch (error) {
+        addLog(`更新 UPX 失败: ${error}`, 'error')
+    } finally {
+        updateUpxBtn.disabled = false
+    }
+}
+
+// 已注册的 UPX 保存在配置文件中，这里只维护下拉框
+function renderUpxVersions(binaries, selected) {
+    upxVersionSelect.innerHTML = ''
+    upxVersionSelect.add(new Option('默认', ''))
+    for (const binary of binaries) {
+        upxVersionSelect.add(new Option(`${binary.version}  ${binary.path}`, binary.version))
+    }
+    upxVersionSelect.value = binaries.some((b) => b.version === selected) ? selected : ''
+    removeUpxBtn.disabled = !upxVersionSelect.value
+}
+
+// 隐藏当前 UPX 不支持的开关，并记录压缩库版本
+async function applyUpxCapabilities() {
+    const caps = await invoke('get_upx_capabilities')
+    const libraries = [
+        ['UCL', caps.ucl],
+        ['zlib', caps.zlib],
+        ['LZMA', caps.lzma],
+    ]
+        .filter(([, version]) => version)
+        .map(([name, version]) => `${name} ${version}`)
+    if (libraries.length) addLog(`UPX 压缩库: ${libraries.join(', ')}`, 'info')
+
+    // 帮助信息为空时无法判断，全部保留
+    const supports = (option) => caps.options.length === 0 || caps.options.includes(option)
+    const switches = [
+        [lzmaCheckbox, '--lzma'],
+        [ultraBruteCheckbox, '--ultra-brute'],
+        [forceCompressCheckbox, '--force'],
+    ]
+    for (const [checkbox, option] of switches) {
+        if (supports(option)) continue
+        checkbox.checked = false
+        checkbox.closest('.switch-row').style.display = 'none'
+    }
+}
+
 async function handleModalBackdropClick(e) {
     if (e.target === settingsModal) {
         await saveCurrentConfig()
@@ -214,6 +575,15 @@ const LEVEL_DESCRIPTIONS = {
 // 更新压缩级别显示
 function updateLevelDisplay(value) {
     const level = parseInt(value)
+    const auto = autoTuneCheckbox.checked
+    compressionLevel.disabled = auto
+    // 设置体积预算时同样按候选压缩方式逐个尝试
+    tuneStrategiesInput.disabled = !auto && !getSizeBudget()
+    if (auto) {
+        levelDisplay.textContent = '自动'
+        levelDescription.textContent = '逐个尝试候选压缩方式，保留最小的结果'
+        return
+    }
     levelDisplay.textContent = level === 10 ? '级别 best' : `级别 ${level}`
     levelDescription.textContent = LEVEL_DESCRIPTIONS[level] || ''
 }
@@ -222,10 +592,28 @@ function updateLevelDisplay(value) {
 function initCompressionLevelSlider() {
     updateLevelDisplay(compressionLevel.value)
     compressionLevel.addEventListener('input', (e) => updateLevelDisplay(e.target.value))
+    autoTuneCheckbox.addEventListener('change', () => updateLevelDisplay(compressionLevel.value))
+    sizeBudgetInput.addEventListener('change', () => {
+        const value = sizeBudgetInput.value.trim()
+        if (value && !getSizeBudget()) {
+            addLog(`[警告] 体积预算格式无效: ${value}，例如 512KB、1.5MB 或 60%`, 'warning')
+        }
+        updateLevelDisplay(compressionLevel.value)
+    })
 }
 
-// 获取当前压缩级别值
+// 与 SizeBudget 的解析规则一致：字节数（可带 K/M/G 单位）或 1-100 的百分比
+const SIZE_BUDGET_PATTERN = /^(\d+(\.\d+)?\s*(b|k|kb|kib|m|mb|mib|g|gb|gib)?|(100|[1-9]\d?)\s*%)$/i
+
+// 获取体积预算，未填写或格式无效时为 null
+function getSizeBudget() {
+    const value = sizeBudgetInput.value.trim()
+    return SIZE_BUDGET_PATTERN.test(value) && !/^0+(\.0*)?\D*$/.test(value) ? value : null
+}
+
+// 获取当前压缩级别值，自动选择时为 auto
 function getCompressionLevel() {
+    if (autoTuneCheckbox.checked) return 'auto'
     const value = parseInt(compressionLevel.value)
     return value === 10 ? 'best' : value.toString()
 }
@@ -275,34 +663,109 @@ async function processBatchFiles(files, mode) {
         'info'
     )
 
+    await copyUnsupportedFiles(files)
+    batchOutputs = new Map()
+    batchResults = dryRunCheckbox.checked ? null : []
+    // 预览同样标出内容相同的文件，查找只读取文件
+    batchDuplicates = await findIdenticalFiles(files)
+    batchRepresentatives = new Map()
+
     const handler = mode === 'compress' ? handleCompressWithFile : handleDecompressWithFile
-    let successCount = 0
-    let failCount = 0
+    const counts = { success: 0, skipped: 0, warning: 0, failed: 0 }
     const batchSize = PERFORMANCE_CONFIG.batchSize
 
-    for (let i = 0; i < files.length; i += batchSize) {
-        const batch = files.slice(i, Math.min(i + batchSize, files.length))
-        addLog(
-            `处理进度: ${i + 1}-${Math.min(i + batchSize, files.length)}/${files.length}`,
-            'info'
-        )
+    // 先处理代表文件，内容相同的文件随后复制其结果
+    const ordered = [
+        ...files.filter((file) => !batchDuplicates.has(file)),
+        ...files.filter((file) => batchDuplicates.has(file)),
+    ]
+    const firstDuplicate = ordered.length - batchDuplicates.size
+
+    for (let i = 0; i < ordered.length; ) {
+        const end = Math.min(i + batchSize, i < firstDuplicate ? firstDuplicate : ordered.length)
+        const batch = ordered.slice(i, end)
+        addLog(`处理进度: ${i + 1}-${end}/${ordered.length}`, 'info')
 
         await Promise.all(
             batch.map(async (file) => {
-                try {
-                    await handler(file)
-                    successCount++
-                } catch {
+                const status = await handler(file)
+                if (status in counts) {
+                    counts[status]++
+                } else {
                     addLog(`处理失败: ${file}`, 'error')
-                    failCount++
+                    counts.failed++
                 }
             })
         )
 
+        i = end
         await new Promise((resolve) => setTimeout(resolve, 0))
     }
 
-    addLog(`批量处理完成! 成功: ${successCount} 个，失败: ${failCount} 个`, 'success', true)
+    const deduplicated = [...batchRepresentatives.values()].filter((r) => r.duplicate_of)
+    batchOutputs = null
+    batchDuplicates = null
+    batchRepresentatives = null
+    if (batchResults) {
+        lastBatch = { mode, items: batchResults }
+        batchResults = null
+        exportReportBtn.disabled = false
+    }
+    addLog(
+        `${dryRunCheckbox.checked ? '预览完成（未修改任何文件）' : '批量处理完成'}! 成功: ${counts.success} 个，跳过: ${counts.skipped} 个，警告: ${counts.warning} 个，失败: ${counts.failed} 个`,
+        counts.failed > 0 ? 'warning' : 'success',
+        true
+    )
+    if (deduplicated.length > 0) {
+        const bytes = deduplicated.reduce((sum, r) => sum + r.original_size, 0)
+        addLog(
+            `去重: ${deduplicated.length} 个文件与其他文件内容相同，已直接复制结果，未运行 UPX（共 ${formatBytes(bytes)}）`,
+            'info'
+        )
+    }
+}
+
+// 内容相同的文件：重复文件 -> 第一个相同的文件；查找失败时不去重
+async function findIdenticalFiles(files) {
+    try {
+        return new Map(Object.entries(await invoke('find_identical_files', { files })))
+    } catch (error) {
+        addLog(`查找相同文件失败: ${error}`, 'warning')
+        return new Map()
+    }
+}
+
+// 不覆盖原文件且设置了输出目录时返回该目录
+function getOutputDir() {
+    return overwriteCheckbox.checked ? '' : outputDirInput.value.trim()
+}
+
+async function mirrorOutputPath(inputFile, outputDir) {
+    return invoke('mirror_output_path', {
+        source: fileSources.get(inputFile) ?? inputFile,
+        input: inputFile,
+        outputDir,
+    })
+}
+
+// 输出目录模式下，把拖入文件夹中的其他文件也复制过去
+async function copyUnsupportedFiles(files) {
+    const outputDir = getOutputDir()
+    if (!outputDir || !copyUnsupportedCheckbox.checked || dryRunCheckbox.checked) return
+
+    const sources = new Set(files.map((file) => fileSources.get(file) ?? file))
+    for (const source of sources) {
+        try {
+            const count = await invoke('copy_unsupported_files', {
+                source,
+                includeSubfolders: includeSubfoldersCheckbox.checked,
+                outputDir,
+            })
+            if (count > 0) addLog(`已复制 ${count} 个其他文件: ${source}`, 'info')
+        } catch (error) {
+            addLog(`复制其他文件失败: ${error}`, 'error')
+        }
+    }
 }
 
 async function checkAndScanPath(path) {
@@ -370,6 +833,7 @@ async function collectFiles(paths) {
     const allFiles = []
     for (const path of paths) {
         const files = await checkAndScanPath(path)
+        files.forEach((file) => fileSources.set(file, path))
         allFiles.push(...files)
     }
     return allFiles
@@ -465,9 +929,17 @@ async function handleCompressWithFile(inputFile) {
     try {
         let outputFile
 
+        const outputDir = getOutputDir()
+
         if (overwriteCheckbox.checked) {
             outputFile = inputFile
             addLog('将覆盖原文件', 'info')
+        } else if (outputDir) {
+            outputFile = await applyNameTemplate(await mirrorOutputPath(inputFile, outputDir))
+            addLog(`输出文件: ${outputFile}`, 'info')
+        } else if (nameTemplateInput.value.trim()) {
+            outputFile = await applyNameTemplate(inputFile)
+            addLog(`输出文件: ${outputFile}`, 'info')
         } else {
             const ext = inputFile.substring(inputFile.lastIndexOf('.'))
             const baseName = inputFile.substring(0, inputFile.lastIndexOf('.'))
@@ -480,38 +952,105 @@ async function handleCompressWithFile(inputFile) {
 
             if (!outputFile) {
                 addLog('未选择输出位置', 'warning')
-                return
+                return 'skipped'
             }
 
             addLog(`输出文件: ${outputFile}`, 'info')
+            // 保存对话框已确认过覆盖
+            return await processUpx('compress', inputFile, outputFile, 'overwrite')
         }
 
-        await processUpx('compress', inputFile, outputFile)
+        return await processUpx('compress', inputFile, outputFile)
     } catch (error) {
         addLog(`操作失败: ${error}`, 'error')
+        return 'failed'
     }
 }
 
 async function handleDecompressWithFile(inputFile) {
     try {
+        const outputDir = getOutputDir()
+        if (outputDir) {
+            const outputFile = await mirrorOutputPath(inputFile, outputDir)
+            addLog(`输出文件: ${outputFile}`, 'info')
+            return await processUpx('decompress', inputFile, outputFile)
+        }
+
         addLog('将覆盖原文件', 'info')
-        await processUpx('decompress', inputFile, inputFile)
+        return await processUpx('decompress', inputFile, inputFile)
     } catch (error) {
         addLog(`操作失败: ${error}`, 'error')
+        return 'failed'
+    }
+}
+
+// 设置了命名模板时按模板生成输出文件名
+async function applyNameTemplate(path) {
+    const template = nameTemplateInput.value.trim()
+    return template ? invoke('render_output_name', { template, input: path }) : path
+}
+
+// 批量处理中两个文件写入同一输出路径时，后一个视为失败，返回失败原因
+function claimBatchOutput(inputFile, outputFile) {
+    if (!batchOutputs || inputFile === outputFile) return null
+
+    const key = outputFile.toLowerCase()
+    const first = batchOutputs.get(key)
+    if (first) return `与 ${first} 的输出路径相同`
+
+    batchOutputs.set(key, inputFile)
+    return null
+}
+
+// 与 UpxResult::failed 结构相同，用于未能调用 UPX 的文件
+function failedResult(options, message) {
+    return {
+        status: 'failed',
+        input_file: options.input_file,
+        output_file: options.output_file,
+        original_size: 0,
+        output_size: 0,
+        ratio: 100,
+        exit_code: null,
+        duration_ms: 0,
+        reason: message,
+        message,
     }
 }
 
-async function processUpx(mode, inputFile, outputFile) {
+async function processUpx(mode, inputFile, outputFile, onConflict = onConflictSelect.value) {
+    const options = {
+        mode,
+        input_file: inputFile,
+        output_file: outputFile,
+        compression_level: getCompressionLevel(),
+        backup: backupCheckbox.checked,
+        lzma: lzmaCheckbox.checked,
+        ultra_brute: ultraBruteCheckbox.checked,
+        force: forceCompressCheckbox.checked,
+        upx_version: upxVersionSelect.value || null,
+        on_conflict: onConflict,
+        manifest: manifestSelect.value,
+        budget: mode === 'compress' ? getSizeBudget() : null,
+    }
+
+    const conflict = claimBatchOutput(inputFile, outputFile)
+    if (conflict) {
+        addLog(`[错误] ${inputFile}: ${conflict}`, 'error')
+        batchResults?.push({ options, result: failedResult(options, conflict) })
+        return 'failed'
+    }
+
     try {
-        const options = {
-            mode,
-            input_file: inputFile,
-            output_file: outputFile,
-            compression_level: getCompressionLevel(),
-            backup: backupCheckbox.checked,
-            lzma: lzmaCheckbox.checked,
-            ultra_brute: ultraBruteCheckbox.checked,
-            force: forceCompressCheckbox.checked,
+        if (dryRunCheckbox.checked) {
+            return logPlanEntry(
+                await invoke('plan_upx', {
+                    options,
+                    root: fileSources.get(inputFile) ?? null,
+                    fullRun: fullRunCheckbox.checked,
+                    duplicateOf: batchDuplicates?.get(inputFile) ?? null,
+                })
+            )
         }
 
         if (lzmaCheckbox.checked) {
@@ -529,10 +1068,85 @@ async function processUpx(mode, inputFile, outputFile) {
         const actionName = mode === 'compress' ? '加壳压缩' : '脱壳解压'
         addLog(`开始${actionName}...`, 'info')
 
-        const result = await invoke('process_upx', { options })
-        parseProcessResult(result)
+        const result = await invoke('process_upx', {
+            options,
+            root: fileSources.get(inputFile) ?? null,
+            fullRun: fullRunCheckbox.checked,
+            duplicateOf: batchRepresentatives?.get(batchDuplicates?.get(inputFile)) ?? null,
+        })
+        batchRepresentatives?.set(inputFile, result)
+        logProcessResult(result)
+        batchResults?.push({ options, result })
+        return result.status
     } catch (error) {
         parseProcessError(String(error))
+        batchResults?.push({ options, result: failedResult(options, String(error)) })
+        return 'failed'
+    }
+}
+
+// 导出最近一次批量处理的报告，按扩展名选择 HTML、CSV 或 JSON
+async function handleExportReport() {
+    if (!lastBatch) return
+
+    try {
+        const path = await save({
+            filters: [
+                { name: 'HTML 摘要', extensions: ['html'] },
+                { name: 'CSV', extensions: ['csv'] },
+                { name: 'JSON', extensions: ['json'] },
+            ],
+            defaultPath: 'upx-report.html',
+        })
+        if (!path) return
+
+        await invoke('export_batch_report', { mode: lastBatch.mode, items: lastBatch.items, path })
+        addLog(`已导出报告: ${path}`, 'success')
+    } catch (error) {
+        addLog(`导出报告失败: ${error}`, 'error')
+    }
+}
+
+// 输出预览结果，返回对应的处理状态用于汇总
+function logPlanEntry(entry) {
+    const labels = {
+        pack: '加壳',
+        unpack: '脱壳',
+        test: '测试',
+        list: '列出',
+        skip: '跳过',
+        fail: '失败',
+    }
+    const tag = `[预览][${labels[entry.action]}]`
+
+    if (entry.action === 'fail') {
+        addLog(`${tag} ${entry.input_file}: ${entry.reason}`, 'error')
+        return 'failed'
+    }
+    if (entry.action === 'skip') {
+        addLog(`${tag} ${entry.input_file}: ${entry.reason}`, 'warning')
+        return 'skipped'
+    }
+
+    addLog(`${tag} ${entry.input_file} -> ${entry.output_file}`, 'info', true)
+    addLog(`命令: ${entry.command_line}`, 'info')
+    if (entry.backup_file) addLog(`备份: ${entry.backup_file}`, 'info')
+    entry.warnings.forEach((warning) => addLog(`[警告] ${warning}`, 'warning'))
+    return entry.warnings.length > 0 ? 'warning' : 'success'
+}
+
+// 按处理状态输出日志
+function logProcessResult(result) {
+    switch (result.status) {
+        case 'success':
+        case 'warning':
+            parseProcessResult(result.message)
+            break
+        case 'skipped':
+            addLog(result.message, 'warning')
+            break
+        default:
+            parseProcessError(result.message)
     }
 }
 
@@ -541,7 +1155,7 @@ function parseProcessResult(result) {
         { patterns: ['操作成功', '操作完成'], type: 'success', highlight: true },
         { patterns: ['输出:', '大小:', '压缩率:'], type: 'success', highlight: false },
         { patterns: ['UPX 输出:'], type: 'info', highlight: false },
-        { patterns: ['扫描', '检测'], type: 'warning', highlight: false },
+        { patterns: ['扫描', '检测', '[警告]'], type: 'warning', highlight: false },
     ]
 
     result.split('\n').forEach((line) => {
@@ -881,13 +1495,29 @@ async function saveCurrentConfig() {
     try {
         const config = {
             compression_level: parseInt(compressionLevel.value),
+            auto_tune: autoTuneCheckbox.checked,
+            tune_strategies: tuneStrategiesInput.value
+                .split(',')
+                .map((s) => s.trim())
+                .filter(Boolean),
+            size_budget: getSizeBudget(),
             overwrite: overwriteCheckbox.checked,
+            output_dir: outputDirInput.value.trim() || null,
+            copy_unsupported: copyUnsupportedCheckbox.checked,
+            name_template: nameTemplateInput.value.trim() || null,
+            on_conflict: onConflictSelect.value,
+            manifest: manifestSelect.value,
+            cache_enabled: cacheEnabledCheckbox.checked,
+            cache_max_mb: Math.max(1, parseInt(cacheMaxMbInput.value) || 2048),
+            incremental: incrementalCheckbox.checked,
             backup: backupCheckbox.checked,
             lzma: lzmaCheckbox.checked,
             ultra_brute: ultraBruteCheckbox.checked,
             include_subfolders: includeSubfoldersCheckbox.checked,
             force_compress: forceCompressCheckbox.checked,
             auto_check_update: autoCheckUpdateCheckbox.checked,
+            upx_path: upxPathInput.value.trim() || null,
+            upx_version: upxVersionSelect.value || null,
         }
 
         await invoke('save_config', { config })
@@ -898,13 +1528,26 @@ async function saveCurrentConfig() {
 
 function applyConfigToUI(config) {
     compressionLevel.value = config.compression_level
+    autoTuneCheckbox.checked = config.auto_tune || false
+    tuneStrategiesInput.value = (config.tune_strategies || []).join(', ')
+    sizeBudgetInput.value = config.size_budget || ''
     overwriteCheckbox.checked = config.overwrite
+    outputDirInput.value = config.output_dir || ''
+    copyUnsupportedCheckbox.checked = config.copy_unsupported || false
+    nameTemplateInput.value = config.name_template || ''
+    onConflictSelect.value = config.on_conflict || 'skip'
+    manifestSelect.value = config.manifest || 'off'
+    cacheEnabledCheckbox.checked = config.cache_enabled || false
+    cacheMaxMbInput.value = config.cache_max_mb || 2048
+    incrementalCheckbox.checked = config.incremental || false
     backupCheckbox.checked = config.backup
     lzmaCheckbox.checked = config.lzma || false
     ultraBruteCheckbox.checked = config.ultra_brute
     includeSubfoldersCheckbox.checked = config.include_subfolders
     forceCompressCheckbox.checked = config.force_compress
     autoCheckUpdateCheckbox.checked = config.auto_check_update !== false
+    upxPathInput.value = config.upx_path || ''
+    renderUpxVersions(config.upx_binaries || [], config.upx_version || '')
     updateLevelDisplay(config.compression_level)
 }
 